@@ -0,0 +1,148 @@
+/*
+ * C ABI of era-js, implemented in src/ffi.rs.
+ *
+ * Every fallible function returns ERA_OK or one of the ERA_* error codes below and keeps
+ * a message for era_last_error(), which a later success clears. Objects are opaque handles released with their _free
+ * function, strings with era_string_free() and byte buffers with era_bytes_free().
+ * Keep this header in sync with src/ffi.rs and ErrorCode::ALL in src/errors.rs.
+ */
+#ifndef ERA_H
+#define ERA_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes, the position of the code in ErrorCode::ALL plus one. Never renumbered. */
+#define ERA_OK 0
+#define ERA_WRONG_PIN 1
+#define ERA_MISSING_SHARD 2
+#define ERA_CORRUPT_SHARD 3
+#define ERA_SHARD_MISMATCH 4
+#define ERA_SEED_MISMATCH 5
+#define ERA_UNSUPPORTED_VERSION 6
+#define ERA_INVALID_LAYOUT 7
+#define ERA_INVALID_PATH 8
+#define ERA_INVALID_MNEMONIC 9
+#define ERA_INVALID_KEY 10
+#define ERA_INVALID_INPUT 11
+#define ERA_RATE_LIMITED 12
+#define ERA_RANDOMNESS_ERROR 13
+#define ERA_INTERNAL 14
+
+typedef struct BaseWallet EraWallet;
+typedef struct Signer EraSigner;
+typedef struct Account EraAccount;
+typedef struct PolkadotSigner EraPolkadotSigner;
+typedef struct EthereumSigner EraEthereumSigner;
+typedef struct BitcoinSigner EraBitcoinSigner;
+typedef struct SolanaSigner EraSolanaSigner;
+typedef struct CosmosSigner EraCosmosSigner;
+
+/* A byte buffer owned by the library */
+typedef struct EraBytes {
+    uint8_t *data;
+    size_t len;
+} EraBytes;
+
+/* "CODE: message" of the last error on this thread, or NULL after a success */
+char *era_last_error(void);
+void era_string_free(char *value);
+void era_bytes_free(EraBytes value);
+
+/* layout_json may be NULL for the default 2 data + 3 parity layout */
+int32_t era_wallet_new(const char *layout_json, EraWallet **out);
+int32_t era_wallet_insert_shard(EraWallet *wallet, const char *name, const char *shard_base64);
+int32_t era_wallet_reconstruct(const EraWallet *wallet, EraBytes *out);
+/* decrypts the project shard with the pin, or the recovery shard when recovery is set */
+int32_t era_wallet_build_signer(EraWallet *wallet, const char *password, bool recovery, EraSigner **out);
+int32_t era_wallet_verify_key(const EraWallet *wallet, const char *seed_hash, bool *out);
+void era_wallet_free(EraWallet *wallet);
+
+int32_t era_signer_mnemonic(const EraSigner *signer, char **out);
+/* uri is a derivation like "//polkadot//0", "//hard/soft" or "//0///password" */
+int32_t era_signer_to_polkadot(const EraSigner *signer, const char *uri, EraPolkadotSigner **out);
+/* scheme is sr25519, ed25519 or ecdsa, era_signer_to_polkadot is sr25519 */
+int32_t era_signer_to_substrate(const EraSigner *signer, const char *scheme, const char *uri,
+                                EraPolkadotSigner **out);
+/* path is BIP-32, m/44'/60'/0'/0/i for ethereum account i */
+int32_t era_signer_to_ethereum(const EraSigner *signer, const char *path, EraEthereumSigner **out);
+/* network is mainnet, testnet, signet or regtest */
+int32_t era_signer_to_bitcoin(const EraSigner *signer, const char *network, EraBitcoinSigner **out);
+/* the ed25519 key of solana account index, at m/44'/501'/index'/0' */
+int32_t era_signer_to_solana(const EraSigner *signer, uint32_t index, EraSolanaSigner **out);
+/* prefix is the bech32 prefix of the chain, coin_type 118 for the cosmos hub and most chains; 0 selects 118 */
+int32_t era_signer_to_cosmos(const EraSigner *signer, const char *prefix, uint32_t index, uint32_t coin_type, EraCosmosSigner **out);
+void era_signer_free(EraSigner *signer);
+
+int32_t era_account_from_mnemonic(const char *mnemonic, EraAccount **out);
+/* JSON KeyObject: private_key, public_key, mnemonic, path, index, depth */
+int32_t era_account_derive(const EraAccount *account, const char *path, char **out_json);
+void era_account_free(EraAccount *account);
+
+/* JSON KeyObject: private_key, public_key, address, path, scheme, ss58_prefix */
+int32_t era_polkadot_key(const EraPolkadotSigner *signer, char **out_json);
+/* 64 byte signatures, 65 for ecdsa */
+int32_t era_polkadot_sign(const EraPolkadotSigner *signer, const uint8_t *message, size_t len, EraBytes *out);
+/* the signature prefixed with its MultiSignature variant: 0 ed25519, 1 sr25519, 2 ecdsa */
+int32_t era_polkadot_multi_signature(const EraPolkadotSigner *signer, const uint8_t *message, size_t len, EraBytes *out);
+/* the SignerPayloadJSON of polkadot.js { address, method, nonce, tip, era, blockNumber, blockHash,
+   specVersion, transactionVersion, genesisHash, signedExtensions, mode, metadataHash, version: 4 or 5 },
+   returns JSON { extrinsic, hash } */
+int32_t era_polkadot_sign_extrinsic(const EraPolkadotSigner *signer, const char *extrinsic_json, char **out_json);
+/* network is an SS58 registry name (polkadot, kusama, substrate, ...) or a prefix number */
+int32_t era_polkadot_set_network(EraPolkadotSigner *signer, const char *network);
+void era_polkadot_free(EraPolkadotSigner *signer);
+
+int32_t era_ss58_reencode(const char *address, const char *network, char **out);
+/* network may be NULL to accept any network. JSON { prefix, network, account_id } */
+int32_t era_ss58_validate(const char *address, const char *network, char **out_json);
+
+/* JSON KeyObject: private_key, public_key, address (EIP-55), path */
+int32_t era_ethereum_key(const EraEthereumSigner *signer, char **out_json);
+/* signatures are the 0x hex of r || s || v, with v = 27 or 28 */
+int32_t era_ethereum_personal_sign(const EraEthereumSigner *signer, const uint8_t *message, size_t len, char **out);
+int32_t era_ethereum_sign_hash(const EraEthereumSigner *signer, const uint8_t hash[32], char **out);
+/* transaction_json is the object of EthereumSigner.sign_transaction, the result is JSON { raw, hash } */
+int32_t era_ethereum_sign_transaction(const EraEthereumSigner *signer, const char *transaction_json, char **out_json);
+/* typed_data_json is the JSON of eth_signTypedData_v4 */
+int32_t era_ethereum_sign_typed_data(const EraEthereumSigner *signer, const char *typed_data_json, char **out);
+/* JSON { domain_separator, struct_hash, hash }, struct_hash is null for an EIP712Domain primary type */
+int32_t era_ethereum_hash_typed_data(const char *typed_data_json, char **out_json);
+void era_ethereum_free(EraEthereumSigner *signer);
+
+/* address_type is p2pkh, p2sh-p2wpkh, p2wpkh or p2tr, index the receive address along BIP-44/49/84/86.
+   JSON KeyObject: private_key (WIF), public_key, address, address_type, path */
+int32_t era_bitcoin_key(const EraBitcoinSigner *signer, const char *address_type, uint32_t index, char **out_json);
+/* signs the inputs whose BIP-32 derivations belong to the wallet, in and out are base64 */
+int32_t era_bitcoin_sign_psbt(const EraBitcoinSigner *signer, const char *psbt_base64, char **out);
+void era_bitcoin_free(EraBitcoinSigner *signer);
+
+/* JSON KeyObject: private_key (base58 keypair), public_key, address (base58), path */
+int32_t era_solana_key(const EraSolanaSigner *signer, char **out_json);
+/* message is a serialized legacy or v0 message, the signature is 64 bytes */
+int32_t era_solana_sign_message(const EraSolanaSigner *signer, const uint8_t *message, size_t len, EraBytes *out);
+/* fills the signature slot of the signer in a serialized transaction */
+int32_t era_solana_sign_transaction(const EraSolanaSigner *signer, const uint8_t *transaction, size_t len, EraBytes *out);
+int32_t era_solana_sign_offchain_message(const EraSolanaSigner *signer, const char *message, EraBytes *out);
+void era_solana_free(EraSolanaSigner *signer);
+
+/* JSON KeyObject: private_key, public_key (compressed), address (bech32), path */
+int32_t era_cosmos_key(const EraCosmosSigner *signer, char **out_json);
+/* the results are the JSON StdSignature { pub_key: { type, value }, signature } with base64 values */
+int32_t era_cosmos_sign_direct(const EraCosmosSigner *signer, const uint8_t *body_bytes, size_t body_len,
+                               const uint8_t *auth_info_bytes, size_t auth_info_len, const char *chain_id,
+                               uint64_t account_number, char **out_json);
+/* doc_json is the StdSignDoc { chain_id, account_number, sequence, fee, msgs, memo } */
+int32_t era_cosmos_sign_amino(const EraCosmosSigner *signer, const char *doc_json, char **out_json);
+void era_cosmos_free(EraCosmosSigner *signer);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ERA_H */
@@ -31,7 +31,7 @@ async function main() {
 
 	console.log(base_wallet.verify_key(result.seed_hash));
 
-	let polkadot_signer: era.PolkadotSigner = key.to_polkadot_signer();
+	let polkadot_signer: era.PolkadotSigner = key.to_polkadot_signer("//polkadot//0");
 	console.log(polkadot_signer.fetch_key());
 	
 	let signer = new Signer(polkadot_signer.fetch_key().private_key);
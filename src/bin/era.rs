@@ -0,0 +1,285 @@
+//! `era`, a command line tool for the shard and key operations of era-js.
+//!
+//! Every command prints JSON on stdout. Secrets (pins, passwords, mnemonics, decrypted shards)
+//! are read from stdin, one per line, so they never show up in the shell history or process list.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::process;
+
+use era_js::crypto::envelope::ShardEnvelope;
+use era_js::era::cryptography::{decrypt_shard, encrypt_shard};
+use era_js::{
+    general_purpose, Account, BaseWallet, Ecdsa, Engine, EraError, ErrorCode, KdfParams, Signer,
+    WalletLayout,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+const USAGE: &str = "\
+Usage: era <command> [options]
+
+Commands:
+  split     Split entropy into sealed shards
+              stdin: entropy (hex) or mnemonic, pin, recovery password
+              --layout <json>    wallet layout, defaults to 2 data + 3 parity shards
+              --kdf <json>       {memory_kib, iterations, parallelism} of Argon2id
+  combine   Rebuild the entropy from shards
+              --shard <name>=<base64>   repeat for every shard
+              --layout <json>           layout the shards were split with
+              --open <name>             decrypt this slot first, stdin: its pin or password
+  verify    Check that shards rebuild a seed hash
+              --seed-hash <hex>, and the options of combine
+  encrypt   Encrypt a shard with Argon2id
+              stdin: password, base64 shard
+              --kdf <json>
+  decrypt   Decrypt a sealed shard or enveloped shard
+              --shard <base64>, stdin: password
+  derive    Derive keys from a mnemonic
+              polkadot [--uri <uri>]    an sr25519 key at a secret URI derivation,
+                                        defaults to //polkadot//0
+              bip32 [--path <path>]     a BIP-32 extended key, defaults to m
+              stdin: mnemonic
+";
+
+/// Command line arguments after the command name
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, EraError> {
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| invalid_input(format!("Missing value for --{}", name)))?;
+                    options.push((name.to_string(), value));
+                }
+                None => positional.push(arg),
+            }
+        }
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, EraError> {
+        self.option(name)
+            .ok_or_else(|| invalid_input(format!("Missing --{}", name)))
+    }
+
+    fn all(&self, name: &str) -> impl Iterator<Item = &str> {
+        let name = name.to_string();
+        self.options
+            .iter()
+            .filter(move |(option, _)| *option == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Secrets read from stdin, one per line
+struct Secrets {
+    lines: Vec<Zeroizing<String>>,
+    next: usize,
+}
+
+impl Secrets {
+    fn read() -> Result<Self, EraError> {
+        let mut input = Zeroizing::new(String::new());
+        io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|e| invalid_input(format!("Could not read stdin: {}", e)))?;
+        let lines = input
+            .lines()
+            .map(|line| Zeroizing::new(line.to_string()))
+            .collect();
+        Ok(Self { lines, next: 0 })
+    }
+
+    fn line(&mut self, what: &str) -> Result<Zeroizing<String>, EraError> {
+        let line = self
+            .lines
+            .get(self.next)
+            .cloned()
+            .ok_or_else(|| invalid_input(format!("Expected the {} on stdin", what)))?;
+        self.next += 1;
+        Ok(line)
+    }
+}
+
+fn invalid_input(message: impl Into<String>) -> EraError {
+    EraError::new(ErrorCode::InvalidInput, message)
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(value: &str, what: &str) -> Result<T, EraError> {
+    serde_json::from_str(value).map_err(|e| invalid_input(format!("Invalid {}: {}", what, e)))
+}
+
+fn decode_base64(value: &str, what: &str) -> Result<Vec<u8>, EraError> {
+    general_purpose::STANDARD.decode(value.trim()).map_err(|e| {
+        EraError::new(
+            ErrorCode::CorruptShard,
+            format!("Invalid base64 {}: {}", what, e),
+        )
+    })
+}
+
+fn to_json(value: &impl Serialize) -> Result<Value, EraError> {
+    serde_json::to_value(value).map_err(|e| EraError::new(ErrorCode::Internal, e.to_string()))
+}
+
+fn layout(args: &Args) -> Result<WalletLayout, EraError> {
+    match args.option("layout") {
+        Some(layout) => parse_json(layout, "wallet layout"),
+        None => Ok(WalletLayout::default()),
+    }
+}
+
+fn kdf(args: &Args) -> Result<KdfParams, EraError> {
+    match args.option("kdf") {
+        Some(kdf) => parse_json(kdf, "key derivation parameters"),
+        None => Ok(KdfParams::default()),
+    }
+}
+
+/// Builds a wallet from the `--shard` options, decrypting the `--open` slot
+fn wallet(args: &Args) -> Result<BaseWallet, EraError> {
+    let mut wallet = BaseWallet::with_layout(layout(args)?)?;
+    for shard in args.all("shard") {
+        let (name, shard) = shard
+            .split_once('=')
+            .ok_or_else(|| invalid_input("Expected --shard <name>=<base64>"))?;
+        wallet.insert_shard(name, decode_base64(shard, "shard")?)?;
+    }
+    if let Some(slot) = args.option("open") {
+        let password = Secrets::read()?.line("pin or password")?;
+        wallet.decrypt_slot(slot, &password)?;
+    }
+    Ok(wallet)
+}
+
+fn split(args: &Args) -> Result<Value, EraError> {
+    let mut secrets = Secrets::read()?;
+    let secret = secrets.line("entropy or mnemonic")?;
+    let pin = secrets.line("pin")?;
+    let recovery_password = secrets.line("recovery password")?;
+    let entropy = match hex::decode(secret.trim_start_matches("0x")) {
+        Ok(entropy) => Zeroizing::new(entropy),
+        Err(_) => {
+            let mnemonic = bip39::Mnemonic::from_phrase(&secret, bip39::Language::English)
+                .map_err(|e| EraError::new(ErrorCode::InvalidMnemonic, e.to_string()))?;
+            Zeroizing::new(mnemonic.entropy().to_vec())
+        }
+    };
+    let shards = BaseWallet::split_entropy(
+        &entropy,
+        &pin,
+        &recovery_password,
+        &layout(args)?,
+        &kdf(args)?,
+    )?;
+    to_json(&shards)
+}
+
+fn combine(args: &Args) -> Result<Value, EraError> {
+    let entropy = wallet(args)?.reconstruct_shards_internal()?;
+    Ok(json!({
+        "entropy": hex::encode(&*entropy),
+        "seed_hash": blake3::hash(&entropy).to_string(),
+    }))
+}
+
+fn verify(args: &Args) -> Result<Value, EraError> {
+    let seed_hash = args.required("seed-hash")?;
+    let valid = wallet(args)?.verify_key(seed_hash.to_string())?;
+    Ok(json!({ "valid": valid }))
+}
+
+fn encrypt(args: &Args) -> Result<Value, EraError> {
+    let mut secrets = Secrets::read()?;
+    let password = secrets.line("password")?;
+    let shard = Zeroizing::new(decode_base64(&secrets.line("shard")?, "shard")?);
+    let sealed = encrypt_shard(&shard, password.as_bytes(), &kdf(args)?)?;
+    Ok(json!({ "shard": general_purpose::STANDARD.encode(sealed) }))
+}
+
+fn decrypt(args: &Args) -> Result<Value, EraError> {
+    let shard = decode_base64(args.required("shard")?, "shard")?;
+    let password = Secrets::read()?.line("password")?;
+    let mut output = BTreeMap::new();
+    let sealed = if ShardEnvelope::is_envelope(&shard) {
+        let envelope = ShardEnvelope::decode(&shard)?;
+        output.insert("envelope", to_json(&envelope.info())?);
+        envelope.payload
+    } else {
+        shard
+    };
+    let shard = Zeroizing::new(decrypt_shard(&sealed, password.as_bytes())?);
+    output.insert("shard", json!(general_purpose::STANDARD.encode(&*shard)));
+    to_json(&output)
+}
+
+fn derive(args: &Args) -> Result<Value, EraError> {
+    let phrase = Secrets::read()?.line("mnemonic")?;
+    let account: Account = phrase.parse()?;
+    match args.positional.first().map(String::as_str) {
+        Some("polkadot") => {
+            let signer = Signer::new(account.to_bytes(), phrase.to_string());
+            let uri = args.option("uri").map(str::to_string);
+            to_json(&signer.to_polkadot_signer(uri)?.get_key()?)
+        }
+        Some("bip32") => {
+            let path = args.option("path").unwrap_or("m");
+            to_json(&account.generate_extended_key(path)?)
+        }
+        _ => Err(invalid_input(
+            "Expected `derive polkadot` or `derive bip32`",
+        )),
+    }
+}
+
+fn run() -> Result<Value, EraError> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().unwrap_or_default();
+    let args = Args::parse(args)?;
+    match command.as_str() {
+        "split" => split(&args),
+        "combine" => combine(&args),
+        "verify" => verify(&args),
+        "encrypt" => encrypt(&args),
+        "decrypt" => decrypt(&args),
+        "derive" => derive(&args),
+        _ => {
+            eprint!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    match run() {
+        Ok(output) => println!("{}", output),
+        Err(error) => {
+            let output = json!({
+                "error": { "code": error.code().as_str(), "message": error.message() }
+            });
+            println!("{}", output);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Bech32 (BIP-173) and bech32m (BIP-350) strings, used by segwit and cosmos addresses.
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// The checksum variant of a string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// BIP-173, segwit v0 and most other chains
+    Bech32,
+    /// BIP-350, segwit v1 and later
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(self) -> u32 {
+        match self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => 0x2bc8_30a3,
+        }
+    }
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = (checksum & 0x01ff_ffff) << 5 ^ u32::from(*value);
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut expanded: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    expanded.push(0);
+    expanded.extend(hrp.bytes().map(|b| b & 0x1f));
+    expanded
+}
+
+/// Regroups bits, e.g. bytes into the 5 bit groups of bech32 and back
+pub fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut accumulator = 0u32;
+    let mut bits = 0u32;
+    let max = (1u32 << to) - 1;
+    let mut converted = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for value in data {
+        let value = u32::from(*value);
+        if value >> from != 0 {
+            return None;
+        }
+        accumulator = (accumulator << from) | value;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            converted.push(((accumulator >> bits) & max) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            converted.push(((accumulator << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || (accumulator << (to - bits)) & max != 0 {
+        return None;
+    }
+    Some(converted)
+}
+
+/// Encodes 5 bit groups under a human readable part
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> String {
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0; 6]);
+    let checksum = polymod(&values) ^ variant.constant();
+
+    let mut encoded = String::with_capacity(hrp.len() + 1 + data.len() + 6);
+    encoded.push_str(hrp);
+    encoded.push('1');
+    for value in data {
+        encoded.push(CHARSET[*value as usize] as char);
+    }
+    for i in 0..6 {
+        encoded.push(CHARSET[((checksum >> (5 * (5 - i))) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+/// Decodes a string into its human readable part, 5 bit groups and checksum variant
+pub fn decode(value: &str) -> Option<(String, Vec<u8>, Variant)> {
+    let lower = value.to_ascii_lowercase();
+    if (lower != value && value.to_ascii_uppercase() != value) || value.len() > 90 {
+        return None;
+    }
+    let separator = lower.rfind('1')?;
+    let (hrp, data) = (&lower[..separator], &lower[separator + 1..]);
+    if hrp.is_empty() || data.len() < 6 || !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return None;
+    }
+    let data = data
+        .bytes()
+        .map(|c| CHARSET.iter().position(|x| *x == c).map(|p| p as u8))
+        .collect::<Option<Vec<u8>>>()?;
+
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(&data);
+    let variant = match polymod(&values) {
+        c if c == Variant::Bech32.constant() => Variant::Bech32,
+        c if c == Variant::Bech32m.constant() => Variant::Bech32m,
+        _ => return None,
+    };
+    Some((hrp.to_string(), data[..data.len() - 6].to_vec(), variant))
+}
+
+/// Encodes a segwit address, bech32 for version 0 and bech32m for later versions
+pub fn encode_segwit(hrp: &str, version: u8, program: &[u8]) -> String {
+    let variant = if version == 0 {
+        Variant::Bech32
+    } else {
+        Variant::Bech32m
+    };
+    let mut data = vec![version];
+    data.extend(convert_bits(program, 8, 5, true).unwrap_or_default());
+    encode(hrp, &data, variant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segwit_addresses() {
+        // the examples of BIP-173 and BIP-350
+        let program = hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        assert_eq!(
+            encode_segwit("bc", 0, &program),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+        let program =
+            hex::decode("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        assert_eq!(
+            encode_segwit("bc", 1, &program),
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0"
+        );
+
+        let (hrp, data, variant) = decode("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").unwrap();
+        assert_eq!((hrp.as_str(), variant), ("bc", Variant::Bech32));
+        assert_eq!(
+            convert_bits(&data[1..], 5, 8, false).unwrap(),
+            hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap()
+        );
+        assert!(decode("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5").is_none());
+        assert!(decode("bc1QW508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").is_none());
+    }
+}
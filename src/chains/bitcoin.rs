@@ -0,0 +1,677 @@
+use crate::chains::bech32;
+use crate::chains::errors::BitcoinError;
+use crate::chains::psbt::{self, Psbt, TxOut};
+use crate::crypto::crypto::derive_key;
+use crate::errors::EraError;
+#[cfg(feature = "wasm")]
+use crate::wasm_bindgen;
+use crate::{general_purpose, Engine, Serialize, XPrv};
+#[cfg(feature = "wasm")]
+use crate::{to_value, JsValue};
+use k256::ecdsa::signature::hazmat::PrehashSigner;
+use k256::ecdsa::{Signature, SigningKey};
+use k256::elliptic_curve::PrimeField;
+use k256::{schnorr, FieldBytes, Scalar};
+use ripemd::Ripemd160;
+use sha2::Digest;
+use std::str::FromStr;
+use zeroize::{Zeroize, Zeroizing};
+
+/// RIPEMD-160 of SHA-256, the hash of public keys in addresses
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(psbt::sha256(data)).into()
+}
+
+fn base58check(version: u8, payload: &[u8]) -> String {
+    let mut data = vec![version];
+    data.extend_from_slice(payload);
+    let checksum = psbt::sha256d(&data);
+    data.extend_from_slice(&checksum[..4]);
+    bs58::encode(data).into_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    /// Testnet and signet, which share their address formats
+    Testnet,
+    Regtest,
+}
+
+impl FromStr for Network {
+    type Err = BitcoinError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "mainnet" | "bitcoin" => Ok(Network::Mainnet),
+            "testnet" | "signet" => Ok(Network::Testnet),
+            "regtest" => Ok(Network::Regtest),
+            _ => Err(BitcoinError::UnknownNetwork(value.into())),
+        }
+    }
+}
+
+impl Network {
+    /// The BIP-44 coin type
+    pub fn coin_type(self) -> u32 {
+        match self {
+            Network::Mainnet => 0,
+            Network::Testnet | Network::Regtest => 1,
+        }
+    }
+
+    fn hrp(self) -> &'static str {
+        match self {
+            Network::Mainnet => "bc",
+            Network::Testnet => "tb",
+            Network::Regtest => "bcrt",
+        }
+    }
+
+    fn p2pkh_version(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            Network::Testnet | Network::Regtest => 0x6f,
+        }
+    }
+
+    fn p2sh_version(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x05,
+            Network::Testnet | Network::Regtest => 0xc4,
+        }
+    }
+
+    fn wif_version(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x80,
+            Network::Testnet | Network::Regtest => 0xef,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressType {
+    /// Legacy pay to public key hash, BIP-44
+    P2pkh,
+    /// Segwit nested in pay to script hash, BIP-49
+    P2shP2wpkh,
+    /// Native segwit v0, bech32, BIP-84
+    P2wpkh,
+    /// Taproot key path, bech32m, BIP-86
+    P2tr,
+}
+
+impl FromStr for AddressType {
+    type Err = BitcoinError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "p2pkh" => Ok(AddressType::P2pkh),
+            "p2sh-p2wpkh" => Ok(AddressType::P2shP2wpkh),
+            "p2wpkh" => Ok(AddressType::P2wpkh),
+            "p2tr" => Ok(AddressType::P2tr),
+            _ => Err(BitcoinError::UnknownAddressType(value.into())),
+        }
+    }
+}
+
+impl AddressType {
+    /// The name `from_str` parses
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AddressType::P2pkh => "p2pkh",
+            AddressType::P2shP2wpkh => "p2sh-p2wpkh",
+            AddressType::P2wpkh => "p2wpkh",
+            AddressType::P2tr => "p2tr",
+        }
+    }
+
+    /// The BIP-43 purpose of the derivation path
+    pub fn purpose(self) -> u32 {
+        match self {
+            AddressType::P2pkh => 44,
+            AddressType::P2shP2wpkh => 49,
+            AddressType::P2wpkh => 84,
+            AddressType::P2tr => 86,
+        }
+    }
+}
+
+fn p2pkh_script(hash: &[u8; 20]) -> Vec<u8> {
+    let mut script = vec![0x76, 0xa9, 0x14];
+    script.extend_from_slice(hash);
+    script.extend_from_slice(&[0x88, 0xac]);
+    script
+}
+
+fn p2wpkh_script(hash: &[u8; 20]) -> Vec<u8> {
+    let mut script = vec![0x00, 0x14];
+    script.extend_from_slice(hash);
+    script
+}
+
+/// Tweaks a key for a taproot output committing to `merkle_root`, or to no scripts (BIP-86).
+/// Returns the tweaked signing key and the x-only internal key.
+fn taproot_tweak(
+    key: &SigningKey,
+    merkle_root: Option<&[u8]>,
+) -> Result<(schnorr::SigningKey, [u8; 32]), BitcoinError> {
+    let internal =
+        schnorr::SigningKey::from_bytes(&key.to_bytes()).map_err(|_| BitcoinError::InvalidKey)?;
+    let internal_key: [u8; 32] = internal.verifying_key().to_bytes().into();
+    let mut data = internal_key.to_vec();
+    data.extend_from_slice(merkle_root.unwrap_or_default());
+    let tweak = psbt::tagged_hash("TapTweak", &data);
+    let tweak: Option<Scalar> = Scalar::from_repr(FieldBytes::from(tweak)).into();
+    let tweak = tweak.ok_or(BitcoinError::InvalidKey)?;
+    let tweaked = Zeroizing::new((**internal.as_nonzero_scalar() + tweak).to_bytes());
+    let tweaked =
+        schnorr::SigningKey::from_bytes(&tweaked).map_err(|_| BitcoinError::InvalidKey)?;
+    Ok((tweaked, internal_key))
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyObject {
+    /// Wallet import format
+    pub private_key: String,
+    /// Compressed SEC1 public key
+    pub public_key: String,
+    pub address: String,
+    pub address_type: String,
+    pub path: String,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct BitcoinSigner {
+    seed: Zeroizing<Vec<u8>>,
+    network: Network,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BitcoinSigner {
+    /// The keys and address of receive address `index`, of type `p2pkh`, `p2sh-p2wpkh`,
+    /// `p2wpkh` or `p2tr`
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn fetch_key(&self, address_type: &str, index: u32) -> Result<JsValue, JsValue> {
+        let address_type = address_type.parse::<AddressType>()?;
+        let path = self.default_path(address_type, index);
+        Ok(to_value(&self.get_key(address_type, &path)?)?)
+    }
+
+    /// Receive address `index` along the BIP-44/49/84/86 path of the address type
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn address(&self, address_type: &str, index: u32) -> Result<String, EraError> {
+        let address_type = address_type.parse::<AddressType>()?;
+        let path = self.default_path(address_type, index);
+        Ok(self.get_key(address_type, &path)?.address)
+    }
+
+    /// Signs every input of a base64 PSBT derived from this wallet, returns the PSBT with
+    /// the signatures added
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn sign_psbt(&self, psbt: &str) -> Result<String, EraError> {
+        let psbt = general_purpose::STANDARD
+            .decode(psbt.trim())
+            .map_err(|e| BitcoinError::InvalidPsbt(format!("Invalid base64: {}", e)))?;
+        Ok(general_purpose::STANDARD.encode(self.sign_psbt_bytes(&psbt)?))
+    }
+
+    /// Zeroes the seed of the signer. The signer is also wiped when it is freed.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn wipe(&mut self) {
+        self.seed.zeroize();
+    }
+}
+
+impl BitcoinSigner {
+    pub fn new(seed: Vec<u8>, network: Network) -> Self {
+        Self {
+            seed: Zeroizing::new(seed),
+            network,
+        }
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// `m/purpose'/coin_type'/0'/0/index`
+    pub fn default_path(&self, address_type: AddressType, index: u32) -> String {
+        format!(
+            "m/{}'/{}'/0'/0/{}",
+            address_type.purpose(),
+            self.network.coin_type(),
+            index
+        )
+    }
+
+    /// The fingerprint of the master key, as PSBT derivations reference it
+    pub fn fingerprint(&self) -> Result<[u8; 4], EraError> {
+        let root = XPrv::new(&*self.seed).map_err(|_| BitcoinError::InvalidKey)?;
+        let hash = hash160(&root.public_key().to_bytes());
+        Ok([hash[0], hash[1], hash[2], hash[3]])
+    }
+
+    /// The keys and address of the key at `path`
+    pub fn get_key(&self, address_type: AddressType, path: &str) -> Result<KeyObject, EraError> {
+        let (path, xpriv) = derive_key(&self.seed, path)?;
+        let key = xpriv.private_key();
+        let public_key = xpriv.public_key().to_bytes();
+        let hash = hash160(&public_key);
+        let address = match address_type {
+            AddressType::P2pkh => base58check(self.network.p2pkh_version(), &hash),
+            AddressType::P2shP2wpkh => {
+                base58check(self.network.p2sh_version(), &hash160(&p2wpkh_script(&hash)))
+            }
+            AddressType::P2wpkh => bech32::encode_segwit(self.network.hrp(), 0, &hash),
+            AddressType::P2tr => {
+                let (tweaked, _) = taproot_tweak(key, None)?;
+                bech32::encode_segwit(self.network.hrp(), 1, &tweaked.verifying_key().to_bytes())
+            }
+        };
+        let mut wif = Zeroizing::new(key.to_bytes().to_vec());
+        wif.push(0x01);
+        Ok(KeyObject {
+            private_key: base58check(self.network.wif_version(), &wif),
+            public_key: hex::encode(public_key),
+            address,
+            address_type: address_type.as_str().into(),
+            path: path.to_string(),
+        })
+    }
+
+    /// The keys an input derives from this wallet, from its BIP-32 derivations
+    fn input_keys(&self, psbt: &Psbt, index: usize) -> Result<Vec<(XPrv, bool)>, EraError> {
+        let fingerprint = self.fingerprint()?;
+        let mut keys = Vec::new();
+        for (key, value) in &psbt.inputs[index] {
+            let (taproot, origin) = match key.first() {
+                Some(&psbt::IN_BIP32_DERIVATION) => (false, &value[..]),
+                Some(&psbt::IN_TAP_BIP32_DERIVATION) => {
+                    // leaf hashes come first, a key path spend has none
+                    if value.first() != Some(&0) {
+                        continue;
+                    }
+                    (true, &value[1..])
+                }
+                _ => continue,
+            };
+            if origin.len() < 4 || origin.len() % 4 != 0 || origin[..4] != fingerprint {
+                continue;
+            }
+            let mut path = String::from("m");
+            for step in origin[4..].chunks(4) {
+                let step = u32::from_le_bytes([step[0], step[1], step[2], step[3]]);
+                path.push_str(&format!("/{}", step & 0x7fff_ffff));
+                if step & 0x8000_0000 != 0 {
+                    path.push('\'');
+                }
+            }
+            let (_, xpriv) = derive_key(&self.seed, &path)?;
+            let public_key = xpriv.public_key().to_bytes();
+            let matches = if taproot {
+                key[1..] == public_key[1..]
+            } else {
+                key[1..] == public_key[..]
+            };
+            if !matches {
+                return Err(BitcoinError::InvalidPsbt(format!(
+                    "The key of input {} does not derive from {}",
+                    index, path
+                ))
+                .into());
+            }
+            keys.push((xpriv, taproot));
+        }
+        Ok(keys)
+    }
+
+    fn sign_ecdsa(
+        key: &SigningKey,
+        sighash: &[u8; 32],
+        hash_type: u32,
+    ) -> Result<Vec<u8>, BitcoinError> {
+        let signature: Signature = key
+            .sign_prehash(sighash)
+            .map_err(|_| BitcoinError::InvalidKey)?;
+        let mut encoded = signature.to_der().as_bytes().to_vec();
+        encoded.push(hash_type as u8);
+        Ok(encoded)
+    }
+
+    /// Signs the inputs of a PSBT whose derivations match this wallet: P2PKH, P2SH-P2WPKH,
+    /// P2WPKH and P2TR key path spends
+    pub fn sign_psbt_bytes(&self, bytes: &[u8]) -> Result<Vec<u8>, EraError> {
+        let mut psbt = Psbt::parse(bytes)?;
+        let mut signed = 0;
+        for index in 0..psbt.inputs.len() {
+            let input = &psbt.inputs[index];
+            if psbt::get(input, psbt::IN_FINAL_SCRIPTSIG).is_some()
+                || psbt::get(input, psbt::IN_FINAL_SCRIPTWITNESS).is_some()
+            {
+                continue;
+            }
+            let keys = self.input_keys(&psbt, index)?;
+            if keys.is_empty() {
+                continue;
+            }
+            let spent = psbt.spent_output(index)?;
+            let script = &spent.script_pubkey;
+            let hash_type = psbt.sighash_type(index)?;
+
+            for (xpriv, taproot) in keys {
+                let key = xpriv.private_key();
+                let public_key = xpriv.public_key().to_bytes();
+                let hash = hash160(&public_key);
+
+                if taproot {
+                    if script.len() != 34 || script[..2] != [0x51, 0x20] {
+                        continue;
+                    }
+                    let merkle_root = psbt::get(&psbt.inputs[index], psbt::IN_TAP_MERKLE_ROOT);
+                    let (tweaked, internal_key) = taproot_tweak(key, merkle_root)?;
+                    if let Some(expected) =
+                        psbt::get(&psbt.inputs[index], psbt::IN_TAP_INTERNAL_KEY)
+                    {
+                        if expected != internal_key {
+                            continue;
+                        }
+                    }
+                    if script[2..] != tweaked.verifying_key().to_bytes()[..] {
+                        continue;
+                    }
+                    let hash_type = hash_type.unwrap_or(psbt::SIGHASH_DEFAULT);
+                    let prevouts: Vec<TxOut> = if hash_type & psbt::SIGHASH_ANYONECANPAY != 0 {
+                        vec![spent.clone()]
+                    } else {
+                        (0..psbt.inputs.len())
+                            .map(|i| psbt.spent_output(i))
+                            .collect::<Result<_, _>>()?
+                    };
+                    let sighash = psbt.tx.taproot_key_sighash(index, &prevouts, hash_type)?;
+                    let mut aux_rand = Zeroizing::new([0u8; 32]);
+                    getrandom::getrandom(&mut *aux_rand)?;
+                    let signature = tweaked
+                        .sign_prehash_with_aux_rand(&sighash, &aux_rand)
+                        .map_err(|_| BitcoinError::InvalidKey)?;
+                    let mut signature = signature.to_bytes().to_vec();
+                    if hash_type != psbt::SIGHASH_DEFAULT {
+                        signature.push(hash_type as u8);
+                    }
+                    psbt::insert(
+                        &mut psbt.inputs[index],
+                        vec![psbt::IN_TAP_KEY_SIG],
+                        signature,
+                    );
+                    signed += 1;
+                    continue;
+                }
+
+                let hash_type = hash_type.unwrap_or(psbt::SIGHASH_ALL);
+                if !matches!(hash_type, 0x01..=0x03 | 0x81..=0x83) {
+                    return Err(BitcoinError::UnsupportedSighash(hash_type).into());
+                }
+                let sighash = if *script == p2pkh_script(&hash) {
+                    psbt.tx.legacy_sighash(index, script, hash_type)?
+                } else if *script == p2wpkh_script(&hash) {
+                    psbt.tx.segwit_v0_sighash(
+                        index,
+                        &p2pkh_script(&hash),
+                        spent.value,
+                        hash_type,
+                    )?
+                } else {
+                    let redeem_script = psbt::get(&psbt.inputs[index], psbt::IN_REDEEM_SCRIPT);
+                    let nested = p2wpkh_script(&hash);
+                    let mut p2sh = vec![0xa9, 0x14];
+                    p2sh.extend_from_slice(&hash160(&nested));
+                    p2sh.push(0x87);
+                    if redeem_script != Some(&nested[..]) || *script != p2sh {
+                        continue;
+                    }
+                    psbt.tx.segwit_v0_sighash(
+                        index,
+                        &p2pkh_script(&hash),
+                        spent.value,
+                        hash_type,
+                    )?
+                };
+                let signature = Self::sign_ecdsa(key, &sighash, hash_type)?;
+                let mut record = vec![psbt::IN_PARTIAL_SIG];
+                record.extend_from_slice(&public_key);
+                psbt::insert(&mut psbt.inputs[index], record, signature);
+                signed += 1;
+            }
+        }
+        if signed == 0 {
+            return Err(
+                BitcoinError::InvalidPsbt("No input derives from this wallet".into()).into(),
+            );
+        }
+        Ok(psbt.serialize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::psbt::{Transaction, TxIn};
+    use crate::Account;
+    use k256::ecdsa::signature::hazmat::PrehashVerifier;
+    use std::convert::TryFrom;
+
+    fn signer(network: Network) -> BitcoinSigner {
+        let account: Account = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+            .parse()
+            .unwrap();
+        BitcoinSigner::new(account.to_bytes(), network)
+    }
+
+    #[test]
+    fn test_addresses() {
+        // the vectors of BIP-44, BIP-49, BIP-84 and BIP-86
+        let mainnet = signer(Network::Mainnet);
+        assert_eq!(
+            mainnet.address("p2pkh", 0).unwrap(),
+            "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"
+        );
+        assert_eq!(
+            mainnet.address("p2wpkh", 0).unwrap(),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        assert_eq!(
+            mainnet.address("p2tr", 0).unwrap(),
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+        );
+        let testnet = signer(Network::Testnet);
+        assert_eq!(
+            testnet.address("p2sh-p2wpkh", 0).unwrap(),
+            "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2"
+        );
+
+        let key = mainnet
+            .get_key(
+                AddressType::P2wpkh,
+                &mainnet.default_path(AddressType::P2wpkh, 0),
+            )
+            .unwrap();
+        assert_eq!(
+            key.private_key,
+            "KyZpNDKnfs94vbrwhJneDi77V6jF64PWPF8x5cdJb8ifgg2DUc9d"
+        );
+        assert_eq!(key.path, "m/84'/0'/0'/0/0");
+        assert!(signer(Network::Regtest)
+            .address("p2wpkh", 0)
+            .unwrap()
+            .starts_with("bcrt1q"));
+        assert!(mainnet.address("p2sh", 0).is_err());
+
+        // the reported address type parses back
+        for address_type in ["p2pkh", "p2sh-p2wpkh", "p2wpkh", "p2tr"].iter() {
+            let parsed: AddressType = address_type.parse().unwrap();
+            let key = mainnet
+                .get_key(parsed, &mainnet.default_path(parsed, 0))
+                .unwrap();
+            assert_eq!(key.address_type, *address_type);
+        }
+    }
+
+    /// A PSBT spending one output of every address type to the signer
+    fn psbt(signer: &BitcoinSigner) -> (Psbt, Vec<TxOut>) {
+        let types = [
+            AddressType::P2pkh,
+            AddressType::P2shP2wpkh,
+            AddressType::P2wpkh,
+            AddressType::P2tr,
+        ];
+        let fingerprint = signer.fingerprint().unwrap();
+        let mut spent = Vec::new();
+        let mut inputs = Vec::new();
+        let mut maps = Vec::new();
+        for (i, address_type) in types.iter().enumerate() {
+            let path = [
+                address_type.purpose() | 0x8000_0000,
+                0x8000_0000,
+                0x8000_0000,
+                0,
+                i as u32,
+            ];
+            let (_, xpriv) =
+                derive_key(&signer.seed, &signer.default_path(*address_type, i as u32)).unwrap();
+            let public_key = xpriv.public_key().to_bytes();
+            let hash = hash160(&public_key);
+            let mut origin = fingerprint.to_vec();
+            for step in path.iter() {
+                origin.extend_from_slice(&step.to_le_bytes());
+            }
+            let mut map = Vec::new();
+            let script = match address_type {
+                AddressType::P2pkh => p2pkh_script(&hash),
+                AddressType::P2shP2wpkh => {
+                    map.push((vec![psbt::IN_REDEEM_SCRIPT], p2wpkh_script(&hash)));
+                    let mut script = vec![0xa9, 0x14];
+                    script.extend_from_slice(&hash160(&p2wpkh_script(&hash)));
+                    script.push(0x87);
+                    script
+                }
+                AddressType::P2wpkh => p2wpkh_script(&hash),
+                AddressType::P2tr => {
+                    let (tweaked, internal) = taproot_tweak(xpriv.private_key(), None).unwrap();
+                    let mut script = vec![0x51, 0x20];
+                    script.extend_from_slice(&tweaked.verifying_key().to_bytes());
+                    let mut key = vec![psbt::IN_TAP_BIP32_DERIVATION];
+                    key.extend_from_slice(&internal);
+                    let mut value = vec![0];
+                    value.extend_from_slice(&origin);
+                    map.push((key, value));
+                    map.push((vec![psbt::IN_TAP_INTERNAL_KEY], internal.to_vec()));
+                    script
+                }
+            };
+            if *address_type != AddressType::P2tr {
+                let mut key = vec![psbt::IN_BIP32_DERIVATION];
+                key.extend_from_slice(&public_key);
+                map.push((key, origin));
+            }
+            let output = TxOut {
+                value: 10_000 * (i as u64 + 1),
+                script_pubkey: script,
+            };
+            if *address_type == AddressType::P2pkh {
+                let previous = Transaction {
+                    version: 2,
+                    inputs: vec![TxIn {
+                        txid: [9u8; 32],
+                        vout: 0,
+                        script_sig: vec![],
+                        sequence: 0,
+                    }],
+                    outputs: vec![output.clone()],
+                    lock_time: 0,
+                };
+                inputs.push(TxIn {
+                    txid: previous.txid(),
+                    vout: 0,
+                    script_sig: vec![],
+                    sequence: 0xffff_fffd,
+                });
+                map.push((vec![psbt::IN_NON_WITNESS_UTXO], previous.serialize()));
+            } else {
+                inputs.push(TxIn {
+                    txid: [i as u8; 32],
+                    vout: 1,
+                    script_sig: vec![],
+                    sequence: 0xffff_fffd,
+                });
+                map.push((vec![psbt::IN_WITNESS_UTXO], output.serialize()));
+            }
+            spent.push(output);
+            maps.push(map);
+        }
+        let tx = Transaction {
+            version: 2,
+            inputs,
+            outputs: vec![TxOut {
+                value: 90_000,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+        };
+        let psbt = Psbt {
+            global: vec![(vec![psbt::GLOBAL_UNSIGNED_TX], tx.serialize())],
+            tx,
+            inputs: maps,
+            outputs: vec![Vec::new()],
+        };
+        (psbt, spent)
+    }
+
+    #[test]
+    fn test_sign_psbt() {
+        let signer = signer(Network::Mainnet);
+        let (unsigned, spent) = psbt(&signer);
+        let encoded = general_purpose::STANDARD.encode(unsigned.serialize());
+        let signed = general_purpose::STANDARD
+            .decode(signer.sign_psbt(&encoded).unwrap())
+            .unwrap();
+        let signed = Psbt::parse(&signed).unwrap();
+
+        for (index, output) in spent.iter().enumerate().take(3) {
+            let (key, value) = signed.inputs[index]
+                .iter()
+                .find(|(key, _)| key[0] == psbt::IN_PARTIAL_SIG)
+                .unwrap();
+            assert_eq!(*value.last().unwrap(), psbt::SIGHASH_ALL as u8);
+            let signature = Signature::from_der(&value[..value.len() - 1]).unwrap();
+            let hash = hash160(&key[1..]);
+            let sighash = if index == 0 {
+                signed
+                    .tx
+                    .legacy_sighash(index, &output.script_pubkey, psbt::SIGHASH_ALL)
+                    .unwrap()
+            } else {
+                signed
+                    .tx
+                    .segwit_v0_sighash(index, &p2pkh_script(&hash), output.value, psbt::SIGHASH_ALL)
+                    .unwrap()
+            };
+            let verifying_key = k256::ecdsa::VerifyingKey::from_sec1_bytes(&key[1..]).unwrap();
+            assert!(verifying_key.verify_prehash(&sighash, &signature).is_ok());
+        }
+
+        let signature = psbt::get(&signed.inputs[3], psbt::IN_TAP_KEY_SIG).unwrap();
+        assert_eq!(signature.len(), 64);
+        let sighash = signed
+            .tx
+            .taproot_key_sighash(3, &spent, psbt::SIGHASH_DEFAULT)
+            .unwrap();
+        let output_key = schnorr::VerifyingKey::from_bytes(&spent[3].script_pubkey[2..]).unwrap();
+        let signature = schnorr::Signature::try_from(signature).unwrap();
+        assert!(output_key.verify_raw(&sighash, &signature).is_ok());
+
+        let other = BitcoinSigner::new(vec![1u8; 32], Network::Mainnet);
+        assert!(other.sign_psbt(&encoded).is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use crate::chains::bech32::{self, Variant};
+use crate::chains::bitcoin::hash160;
+use crate::chains::errors::CosmosError;
+use crate::chains::psbt::sha256;
+use crate::errors::EraError;
+#[cfg(feature = "wasm")]
+use crate::wasm_bindgen;
+#[cfg(feature = "wasm")]
+use crate::{errors::from_js, to_value, JsValue};
+use crate::{general_purpose, Engine, Serialize};
+use k256::ecdsa::signature::hazmat::PrehashSigner;
+use k256::ecdsa::{Signature, SigningKey};
+use serde_json::Value;
+use zeroize::{Zeroize, Zeroizing};
+
+/// The SLIP-44 coin type of the cosmos hub, shared by most cosmos SDK chains
+pub const COSMOS_COIN_TYPE: u32 = 118;
+
+/// The amino type of secp256k1 public keys
+pub const PUB_KEY_TYPE: &str = "tendermint/PubKeySecp256k1";
+
+/// `m/44'/coin_type'/0'/0/index`
+pub fn account_path(coin_type: u32, index: u32) -> String {
+    format!("m/44'/{}'/0'/0/{}", coin_type, index)
+}
+
+/// Checks a bech32 human readable part: lowercase printable ASCII, e.g. `cosmos` or `osmo`
+pub fn validate_prefix(prefix: &str) -> Result<(), CosmosError> {
+    let valid = !prefix.is_empty()
+        && prefix.len() <= 83
+        && prefix
+            .bytes()
+            .all(|b| (33..=126).contains(&b) && !b.is_ascii_uppercase());
+    if valid {
+        Ok(())
+    } else {
+        Err(CosmosError::InvalidPrefix(prefix.into()))
+    }
+}
+
+/// The `SignDoc` of SIGN_MODE_DIRECT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignDoc {
+    /// The protobuf `TxBody`
+    pub body_bytes: Vec<u8>,
+    /// The protobuf `AuthInfo`
+    pub auth_info_bytes: Vec<u8>,
+    pub chain_id: String,
+    pub account_number: u64,
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+impl SignDoc {
+    /// The protobuf encoding of the document, without the fields left at their defaults
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (tag, value) in [
+            (0x0a, &self.body_bytes[..]),
+            (0x12, &self.auth_info_bytes[..]),
+            (0x1a, self.chain_id.as_bytes()),
+        ]
+        .iter()
+        {
+            if !value.is_empty() {
+                out.push(*tag);
+                write_varint(&mut out, value.len() as u64);
+                out.extend_from_slice(value);
+            }
+        }
+        if self.account_number != 0 {
+            out.push(0x20);
+            write_varint(&mut out, self.account_number);
+        }
+        out
+    }
+}
+
+fn write_canonical(out: &mut String, value: &Value) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(out, &Value::String((*key).clone()));
+                out.push(':');
+                write_canonical(out, &map[key.as_str()]);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(out, item);
+            }
+            out.push(']');
+        }
+        Value::String(_) => {
+            // amino escapes the HTML characters like Go's encoding/json
+            let encoded = value.to_string();
+            for c in encoded.chars() {
+                match c {
+                    '<' => out.push_str("\\u003c"),
+                    '>' => out.push_str("\\u003e"),
+                    '&' => out.push_str("\\u0026"),
+                    '\u{2028}' => out.push_str("\\u2028"),
+                    '\u{2029}' => out.push_str("\\u2029"),
+                    c => out.push(c),
+                }
+            }
+        }
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+/// The canonical JSON of amino: keys sorted at every level, no whitespace and `<`, `>` and
+/// `&` escaped
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(&mut out, value);
+    out
+}
+
+/// Checks that an amino `StdSignDoc` has its fields, with the 64 bit numbers as strings
+fn validate_amino(doc: &Value) -> Result<(), CosmosError> {
+    let doc = doc
+        .as_object()
+        .ok_or_else(|| CosmosError::InvalidSignDoc("The document is not an object".into()))?;
+    for field in [
+        "chain_id",
+        "account_number",
+        "sequence",
+        "fee",
+        "msgs",
+        "memo",
+    ]
+    .iter()
+    {
+        if !doc.contains_key(*field) {
+            return Err(CosmosError::InvalidSignDoc(format!("Missing {}", field)));
+        }
+    }
+    for field in ["account_number", "sequence"].iter() {
+        let numeric = doc[*field]
+            .as_str()
+            .is_some_and(|value| value.parse::<u64>().is_ok());
+        if !numeric {
+            return Err(CosmosError::InvalidSignDoc(format!(
+                "{} must be a decimal string",
+                field
+            )));
+        }
+    }
+    if !doc["msgs"].is_array() {
+        return Err(CosmosError::InvalidSignDoc("msgs must be an array".into()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PubKey {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Base64 of the compressed public key
+    pub value: String,
+}
+
+/// A signature with the public key that verifies it, as the nodes and wallets exchange it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StdSignature {
+    pub pub_key: PubKey,
+    /// Base64 of the 64 byte `r || s` signature with a low `s`
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyObject {
+    pub private_key: String,
+    /// Compressed SEC1 public key
+    pub public_key: String,
+    /// Bech32 address under the prefix of the chain
+    pub address: String,
+    pub path: String,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct CosmosSigner {
+    signer: Zeroizing<Vec<u8>>,
+    prefix: String,
+    path: String,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CosmosSigner {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn fetch_key(&self) -> Result<JsValue, JsValue> {
+        Ok(to_value(&self.get_key()?)?)
+    }
+
+    /// The bech32 address of the signer under the prefix of the chain
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn address(&self) -> Result<String, EraError> {
+        Ok(self.get_key()?.address)
+    }
+
+    /// Signs a SIGN_MODE_DIRECT `SignDoc`, returns the `{ pub_key, signature }`
+    /// `StdSignature`. The signature bytes go into `TxRaw.signatures`.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn sign_direct(
+        &self,
+        body_bytes: &[u8],
+        auth_info_bytes: &[u8],
+        chain_id: &str,
+        account_number: u64,
+    ) -> Result<JsValue, JsValue> {
+        let doc = SignDoc {
+            body_bytes: body_bytes.to_vec(),
+            auth_info_bytes: auth_info_bytes.to_vec(),
+            chain_id: chain_id.into(),
+            account_number,
+        };
+        Ok(to_value(&self.sign_doc(&doc)?)?)
+    }
+
+    /// Signs an amino JSON `StdSignDoc` `{ chain_id, account_number, sequence, fee, msgs,
+    /// memo }`, given as an object or a JSON string. Returns the `StdSignature`.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn sign_amino(&self, doc: JsValue) -> Result<JsValue, JsValue> {
+        let doc: Value = match doc.as_string() {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| CosmosError::InvalidSignDoc(e.to_string()))?,
+            None => from_js(doc, "sign doc")?,
+        };
+        Ok(to_value(&self.sign_amino_doc(&doc)?)?)
+    }
+
+    /// Zeroes the key of the signer. The signer is also wiped when it is freed.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn wipe(&mut self) {
+        self.signer.zeroize();
+    }
+}
+
+impl CosmosSigner {
+    pub fn new(signer: Vec<u8>, prefix: String, path: String) -> Result<Self, CosmosError> {
+        validate_prefix(&prefix)?;
+        Ok(Self {
+            signer: Zeroizing::new(signer),
+            prefix,
+            path,
+        })
+    }
+
+    fn key(&self) -> Result<SigningKey, CosmosError> {
+        SigningKey::from_slice(&self.signer).map_err(|_| CosmosError::InvalidKey)
+    }
+
+    fn public_key(&self) -> Result<Vec<u8>, CosmosError> {
+        Ok(self
+            .key()?
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec())
+    }
+
+    /// The keys and bech32 address of the signer
+    pub fn get_key(&self) -> Result<KeyObject, CosmosError> {
+        let public_key = self.public_key()?;
+        let data = bech32::convert_bits(&hash160(&public_key), 8, 5, true).unwrap_or_default();
+        Ok(KeyObject {
+            private_key: format!("0x{}", hex::encode(&*self.signer)),
+            public_key: format!("0x{}", hex::encode(&public_key)),
+            address: bech32::encode(&self.prefix, &data, Variant::Bech32),
+            path: self.path.clone(),
+        })
+    }
+
+    /// Signs the SHA-256 of a message
+    pub fn sign_bytes(&self, message: &[u8]) -> Result<StdSignature, CosmosError> {
+        let signature: Signature = self
+            .key()?
+            .sign_prehash(&sha256(message))
+            .map_err(|_| CosmosError::InvalidKey)?;
+        Ok(StdSignature {
+            pub_key: PubKey {
+                kind: PUB_KEY_TYPE.into(),
+                value: general_purpose::STANDARD.encode(self.public_key()?),
+            },
+            signature: general_purpose::STANDARD.encode(signature.to_bytes()),
+        })
+    }
+
+    /// Signs a SIGN_MODE_DIRECT `SignDoc`
+    pub fn sign_doc(&self, doc: &SignDoc) -> Result<StdSignature, CosmosError> {
+        self.sign_bytes(&doc.encode())
+    }
+
+    /// Signs the canonical JSON of an amino `StdSignDoc`
+    pub fn sign_amino_doc(&self, doc: &Value) -> Result<StdSignature, CosmosError> {
+        validate_amino(doc)?;
+        self.sign_bytes(canonical_json(doc).as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::signature::hazmat::PrehashVerifier;
+    use k256::ecdsa::VerifyingKey;
+    use serde_json::json;
+
+    fn signer() -> CosmosSigner {
+        CosmosSigner::new(vec![5u8; 32], "cosmos".into(), account_path(118, 0)).unwrap()
+    }
+
+    fn verify(signature: &StdSignature, message: &[u8]) -> bool {
+        let key = general_purpose::STANDARD
+            .decode(&signature.pub_key.value)
+            .unwrap();
+        let key = VerifyingKey::from_sec1_bytes(&key).unwrap();
+        let bytes = general_purpose::STANDARD
+            .decode(&signature.signature)
+            .unwrap();
+        let signature = Signature::from_slice(&bytes).unwrap();
+        signature.normalize_s().is_none()
+            && key.verify_prehash(&sha256(message), &signature).is_ok()
+    }
+
+    #[test]
+    fn test_sign_direct() {
+        let doc = SignDoc {
+            body_bytes: vec![1, 2],
+            auth_info_bytes: vec![3],
+            chain_id: "cosmoshub-4".into(),
+            account_number: 300,
+        };
+        let encoded = doc.encode();
+        assert_eq!(
+            hex::encode(&encoded),
+            "0a0201021201031a0b636f736d6f736875622d3420ac02"
+        );
+        let signature = signer().sign_doc(&doc).unwrap();
+        assert_eq!(signature.pub_key.kind, PUB_KEY_TYPE);
+        assert!(verify(&signature, &encoded));
+
+        let empty = SignDoc {
+            body_bytes: vec![],
+            auth_info_bytes: vec![],
+            chain_id: String::new(),
+            account_number: 0,
+        };
+        assert!(empty.encode().is_empty());
+    }
+
+    #[test]
+    fn test_sign_amino() {
+        assert_eq!(
+            canonical_json(&json!({ "b": 1, "a": { "d": "<a&b>", "c": [2, 1] } })),
+            r#"{"a":{"c":[2,1],"d":"\u003ca\u0026b\u003e"},"b":1}"#
+        );
+
+        let doc = json!({
+            "memo": "",
+            "msgs": [{ "type": "cosmos-sdk/MsgSend", "value": { "amount": [{ "denom": "uatom", "amount": "1" }] } }],
+            "sequence": "0",
+            "fee": { "gas": "200000", "amount": [] },
+            "chain_id": "cosmoshub-4",
+            "account_number": "1",
+        });
+        let signer = signer();
+        let signature = signer.sign_amino_doc(&doc).unwrap();
+        assert!(verify(&signature, canonical_json(&doc).as_bytes()));
+
+        let mut numeric = doc.clone();
+        numeric["sequence"] = json!(0);
+        assert!(signer.sign_amino_doc(&numeric).is_err());
+        let mut missing = doc;
+        missing.as_object_mut().unwrap().remove("fee");
+        assert!(signer.sign_amino_doc(&missing).is_err());
+    }
+
+    #[test]
+    fn test_prefixes() {
+        assert!(validate_prefix("osmo").is_ok());
+        assert!(validate_prefix("Cosmos").is_err());
+        assert!(validate_prefix("").is_err());
+        assert!(CosmosSigner::new(vec![5u8; 32], "a b".into(), String::new()).is_err());
+    }
+}
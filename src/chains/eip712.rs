@@ -0,0 +1,501 @@
+//! EIP-712 typed structured data, as signed by `eth_signTypedData_v4`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::chains::errors::EthereumError;
+use crate::chains::ethereum::keccak256;
+use crate::chains::evm::{Address, Quantity};
+
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+/// The fields of the domain in the order `EIP712Domain` lists them when it is not given
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32"),
+];
+
+fn invalid(message: impl Into<String>) -> EthereumError {
+    EthereumError::InvalidTypedData(message.into())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TypedField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+/// The JSON of `eth_signTypedData_v4`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<TypedField>>,
+    pub primary_type: String,
+    pub domain: Map<String, Value>,
+    #[serde(default)]
+    pub message: Value,
+}
+
+/// The hashes of typed data, to preview what a signature commits to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TypedDataHash {
+    pub domain_separator: String,
+    /// Absent when the primary type is `EIP712Domain`
+    pub struct_hash: Option<String>,
+    /// `keccak256(0x1901 || domain_separator || struct_hash)`, the hash that is signed
+    pub hash: String,
+}
+
+struct Hashes {
+    domain_separator: [u8; 32],
+    struct_hash: Option<[u8; 32]>,
+    hash: [u8; 32],
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Splits `T[]` or `T[n]` into `T` and the fixed length, if any
+fn array_type(kind: &str) -> Option<(&str, Option<&str>)> {
+    let inner = kind.strip_suffix(']')?;
+    let open = inner.rfind('[')?;
+    let length = &inner[open + 1..];
+    Some((
+        &inner[..open],
+        if length.is_empty() {
+            None
+        } else {
+            Some(length)
+        },
+    ))
+}
+
+fn base_type(kind: &str) -> &str {
+    match array_type(kind) {
+        Some((inner, _)) => base_type(inner),
+        None => kind,
+    }
+}
+
+fn decode_hex(value: &Value, kind: &str) -> Result<Vec<u8>, EthereumError> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| invalid(format!("Expected a hex string for {}", kind)))?;
+    hex::decode(text.strip_prefix("0x").unwrap_or(text))
+        .map_err(|e| invalid(format!("Invalid hex for {}: {}", kind, e)))
+}
+
+/// Encodes an integer of `bits` bits as a 32 byte word, two's complement when signed
+fn encode_integer(value: &Value, bits: usize, signed: bool) -> Result<[u8; 32], EthereumError> {
+    let out_of_range = || invalid(format!("{} is out of range", value));
+    let (negative, magnitude) = match value {
+        Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+            (Some(unsigned), _) => (false, Quantity::from(unsigned)),
+            (None, Some(negative)) => (true, Quantity::from(negative.unsigned_abs())),
+            _ => return Err(invalid(format!("{} is not an integer", number))),
+        },
+        Value::String(text) => match text.strip_prefix('-') {
+            Some(digits) => (true, digits.parse()?),
+            None => (false, text.parse()?),
+        },
+        _ => return Err(invalid(format!("Expected an integer, got {}", value))),
+    };
+
+    let mut word = [0u8; 32];
+    let bytes = magnitude.as_bytes();
+    word[32 - bytes.len()..].copy_from_slice(bytes);
+    if negative && !signed {
+        return Err(out_of_range());
+    }
+    // the magnitude must fit in `bits` bits, one less when signed, with -2^(bits-1) allowed
+    let limit_bits = if signed { bits - 1 } else { bits };
+    let used_bits = match word.iter().position(|b| *b != 0) {
+        Some(i) => (32 - i) * 8 - word[i].leading_zeros() as usize,
+        None => 0,
+    };
+    let is_min = negative && used_bits == limit_bits + 1 && {
+        let mut min = [0u8; 32];
+        min[31 - limit_bits / 8] = 1 << (limit_bits % 8);
+        min == word
+    };
+    if used_bits > limit_bits && !is_min {
+        return Err(out_of_range());
+    }
+    if negative {
+        // two's complement over the whole word
+        for byte in word.iter_mut() {
+            *byte = !*byte;
+        }
+        for byte in word.iter_mut().rev() {
+            let (sum, carry) = byte.overflowing_add(1);
+            *byte = sum;
+            if !carry {
+                break;
+            }
+        }
+    }
+    Ok(word)
+}
+
+fn parse_size(
+    text: &str,
+    kind: &str,
+    min: usize,
+    max: usize,
+    step: usize,
+) -> Result<usize, EthereumError> {
+    match text.parse::<usize>() {
+        Ok(size) if size >= min && size <= max && size % step == 0 => Ok(size),
+        _ => Err(invalid(format!("Unknown type {}", kind))),
+    }
+}
+
+impl TypedData {
+    /// Parses the JSON of `eth_signTypedData_v4`
+    pub fn from_json(json: &str) -> Result<Self, EthereumError> {
+        serde_json::from_str(json).map_err(|e| invalid(e.to_string()))
+    }
+
+    /// The fields of a struct type. The domain type is inferred from the domain when it is
+    /// not listed.
+    fn fields(&self, kind: &str) -> Option<Vec<TypedField>> {
+        if let Some(fields) = self.types.get(kind) {
+            return Some(fields.clone());
+        }
+        if kind != DOMAIN_TYPE {
+            return None;
+        }
+        let fields = DOMAIN_FIELDS
+            .iter()
+            .filter(|(name, _)| self.domain.contains_key(*name))
+            .map(|(name, kind)| TypedField {
+                name: name.to_string(),
+                kind: kind.to_string(),
+            })
+            .collect();
+        Some(fields)
+    }
+
+    fn collect_dependencies(&self, kind: &str, found: &mut BTreeSet<String>) {
+        let kind = base_type(kind);
+        if found.contains(kind) {
+            return;
+        }
+        if let Some(fields) = self.fields(kind) {
+            found.insert(kind.to_string());
+            for field in fields {
+                self.collect_dependencies(&field.kind, found);
+            }
+        }
+    }
+
+    /// `Name(type name,...)` followed by the referenced struct types in alphabetical order
+    pub fn encode_type(&self, kind: &str) -> Result<String, EthereumError> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(kind, &mut dependencies);
+        if !dependencies.remove(kind) {
+            return Err(invalid(format!("Unknown struct type {}", kind)));
+        }
+        let mut encoded = String::new();
+        for name in std::iter::once(kind).chain(dependencies.iter().map(String::as_str)) {
+            let fields = self.fields(name).unwrap_or_default();
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| format!("{} {}", field.kind, field.name))
+                .collect();
+            encoded.push_str(&format!("{}({})", name, fields.join(",")));
+        }
+        Ok(encoded)
+    }
+
+    pub fn type_hash(&self, kind: &str) -> Result<[u8; 32], EthereumError> {
+        Ok(keccak256(self.encode_type(kind)?.as_bytes()))
+    }
+
+    /// `keccak256(type_hash || encoded fields)`
+    pub fn hash_struct(&self, kind: &str, value: &Value) -> Result<[u8; 32], EthereumError> {
+        let object = value
+            .as_object()
+            .ok_or_else(|| invalid(format!("Expected an object for {}", kind)))?;
+        let fields = self
+            .fields(kind)
+            .ok_or_else(|| invalid(format!("Unknown struct type {}", kind)))?;
+        let mut encoded = self.type_hash(kind)?.to_vec();
+        for field in fields {
+            let value = object.get(&field.name).unwrap_or(&Value::Null);
+            let word = self.encode_value(&field.kind, value).map_err(|e| match e {
+                EthereumError::InvalidTypedData(reason) => {
+                    invalid(format!("{}.{}: {}", kind, field.name, reason))
+                }
+                e => e,
+            })?;
+            encoded.extend_from_slice(&word);
+        }
+        Ok(keccak256(&encoded))
+    }
+
+    fn encode_value(&self, kind: &str, value: &Value) -> Result<[u8; 32], EthereumError> {
+        if self.fields(kind).is_some() {
+            // v4 encodes a missing struct as zeros
+            if value.is_null() {
+                return Ok([0u8; 32]);
+            }
+            return self.hash_struct(kind, value);
+        }
+        if value.is_null() {
+            return Err(invalid("Missing value"));
+        }
+        if let Some((inner, length)) = array_type(kind) {
+            let items = value
+                .as_array()
+                .ok_or_else(|| invalid(format!("Expected an array for {}", kind)))?;
+            if let Some(length) = length {
+                if length.parse::<usize>().ok() != Some(items.len()) {
+                    return Err(invalid(format!("Expected {} items for {}", length, kind)));
+                }
+            }
+            let mut encoded = Vec::with_capacity(items.len() * 32);
+            for item in items {
+                encoded.extend_from_slice(&self.encode_value(inner, item)?);
+            }
+            return Ok(keccak256(&encoded));
+        }
+
+        let mut word = [0u8; 32];
+        match kind {
+            "string" => {
+                let text = value.as_str().ok_or_else(|| invalid("Expected a string"))?;
+                word = keccak256(text.as_bytes());
+            }
+            "bytes" => word = keccak256(&decode_hex(value, kind)?),
+            "bool" => {
+                let flag = match value {
+                    Value::Bool(flag) => *flag,
+                    Value::String(text) if text == "true" || text == "false" => text == "true",
+                    _ => return Err(invalid("Expected a boolean")),
+                };
+                word[31] = u8::from(flag);
+            }
+            "address" => {
+                let text = value
+                    .as_str()
+                    .ok_or_else(|| invalid("Expected an address"))?;
+                let address: Address = text.parse()?;
+                word[12..].copy_from_slice(&address.0);
+            }
+            _ => {
+                if let Some(size) = kind.strip_prefix("bytes") {
+                    let size = parse_size(size, kind, 1, 32, 1)?;
+                    let bytes = decode_hex(value, kind)?;
+                    if bytes.len() != size {
+                        return Err(invalid(format!("Expected {} bytes", size)));
+                    }
+                    word[..size].copy_from_slice(&bytes);
+                } else if let Some(bits) = kind.strip_prefix("uint") {
+                    word = encode_integer(value, parse_size(bits, kind, 8, 256, 8)?, false)?;
+                } else if let Some(bits) = kind.strip_prefix("int") {
+                    word = encode_integer(value, parse_size(bits, kind, 8, 256, 8)?, true)?;
+                } else {
+                    return Err(invalid(format!("Unknown type {}", kind)));
+                }
+            }
+        }
+        Ok(word)
+    }
+
+    pub fn domain_separator(&self) -> Result<[u8; 32], EthereumError> {
+        self.hash_struct(DOMAIN_TYPE, &Value::Object(self.domain.clone()))
+    }
+
+    /// The domain separator, the struct hash of the message and the hash to sign
+    fn hashes(&self) -> Result<Hashes, EthereumError> {
+        let domain_separator = self.domain_separator()?;
+        let mut payload = vec![0x19, 0x01];
+        payload.extend_from_slice(&domain_separator);
+        let struct_hash = if self.primary_type == DOMAIN_TYPE {
+            None
+        } else {
+            let struct_hash = self.hash_struct(&self.primary_type, &self.message)?;
+            payload.extend_from_slice(&struct_hash);
+            Some(struct_hash)
+        };
+        Ok(Hashes {
+            domain_separator,
+            struct_hash,
+            hash: keccak256(&payload),
+        })
+    }
+
+    /// The hashes a signature of the typed data commits to
+    pub fn hash(&self) -> Result<TypedDataHash, EthereumError> {
+        let hashes = self.hashes()?;
+        Ok(TypedDataHash {
+            domain_separator: to_hex(&hashes.domain_separator),
+            struct_hash: hashes.struct_hash.as_ref().map(|hash| to_hex(hash)),
+            hash: to_hex(&hashes.hash),
+        })
+    }
+
+    /// The 32 byte hash to sign
+    pub fn signing_hash(&self) -> Result<[u8; 32], EthereumError> {
+        Ok(self.hashes()?.hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::ethereum::EthereumSigner;
+    use serde_json::json;
+
+    fn mail() -> serde_json::Value {
+        json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallet", "type": "address" }
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" }
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            },
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!"
+            }
+        })
+    }
+
+    #[test]
+    fn test_mail_example() {
+        // the example of EIP-712
+        let data: TypedData = serde_json::from_value(mail()).unwrap();
+        assert_eq!(
+            data.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        let hash = data.hash().unwrap();
+        assert_eq!(
+            hash.domain_separator,
+            "0xf2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            hash.struct_hash.unwrap(),
+            "0xc52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+        );
+        assert_eq!(
+            hash.hash,
+            "0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+
+        let signer = EthereumSigner::new(keccak256(b"cow").to_vec(), "".into());
+        assert_eq!(
+            signer.sign_typed(&data).unwrap().to_hex(),
+            "0x4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c"
+        );
+
+        // the domain type is inferred when it is not listed
+        let mut inferred = mail();
+        inferred["types"]
+            .as_object_mut()
+            .unwrap()
+            .remove("EIP712Domain");
+        let inferred: TypedData = serde_json::from_value(inferred).unwrap();
+        assert_eq!(inferred.hash().unwrap(), data.hash().unwrap());
+    }
+
+    #[test]
+    fn test_dynamic_types() {
+        let data: TypedData = serde_json::from_value(json!({
+            "types": {
+                "Batch": [
+                    { "name": "amounts", "type": "uint256[]" },
+                    { "name": "deltas", "type": "int8[2]" },
+                    { "name": "payload", "type": "bytes" },
+                    { "name": "tag", "type": "bytes4" },
+                    { "name": "groups", "type": "Group[]" }
+                ],
+                "Group": [{ "name": "members", "type": "address[]" }]
+            },
+            "primaryType": "Batch",
+            "domain": { "name": "Batch" },
+            "message": {
+                "amounts": [1, "0x02"],
+                "deltas": [-1, "127"],
+                "payload": "0x1234",
+                "tag": "0xdeadbeef",
+                "groups": [{ "members": ["0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"] }]
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            data.encode_type("Batch").unwrap(),
+            "Batch(uint256[] amounts,int8[2] deltas,bytes payload,bytes4 tag,Group[] groups)Group(address[] members)"
+        );
+
+        let word = |last: u8| {
+            let mut word = [0u8; 32];
+            word[31] = last;
+            word
+        };
+        assert_eq!(
+            data.encode_value("uint256[]", &json!([1, 2])).unwrap(),
+            keccak256(&[word(1), word(2)].concat())
+        );
+        assert_eq!(data.encode_value("int8", &json!(-1)).unwrap(), [0xff; 32]);
+        assert_eq!(
+            data.encode_value("bytes", &json!("0x1234")).unwrap(),
+            keccak256(&[0x12, 0x34])
+        );
+        let member = data
+            .encode_value(
+                "address",
+                &json!("0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"),
+            )
+            .unwrap();
+        let group = keccak256(&[data.type_hash("Group").unwrap(), keccak256(&member)].concat());
+        assert_eq!(
+            data.encode_value(
+                "Group[]",
+                &json!([{ "members": ["0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"] }])
+            )
+            .unwrap(),
+            keccak256(&group)
+        );
+        assert!(data.hash().is_ok());
+
+        assert!(data.encode_value("int8", &json!(128)).is_err());
+        assert!(data.encode_value("int8", &json!(-128)).is_ok());
+        assert!(data.encode_value("int8", &json!(-129)).is_err());
+        assert!(data.encode_value("uint8", &json!(-1)).is_err());
+        assert!(data.encode_value("uint8", &json!(256)).is_err());
+        assert!(data.encode_value("int8[2]", &json!([1])).is_err());
+        assert!(data.encode_value("bytes4", &json!("0x12")).is_err());
+        assert!(data.encode_value("uint7", &json!(1)).is_err());
+        assert!(data.encode_value("Missing", &json!(1)).is_err());
+    }
+}
@@ -1,24 +1,144 @@
 use std::fmt::{self, Display};
 
-use wasm_bindgen::JsValue;
-
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum PolkadotError {
-    FragmentationError,
+    /// The seed or key does not form a valid keypair
+    InvalidKey,
+    /// The secret URI or derivation junctions can not be parsed
+    InvalidSecretUri,
+    /// Not `sr25519`, `ed25519` or `ecdsa`
+    UnknownScheme(String),
+    /// Neither a network of the SS58 registry nor an unreserved prefix number
+    UnknownNetwork(String),
+    /// An SS58 address with a bad checksum, length or prefix
+    InvalidAddress(String),
+    /// An extrinsic is missing data or has malformed hashes
+    InvalidExtrinsic(String),
 }
 
 impl std::error::Error for PolkadotError {}
 
-impl Into<JsValue> for PolkadotError {
-    fn into(self) -> JsValue {
-        JsValue::from_str(&self.to_string())
+impl Display for PolkadotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolkadotError::InvalidKey => write!(f, "Invalid polkadot key"),
+            PolkadotError::InvalidSecretUri => write!(f, "Invalid secret URI"),
+            PolkadotError::UnknownScheme(scheme) => {
+                write!(f, "Unknown signature scheme '{}'", scheme)
+            }
+            PolkadotError::UnknownNetwork(network) => {
+                write!(f, "Unknown SS58 network '{}'", network)
+            }
+            PolkadotError::InvalidAddress(reason) => write!(f, "Invalid address: {}", reason),
+            PolkadotError::InvalidExtrinsic(reason) => write!(f, "Invalid extrinsic: {}", reason),
+        }
     }
 }
 
-impl Display for PolkadotError {
+#[derive(Debug, PartialEq)]
+pub enum EthereumError {
+    /// The key is not a valid secp256k1 secret key
+    InvalidKey,
+    /// A hash to sign is not 32 bytes
+    InvalidHash,
+    /// The signature can not be produced or does not recover a public key
+    InvalidSignature,
+    /// A transaction is missing fields or mixes fields of another transaction type
+    InvalidTransaction(String),
+    /// EIP-712 typed data does not match its types
+    InvalidTypedData(String),
+}
+
+impl std::error::Error for EthereumError {}
+
+impl Display for EthereumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EthereumError::InvalidKey => write!(f, "Invalid ethereum key"),
+            EthereumError::InvalidHash => write!(f, "Hashes to sign must be 32 bytes"),
+            EthereumError::InvalidSignature => write!(f, "Invalid ethereum signature"),
+            EthereumError::InvalidTransaction(reason) => {
+                write!(f, "Invalid transaction: {}", reason)
+            }
+            EthereumError::InvalidTypedData(reason) => write!(f, "Invalid typed data: {}", reason),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BitcoinError {
+    /// The key is not a valid secp256k1 secret key or tweaks to an invalid key
+    InvalidKey,
+    /// Not `mainnet`, `testnet`, `signet` or `regtest`
+    UnknownNetwork(String),
+    /// Not `p2pkh`, `p2sh-p2wpkh`, `p2wpkh` or `p2tr`
+    UnknownAddressType(String),
+    /// The PSBT can not be parsed or an input can not be signed
+    InvalidPsbt(String),
+    /// An input asks for a sighash type the signer does not produce
+    UnsupportedSighash(u32),
+}
+
+impl std::error::Error for BitcoinError {}
+
+impl Display for BitcoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitcoinError::InvalidKey => write!(f, "Invalid bitcoin key"),
+            BitcoinError::UnknownNetwork(network) => {
+                write!(f, "Unknown bitcoin network '{}'", network)
+            }
+            BitcoinError::UnknownAddressType(kind) => write!(f, "Unknown address type '{}'", kind),
+            BitcoinError::InvalidPsbt(reason) => write!(f, "Invalid PSBT: {}", reason),
+            BitcoinError::UnsupportedSighash(hash_type) => {
+                write!(f, "Unsupported sighash type 0x{:02x}", hash_type)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SolanaError {
+    /// The key is not a valid ed25519 secret key
+    InvalidKey,
+    /// A transaction, message or off-chain message can not be parsed or signed
+    InvalidMessage(String),
+    /// The signer is not a required signer of the message
+    NotASigner,
+}
+
+impl std::error::Error for SolanaError {}
+
+impl Display for SolanaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolanaError::InvalidKey => write!(f, "Invalid solana key"),
+            SolanaError::InvalidMessage(reason) => write!(f, "Invalid solana message: {}", reason),
+            SolanaError::NotASigner => {
+                write!(f, "The signer is not a required signer of the message")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CosmosError {
+    /// The key is not a valid secp256k1 secret key
+    InvalidKey,
+    /// The bech32 prefix is empty, too long or not lowercase printable ASCII
+    InvalidPrefix(String),
+    /// An amino or direct sign document is missing fields or can not be parsed
+    InvalidSignDoc(String),
+}
+
+impl std::error::Error for CosmosError {}
+
+impl Display for CosmosError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            PolkadotError::FragmentationError => write!(f, "Fragmentation error"),
+            CosmosError::InvalidKey => write!(f, "Invalid cosmos key"),
+            CosmosError::InvalidPrefix(prefix) => write!(f, "Invalid bech32 prefix '{}'", prefix),
+            CosmosError::InvalidSignDoc(reason) => write!(f, "Invalid sign doc: {}", reason),
         }
     }
 }
@@ -0,0 +1,379 @@
+use crate::chains::eip712::TypedData;
+use crate::chains::errors::EthereumError;
+use crate::chains::evm::{Authorization, AuthorizationRequest, SignedTransaction, Transaction};
+use crate::errors::EraError;
+#[cfg(feature = "wasm")]
+use crate::wasm_bindgen;
+use crate::Serialize;
+#[cfg(feature = "wasm")]
+use crate::{errors::from_js, to_value, JsValue};
+#[cfg(feature = "wasm")]
+use js_sys::{Array, BigInt, Object};
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+use sha3::{Digest, Keccak256};
+#[cfg(feature = "wasm")]
+use std::convert::TryFrom;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsCast;
+use zeroize::{Zeroize, Zeroizing};
+
+/// The default BIP-44 path of the first ethereum account, `m/44'/60'/0'/0/i` for the others
+pub const DEFAULT_PATH: &str = "m/44'/60'/0'/0/0";
+
+#[derive(Debug, Serialize)]
+pub struct KeyObject {
+    pub private_key: String,
+    /// Uncompressed SEC1 public key
+    pub public_key: String,
+    /// EIP-55 checksummed address
+    pub address: String,
+    pub path: String,
+}
+
+/// A secp256k1 signature with the id that recovers its public key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoverableSignature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    /// 0 or 1, the y parity of the signature
+    pub recovery_id: u8,
+}
+
+impl RecoverableSignature {
+    /// `r || s || v` with `v = 27 + recovery_id`, as returned by `personal_sign`
+    pub fn to_bytes(&self) -> [u8; 65] {
+        let mut bytes = [0u8; 65];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..64].copy_from_slice(&self.s);
+        bytes[64] = 27 + self.recovery_id;
+        bytes
+    }
+
+    /// Parses `r || s || v`, with `v` either 0/1 or 27/28
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EthereumError> {
+        if bytes.len() != 65 {
+            return Err(EthereumError::InvalidSignature);
+        }
+        let recovery_id = match bytes[64] {
+            0 | 27 => 0,
+            1 | 28 => 1,
+            _ => return Err(EthereumError::InvalidSignature),
+        };
+        let mut signature = Self {
+            r: [0u8; 32],
+            s: [0u8; 32],
+            recovery_id,
+        };
+        signature.r.copy_from_slice(&bytes[..32]);
+        signature.s.copy_from_slice(&bytes[32..64]);
+        Ok(signature)
+    }
+
+    pub fn to_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.to_bytes()))
+    }
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// The EIP-191 hash of a `personal_sign` message
+pub fn hash_message(message: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()));
+    hasher.update(message);
+    hasher.finalize().into()
+}
+
+/// Formats a 20 byte address with the EIP-55 mixed case checksum
+pub fn to_checksum_address(address: &[u8; 20]) -> String {
+    let lower = hex::encode(address);
+    let hash = keccak256(lower.as_bytes());
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+fn address_of(key: &VerifyingKey) -> [u8; 20] {
+    let point = key.to_encoded_point(false);
+    let hash = keccak256(&point.as_bytes()[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
+}
+
+/// Recovers the checksummed address that signed a 32 byte hash
+pub fn recover_address(
+    hash: &[u8],
+    signature: &RecoverableSignature,
+) -> Result<String, EthereumError> {
+    if hash.len() != 32 {
+        return Err(EthereumError::InvalidHash);
+    }
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&signature.r);
+    bytes[32..].copy_from_slice(&signature.s);
+    let ecdsa = Signature::from_slice(&bytes).map_err(|_| EthereumError::InvalidSignature)?;
+    let recovery_id =
+        RecoveryId::from_byte(signature.recovery_id).ok_or(EthereumError::InvalidSignature)?;
+    let key = VerifyingKey::recover_from_prehash(hash, &ecdsa, recovery_id)
+        .map_err(|_| EthereumError::InvalidSignature)?;
+    Ok(to_checksum_address(&address_of(&key)))
+}
+
+/// serde-wasm-bindgen only reads BigInts up to `u64::MAX`, but ethers and viem pass amounts
+/// like `parseEther("100")` as BigInts. Larger ones, in plain objects and arrays, are turned
+/// into decimal strings, which `Quantity` reads up to 256 bits.
+#[cfg(feature = "wasm")]
+fn wide_bigints(value: JsValue) -> JsValue {
+    if value.is_bigint() {
+        let bigint: BigInt = value.unchecked_into();
+        if u64::try_from(bigint.clone()).is_ok() {
+            return bigint.into();
+        }
+        return bigint
+            .to_string(10)
+            .map(JsValue::from)
+            .unwrap_or_else(|_| bigint.into());
+    }
+    if Array::is_array(&value) {
+        let array: Array = value.unchecked_into();
+        return array.iter().map(wide_bigints).collect::<Array>().into();
+    }
+    let plain = value.is_object() && {
+        let prototype = JsValue::from(Object::get_prototype_of(&value));
+        prototype.is_null() || prototype == JsValue::from(Object::get_prototype_of(&Object::new()))
+    };
+    if !plain {
+        return value;
+    }
+    let entries = Object::entries(value.unchecked_ref());
+    for entry in entries.iter() {
+        let entry: Array = entry.unchecked_into();
+        entry.set(1, wide_bigints(entry.get(1)));
+    }
+    Object::from_entries(&entries)
+        .map(JsValue::from)
+        .unwrap_or(value)
+}
+
+#[cfg(feature = "wasm")]
+fn typed_data_from(value: JsValue) -> Result<TypedData, JsValue> {
+    match value.as_string() {
+        Some(json) => Ok(TypedData::from_json(&json)?),
+        None => Ok(from_js(wide_bigints(value), "typed data")?),
+    }
+}
+
+/// Hashes EIP-712 typed data without signing it, to preview a signature request.
+/// Returns `{ domain_separator, struct_hash, hash }`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = hashTypedData)]
+pub fn hash_typed_data(typed_data: JsValue) -> Result<JsValue, JsValue> {
+    Ok(to_value(&typed_data_from(typed_data)?.hash()?)?)
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct EthereumSigner {
+    signer: Zeroizing<Vec<u8>>,
+    path: String,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl EthereumSigner {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn fetch_key(&self) -> Result<JsValue, JsValue> {
+        Ok(to_value(&self.get_key()?)?)
+    }
+
+    /// The EIP-55 checksummed address of the signer
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn address(&self) -> Result<String, EraError> {
+        Ok(to_checksum_address(&address_of(
+            self.key()?.verifying_key(),
+        )))
+    }
+
+    /// Signs a message with the EIP-191 prefix, the 65 byte `r || s || v` hex of `personal_sign`
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn personal_sign(&self, message: &[u8]) -> Result<String, EraError> {
+        Ok(self.sign_message(message)?.to_hex())
+    }
+
+    /// Signs a 32 byte hash as is, the 65 byte `r || s || v` hex
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn sign_hash(&self, hash: &[u8]) -> Result<String, EraError> {
+        Ok(self.sign_prehash(hash)?.to_hex())
+    }
+
+    /// Signs a transaction object, returns `{ raw, hash }` with the `0x` hex of the signed
+    /// transaction and its hash.
+    ///
+    /// Takes the camelCase requests of ethers and viem. `type` is `legacy`, `eip2930`, `eip1559`
+    /// or `eip7702`, or their type number. Amounts may be numbers, BigInts, decimal strings or
+    /// `0x` hex strings.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn sign_transaction(&self, transaction: JsValue) -> Result<JsValue, JsValue> {
+        let transaction: Transaction = from_js(wide_bigints(transaction), "transaction")?;
+        Ok(to_value(&self.sign_tx(&transaction)?)?)
+    }
+
+    /// Signs an EIP-7702 `{ chainId, address, nonce }` delegation, returns the entry of an
+    /// `authorizationList`
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn sign_authorization(&self, authorization: JsValue) -> Result<JsValue, JsValue> {
+        let request: AuthorizationRequest = from_js(wide_bigints(authorization), "authorization")?;
+        Ok(to_value(&self.authorize(&request)?)?)
+    }
+
+    /// Signs EIP-712 typed data like `eth_signTypedData_v4`, given as its JSON string or
+    /// object `{ types, primaryType, domain, message }`. Returns the 65 byte `r || s || v` hex.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn sign_typed_data(&self, typed_data: JsValue) -> Result<String, JsValue> {
+        let typed_data = typed_data_from(typed_data)?;
+        Ok(self.sign_typed(&typed_data)?.to_hex())
+    }
+
+    /// Zeroes the key of the signer. The signer is also wiped when it is freed.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn wipe(&mut self) {
+        self.signer.zeroize();
+    }
+}
+
+impl EthereumSigner {
+    pub fn new(signer: Vec<u8>, path: String) -> Self {
+        Self {
+            signer: Zeroizing::new(signer),
+            path,
+        }
+    }
+
+    fn key(&self) -> Result<SigningKey, EthereumError> {
+        SigningKey::from_slice(&self.signer).map_err(|_| EthereumError::InvalidKey)
+    }
+
+    /// The keys and ethereum address of the signer
+    pub fn get_key(&self) -> Result<KeyObject, EthereumError> {
+        let key = self.key()?;
+        let public = key.verifying_key().to_encoded_point(false);
+        Ok(KeyObject {
+            private_key: format!("0x{}", hex::encode(&*self.signer)),
+            public_key: format!("0x{}", hex::encode(public.as_bytes())),
+            address: to_checksum_address(&address_of(key.verifying_key())),
+            path: self.path.clone(),
+        })
+    }
+
+    /// Signs a 32 byte hash with a low-s recoverable signature
+    pub fn sign_prehash(&self, hash: &[u8]) -> Result<RecoverableSignature, EthereumError> {
+        if hash.len() != 32 {
+            return Err(EthereumError::InvalidHash);
+        }
+        let (signature, recovery_id) = self
+            .key()?
+            .sign_prehash_recoverable(hash)
+            .map_err(|_| EthereumError::InvalidSignature)?;
+        let (r, s) = signature.split_bytes();
+        Ok(RecoverableSignature {
+            r: r.into(),
+            s: s.into(),
+            recovery_id: recovery_id.to_byte(),
+        })
+    }
+
+    /// Signs the EIP-191 hash of a message
+    pub fn sign_message(&self, message: &[u8]) -> Result<RecoverableSignature, EthereumError> {
+        self.sign_prehash(&hash_message(message))
+    }
+
+    /// Signs a transaction, the raw signed transaction and its hash
+    pub fn sign_tx(&self, transaction: &Transaction) -> Result<SignedTransaction, EthereumError> {
+        let signature = self.sign_prehash(&transaction.signing_hash()?)?;
+        transaction.signed_with(&signature)
+    }
+
+    /// Signs EIP-712 typed data
+    pub fn sign_typed(
+        &self,
+        typed_data: &TypedData,
+    ) -> Result<RecoverableSignature, EthereumError> {
+        self.sign_prehash(&typed_data.signing_hash()?)
+    }
+
+    /// Signs an EIP-7702 delegation of this account
+    pub fn authorize(
+        &self,
+        request: &AuthorizationRequest,
+    ) -> Result<Authorization, EthereumError> {
+        let signature = self.sign_prehash(&request.signing_hash())?;
+        Ok(Authorization::new(request, &signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    fn signer() -> EthereumSigner {
+        let key = hex::decode("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+            .unwrap();
+        EthereumSigner::new(key, DEFAULT_PATH.into())
+    }
+
+    #[test]
+    fn test_checksum_address() {
+        let address = hex::decode("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        let address: [u8; 20] = address.as_slice().try_into().unwrap();
+        assert_eq!(
+            to_checksum_address(&address),
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+        assert_eq!(
+            signer().address().unwrap(),
+            "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23"
+        );
+    }
+
+    #[test]
+    fn test_personal_sign() {
+        let signer = signer();
+        assert_eq!(
+            hex::encode(hash_message(b"Some data")),
+            "1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655"
+        );
+        assert_eq!(
+            signer.personal_sign(b"Some data").unwrap(),
+            "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c"
+        );
+
+        let hash = keccak256(b"raw");
+        let signature = signer.sign_prehash(&hash).unwrap();
+        let parsed = RecoverableSignature::from_bytes(&signature.to_bytes()).unwrap();
+        assert_eq!(parsed, signature);
+        assert_eq!(
+            recover_address(&hash, &signature).unwrap(),
+            signer.address().unwrap()
+        );
+        assert_eq!(
+            signer.sign_prehash(b"short").unwrap_err(),
+            EthereumError::InvalidHash
+        );
+    }
+}
@@ -0,0 +1,797 @@
+//! EVM transactions: legacy (EIP-155), access list (EIP-2930), dynamic fee (EIP-1559) and
+//! set code (EIP-7702), deserialized from the camelCase request objects of ethers and viem.
+//! The snake_case field names are accepted too.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::chains::errors::EthereumError;
+use crate::chains::ethereum::{keccak256, to_checksum_address, RecoverableSignature};
+use crate::chains::rlp;
+
+/// Magic byte prefixed to the payload of an EIP-7702 authorization
+const AUTHORIZATION_MAGIC: u8 = 0x05;
+
+/// Largest integer a javascript number holds exactly
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+fn invalid(message: impl Into<String>) -> EthereumError {
+    EthereumError::InvalidTransaction(message.into())
+}
+
+fn trim_zeros(value: &[u8]) -> Vec<u8> {
+    let start = value.iter().position(|b| *b != 0).unwrap_or(value.len());
+    value[start..].to_vec()
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>, EthereumError> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    hex::decode(digits).map_err(|e| invalid(format!("Invalid hex '{}': {}", value, e)))
+}
+
+/// An unsigned integer of up to 256 bits, kept as big-endian bytes without leading zeros.
+///
+/// Deserializes from a number, a decimal string or a `0x` hex string, so amounts in wei
+/// do not lose precision in javascript. BigInts beyond `u64::MAX` reach it as decimal strings,
+/// see `wide_bigints` in the ethereum signer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Quantity(Vec<u8>);
+
+impl Quantity {
+    /// Big-endian bytes without leading zeros, empty for zero
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn from_be_bytes(bytes: &[u8]) -> Result<Self, EthereumError> {
+        let trimmed = trim_zeros(bytes);
+        if trimmed.len() > 32 {
+            return Err(invalid("Quantity exceeds 256 bits"));
+        }
+        Ok(Self(trimmed))
+    }
+
+    fn from_decimal(value: &str) -> Result<Self, EthereumError> {
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid(format!("Invalid quantity '{}'", value)));
+        }
+        let mut word = [0u8; 32];
+        for digit in value.bytes() {
+            // word = word * 10 + digit
+            let mut carry = u16::from(digit - b'0');
+            for byte in word.iter_mut().rev() {
+                let product = u16::from(*byte) * 10 + carry;
+                *byte = product as u8;
+                carry = product >> 8;
+            }
+            if carry != 0 {
+                return Err(invalid("Quantity exceeds 256 bits"));
+            }
+        }
+        Self::from_be_bytes(&word)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        rlp::uint(&self.0)
+    }
+}
+
+impl From<u64> for Quantity {
+    fn from(value: u64) -> Self {
+        Self(trim_zeros(&value.to_be_bytes()))
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = EthereumError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.strip_prefix("0x") {
+            Some(digits) => {
+                let padded = if digits.len() % 2 == 1 {
+                    format!("0{}", digits)
+                } else {
+                    digits.to_string()
+                };
+                Self::from_be_bytes(&decode_hex(&padded)?)
+            }
+            None => Self::from_decimal(value),
+        }
+    }
+}
+
+impl Serialize for Quantity {
+    /// The `0x` hex of ethereum JSON-RPC quantities, `0x0` for zero
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let digits = hex::encode(&self.0);
+        let digits = digits.trim_start_matches('0');
+        let digits = if digits.is_empty() { "0" } else { digits };
+        serializer.serialize_str(&format!("0x{}", digits))
+    }
+}
+
+impl<'de> Deserialize<'de> for Quantity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct QuantityVisitor;
+
+        impl<'de> Visitor<'de> for QuantityVisitor {
+            type Value = Quantity;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an unsigned integer, as a number, a decimal string or a 0x hex string")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Quantity, E> {
+                Ok(value.into())
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Quantity, E> {
+                u64::try_from(value)
+                    .map(Quantity::from)
+                    .map_err(|_| E::custom("Quantities can not be negative"))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Quantity, E> {
+                if value < 0.0 || value.fract() != 0.0 || value > MAX_SAFE_INTEGER {
+                    return Err(E::custom(
+                        "Quantities must be safe integers, pass larger ones as strings",
+                    ));
+                }
+                Ok((value as u64).into())
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Quantity, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(QuantityVisitor)
+    }
+}
+
+/// A 20 byte account address. Mixed case addresses must carry a valid EIP-55 checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Address(pub [u8; 20]);
+
+impl FromStr for Address {
+    type Err = EthereumError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_hex(value)?;
+        let mut address = [0u8; 20];
+        if bytes.len() != 20 {
+            return Err(invalid(format!("Address '{}' is not 20 bytes", value)));
+        }
+        address.copy_from_slice(&bytes);
+        let digits = value.strip_prefix("0x").unwrap_or(value);
+        let mixed_case = digits.chars().any(|c| c.is_ascii_uppercase())
+            && digits.chars().any(|c| c.is_ascii_lowercase());
+        if mixed_case && to_checksum_address(&address)[2..] != *digits {
+            return Err(invalid(format!(
+                "Address '{}' has an invalid checksum",
+                value
+            )));
+        }
+        Ok(Self(address))
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_checksum_address(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Arbitrary bytes given as a `0x` hex string
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HexBytes(pub Vec<u8>);
+
+impl<'de> Deserialize<'de> for HexBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        decode_hex(&value).map(HexBytes).map_err(de::Error::custom)
+    }
+}
+
+/// The transaction `type`, by name (`eip1559`, as viem does) or by EIP-2718 number (`2` or
+/// `"0x2"`, as ethers and JSON-RPC do)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionType {
+    /// Type 0, signed with the EIP-155 chain id
+    Legacy,
+    /// Type 1
+    Eip2930,
+    /// Type 2
+    Eip1559,
+    /// Type 4
+    Eip7702,
+}
+
+impl TransactionType {
+    /// The EIP-2718 type byte
+    pub fn id(self) -> u8 {
+        match self {
+            TransactionType::Legacy => 0x00,
+            TransactionType::Eip2930 => 0x01,
+            TransactionType::Eip1559 => 0x02,
+            TransactionType::Eip7702 => 0x04,
+        }
+    }
+
+    fn from_id(id: u64) -> Option<Self> {
+        match id {
+            0x00 => Some(TransactionType::Legacy),
+            0x01 => Some(TransactionType::Eip2930),
+            0x02 => Some(TransactionType::Eip1559),
+            0x04 => Some(TransactionType::Eip7702),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TransactionType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TypeVisitor;
+
+        impl<'de> Visitor<'de> for TypeVisitor {
+            type Value = TransactionType;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("legacy, eip2930, eip1559, eip7702 or their type number")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<TransactionType, E> {
+                TransactionType::from_id(value)
+                    .ok_or_else(|| E::custom(format!("Unsupported transaction type {}", value)))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<TransactionType, E> {
+                u64::try_from(value)
+                    .map_err(|_| E::custom(format!("Unsupported transaction type {}", value)))
+                    .and_then(|value| self.visit_u64(value))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<TransactionType, E> {
+                if value < 0.0 || value.fract() != 0.0 || value > 255.0 {
+                    return Err(E::custom(format!("Unsupported transaction type {}", value)));
+                }
+                self.visit_u64(value as u64)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<TransactionType, E> {
+                match value {
+                    "legacy" => Ok(TransactionType::Legacy),
+                    "eip2930" => Ok(TransactionType::Eip2930),
+                    "eip1559" => Ok(TransactionType::Eip1559),
+                    "eip7702" => Ok(TransactionType::Eip7702),
+                    _ => {
+                        let id = value
+                            .strip_prefix("0x")
+                            .and_then(|digits| u64::from_str_radix(digits, 16).ok())
+                            .ok_or_else(|| {
+                                E::custom(format!("Unsupported transaction type '{}'", value))
+                            })?;
+                        self.visit_u64(id)
+                    }
+                }
+            }
+        }
+
+        deserializer.deserialize_any(TypeVisitor)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct AccessListItem {
+    pub address: Address,
+    /// 32 byte storage slots
+    #[serde(default, alias = "storage_keys")]
+    pub storage_keys: Vec<HexBytes>,
+}
+
+impl AccessListItem {
+    fn encode(&self) -> Result<Vec<u8>, EthereumError> {
+        let mut keys = Vec::with_capacity(self.storage_keys.len());
+        for key in &self.storage_keys {
+            if key.0.len() != 32 {
+                return Err(invalid("Storage keys must be 32 bytes"));
+            }
+            keys.push(rlp::bytes(&key.0));
+        }
+        Ok(rlp::list(&[rlp::bytes(&self.address.0), rlp::list(&keys)]))
+    }
+}
+
+/// The delegation an EIP-7702 account signs before it is authorized
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct AuthorizationRequest {
+    /// 0 authorizes the delegation on every chain
+    #[serde(alias = "chain_id")]
+    pub chain_id: Quantity,
+    /// The contract whose code the account delegates to
+    pub address: Address,
+    pub nonce: Quantity,
+}
+
+impl AuthorizationRequest {
+    /// `keccak256(0x05 || rlp([chain_id, address, nonce]))`
+    pub fn signing_hash(&self) -> [u8; 32] {
+        let mut payload = vec![AUTHORIZATION_MAGIC];
+        payload.extend(rlp::list(&[
+            self.chain_id.encode(),
+            rlp::bytes(&self.address.0),
+            self.nonce.encode(),
+        ]));
+        keccak256(&payload)
+    }
+}
+
+/// A signed EIP-7702 authorization, an entry of `authorizationList`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct Authorization {
+    #[serde(alias = "chain_id")]
+    pub chain_id: Quantity,
+    pub address: Address,
+    pub nonce: Quantity,
+    #[serde(alias = "y_parity")]
+    pub y_parity: u8,
+    pub r: Quantity,
+    pub s: Quantity,
+}
+
+impl Authorization {
+    pub fn new(request: &AuthorizationRequest, signature: &RecoverableSignature) -> Self {
+        Self {
+            chain_id: request.chain_id.clone(),
+            address: request.address,
+            nonce: request.nonce.clone(),
+            y_parity: signature.recovery_id,
+            r: Quantity(trim_zeros(&signature.r)),
+            s: Quantity(trim_zeros(&signature.s)),
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        rlp::list(&[
+            self.chain_id.encode(),
+            rlp::bytes(&self.address.0),
+            self.nonce.encode(),
+            rlp::u64(u64::from(self.y_parity)),
+            self.r.encode(),
+            self.s.encode(),
+        ])
+    }
+}
+
+/// An unsigned EVM transaction. The fee fields depend on `type`: `gasPrice` for legacy and
+/// EIP-2930 transactions, `maxFeePerGas` and `maxPriorityFeePerGas` for the others.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct Transaction {
+    #[serde(rename = "type")]
+    pub kind: TransactionType,
+    #[serde(alias = "chain_id")]
+    pub chain_id: u64,
+    pub nonce: Quantity,
+    #[serde(default, alias = "gas_price")]
+    pub gas_price: Option<Quantity>,
+    #[serde(default, alias = "max_priority_fee_per_gas")]
+    pub max_priority_fee_per_gas: Option<Quantity>,
+    #[serde(default, alias = "max_fee_per_gas")]
+    pub max_fee_per_gas: Option<Quantity>,
+    /// `gas` in viem
+    #[serde(alias = "gas_limit", alias = "gas")]
+    pub gas_limit: Quantity,
+    /// Missing for contract creation
+    #[serde(default)]
+    pub to: Option<Address>,
+    #[serde(default)]
+    pub value: Quantity,
+    #[serde(default)]
+    pub data: HexBytes,
+    #[serde(default, alias = "access_list")]
+    pub access_list: Vec<AccessListItem>,
+    #[serde(default, alias = "authorization_list")]
+    pub authorization_list: Vec<Authorization>,
+}
+
+/// A signed transaction, ready for `eth_sendRawTransaction`
+#[derive(Debug, Clone, Serialize)]
+pub struct SignedTransaction {
+    pub raw: String,
+    pub hash: String,
+}
+
+impl Transaction {
+    fn validate(&self) -> Result<(), EthereumError> {
+        let legacy_fees = matches!(
+            self.kind,
+            TransactionType::Legacy | TransactionType::Eip2930
+        );
+        let dynamic_fees =
+            self.max_fee_per_gas.is_some() || self.max_priority_fee_per_gas.is_some();
+        if legacy_fees && (self.gas_price.is_none() || dynamic_fees) {
+            return Err(invalid(
+                "Legacy and EIP-2930 transactions take gasPrice and no max fees",
+            ));
+        }
+        if !legacy_fees
+            && (self.gas_price.is_some()
+                || self.max_fee_per_gas.is_none()
+                || self.max_priority_fee_per_gas.is_none())
+        {
+            return Err(invalid(
+                "EIP-1559 and EIP-7702 transactions take maxFeePerGas and maxPriorityFeePerGas, not gasPrice",
+            ));
+        }
+        if self.kind == TransactionType::Legacy && !self.access_list.is_empty() {
+            return Err(invalid("Legacy transactions have no access list"));
+        }
+        if self.kind == TransactionType::Eip7702 {
+            if self.to.is_none() {
+                return Err(invalid("EIP-7702 transactions can not create contracts"));
+            }
+            if self.authorization_list.is_empty() {
+                return Err(invalid("EIP-7702 transactions need an authorization list"));
+            }
+        } else if !self.authorization_list.is_empty() {
+            return Err(invalid(
+                "Only EIP-7702 transactions have an authorization list",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Encodes the transaction, with its signature or as the payload to sign
+    pub fn encode(
+        &self,
+        signature: Option<&RecoverableSignature>,
+    ) -> Result<Vec<u8>, EthereumError> {
+        self.validate()?;
+        let quantity =
+            |value: &Option<Quantity>| value.as_ref().map(Quantity::encode).unwrap_or_default();
+        let to = rlp::bytes(self.to.as_ref().map(|to| &to.0[..]).unwrap_or(&[]));
+        let mut access_list = Vec::with_capacity(self.access_list.len());
+        for item in &self.access_list {
+            access_list.push(item.encode()?);
+        }
+
+        let mut fields = match self.kind {
+            TransactionType::Legacy => vec![self.nonce.encode(), quantity(&self.gas_price)],
+            TransactionType::Eip2930 => vec![
+                rlp::u64(self.chain_id),
+                self.nonce.encode(),
+                quantity(&self.gas_price),
+            ],
+            TransactionType::Eip1559 | TransactionType::Eip7702 => vec![
+                rlp::u64(self.chain_id),
+                self.nonce.encode(),
+                quantity(&self.max_priority_fee_per_gas),
+                quantity(&self.max_fee_per_gas),
+            ],
+        };
+        fields.push(self.gas_limit.encode());
+        fields.push(to);
+        fields.push(self.value.encode());
+        fields.push(rlp::bytes(&self.data.0));
+
+        if self.kind == TransactionType::Legacy {
+            // EIP-155 folds the chain id into v
+            match signature {
+                Some(signature) => {
+                    let v = self
+                        .chain_id
+                        .checked_mul(2)
+                        .and_then(|v| v.checked_add(35 + u64::from(signature.recovery_id)))
+                        .ok_or_else(|| invalid("Chain id is too large for EIP-155"))?;
+                    fields.push(rlp::u64(v));
+                    fields.push(rlp::uint(&signature.r));
+                    fields.push(rlp::uint(&signature.s));
+                }
+                None => {
+                    fields.push(rlp::u64(self.chain_id));
+                    fields.push(rlp::u64(0));
+                    fields.push(rlp::u64(0));
+                }
+            }
+            return Ok(rlp::list(&fields));
+        }
+
+        fields.push(rlp::list(&access_list));
+        if self.kind == TransactionType::Eip7702 {
+            let authorizations: Vec<Vec<u8>> = self
+                .authorization_list
+                .iter()
+                .map(Authorization::encode)
+                .collect();
+            fields.push(rlp::list(&authorizations));
+        }
+        if let Some(signature) = signature {
+            fields.push(rlp::u64(u64::from(signature.recovery_id)));
+            fields.push(rlp::uint(&signature.r));
+            fields.push(rlp::uint(&signature.s));
+        }
+        let mut encoded = vec![self.kind.id()];
+        encoded.extend(rlp::list(&fields));
+        Ok(encoded)
+    }
+
+    /// The keccak256 hash the sender signs
+    pub fn signing_hash(&self) -> Result<[u8; 32], EthereumError> {
+        Ok(keccak256(&self.encode(None)?))
+    }
+
+    /// The raw transaction and its hash under a signature of `signing_hash`
+    pub fn signed_with(
+        &self,
+        signature: &RecoverableSignature,
+    ) -> Result<SignedTransaction, EthereumError> {
+        let raw = self.encode(Some(signature))?;
+        Ok(SignedTransaction {
+            hash: format!("0x{}", hex::encode(keccak256(&raw))),
+            raw: format!("0x{}", hex::encode(raw)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::ethereum::{recover_address, EthereumSigner};
+    use serde_json::json;
+
+    fn signer() -> EthereumSigner {
+        let key = hex::decode("4646464646464646464646464646464646464646464646464646464646464646")
+            .unwrap();
+        EthereumSigner::new(key, "".into())
+    }
+
+    fn transaction(value: serde_json::Value) -> Transaction {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_quantities() {
+        let quantity: Quantity = "1000000000000000000".parse().unwrap();
+        assert_eq!(quantity, "0xde0b6b3a7640000".parse().unwrap());
+        assert_eq!(
+            quantity.as_bytes(),
+            hex::decode("0de0b6b3a7640000").unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&quantity).unwrap(),
+            "0xde0b6b3a7640000"
+        );
+        assert_eq!(serde_json::to_value(Quantity::from(0)).unwrap(), "0x0");
+        // 100 ether is past u64::MAX, javascript BigInts this wide arrive as decimal strings
+        let wide: Quantity = serde_json::from_value(json!("100000000000000000000")).unwrap();
+        assert_eq!(wide.as_bytes(), hex::decode("056bc75e2d63100000").unwrap());
+        assert!(serde_json::from_value::<Quantity>(json!(-1)).is_err());
+        assert!(serde_json::from_value::<Quantity>(json!(1.5)).is_err());
+        let max = format!("0x{}", "ff".repeat(32));
+        assert!(max.parse::<Quantity>().is_ok());
+        assert!(format!("{}00", max).parse::<Quantity>().is_err());
+
+        assert!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse::<Address>()
+            .is_ok());
+        assert!("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+            .parse::<Address>()
+            .is_ok());
+        assert!("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse::<Address>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_legacy_transaction() {
+        // the example of EIP-155
+        let tx = transaction(json!({
+            "type": "legacy",
+            "chain_id": 1,
+            "nonce": 9,
+            "gas_price": "20000000000",
+            "gas_limit": 21000,
+            "to": "0x3535353535353535353535353535353535353535",
+            "value": "1000000000000000000",
+        }));
+        assert_eq!(
+            hex::encode(tx.signing_hash().unwrap()),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+        let signed = signer().sign_tx(&tx).unwrap();
+        assert_eq!(
+            signed.raw,
+            "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+        assert_eq!(
+            signed.hash,
+            format!(
+                "0x{}",
+                hex::encode(keccak256(&hex::decode(&signed.raw[2..]).unwrap()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_typed_transactions() {
+        let signer = signer();
+        let authorization = signer
+            .authorize(&AuthorizationRequest {
+                chain_id: 1.into(),
+                address: "0x3535353535353535353535353535353535353535"
+                    .parse()
+                    .unwrap(),
+                nonce: 0.into(),
+            })
+            .unwrap();
+        assert_eq!(authorization.y_parity, 0);
+        assert_eq!(
+            hex::encode(authorization.r.as_bytes()),
+            "62ffd2b09baa5c561d162560dba5b8eac35ddd822fc9b57ae9c358a1f6d028c3"
+        );
+        let access_list = json!([{
+            "address": "0x3535353535353535353535353535353535353535",
+            "storageKeys": [format!("0x{}", "00".repeat(32))],
+        }]);
+        // ethers style requests, cross-checked against an independent implementation
+        // that reproduces the EIP-155 example above
+        let cases = [
+            (
+                json!({ "type": 1, "gasPrice": 1, "accessList": access_list }),
+                "01f89c01800182ea6094353535353535353535353535353535353535353580821234f838f7943535353535353535353535353535353535353535e1a0000000000000000000000000000000000000000000000000000000000000000001a0173ad89f37f6e5bc95f543750d2401b1461a3712d6ac35ed01abc68b63148a20a07ef12b97f45679e6d2147eec7f6b2b15dd4214613682b422a4c0c04760f0ddde",
+            ),
+            (
+                json!({ "type": 2, "maxFeePerGas": 2, "maxPriorityFeePerGas": 1 }),
+                "02f8640180010282ea6094353535353535353535353535353535353535353580821234c080a01e02a0497cfc0a7e578a052309ea8d37182a52a7bcb3a35868d83830c761df35a075dd2ea3226036208b07104c2d3e6f7a173466bae51984b659fc9762488947fd",
+            ),
+            (
+                json!({
+                    "type": "0x4",
+                    "maxFeePerGas": 2,
+                    "maxPriorityFeePerGas": 1,
+                    "authorizationList": [serde_json::to_value(&authorization).unwrap()],
+                }),
+                "04f8c20180010282ea6094353535353535353535353535353535353535353580821234c0f85cf85a019435353535353535353535353535353535353535358080a062ffd2b09baa5c561d162560dba5b8eac35ddd822fc9b57ae9c358a1f6d028c3a069658643036aed4250120760ec386e4a488ea511bc8b9c3bac098f1bc825b13e01a0b0c5102262125dbbc8f42078e4a9372b72d57ee3a9f75a4c8f48a74d9d8efacea06b6ed1e030173e09f392154f5a1efbe33b759d9a5bd02afcb545529887af4c06",
+            ),
+        ];
+        for (mut fields, raw) in cases.iter().cloned() {
+            let object = fields.as_object_mut().unwrap();
+            object.insert("chainId".into(), json!(1));
+            object.insert("nonce".into(), json!(0));
+            object.insert("gasLimit".into(), json!(60000));
+            object.insert(
+                "to".into(),
+                json!("0x3535353535353535353535353535353535353535"),
+            );
+            object.insert("data".into(), json!("0x1234"));
+            let tx = transaction(fields);
+
+            let signed = signer.sign_tx(&tx).unwrap();
+            assert_eq!(signed.raw, format!("0x{}", raw));
+        }
+
+        // the field order of EIP-1559
+        let tx = transaction(json!({
+            "type": "eip1559",
+            "chain_id": 1,
+            "nonce": 0,
+            "max_priority_fee_per_gas": 1,
+            "max_fee_per_gas": 2,
+            "gas_limit": 60000,
+            "to": "0x3535353535353535353535353535353535353535",
+            "data": "0x1234",
+        }));
+        let mut expected = vec![0x02];
+        expected.extend(rlp::list(&[
+            rlp::u64(1),
+            rlp::u64(0),
+            rlp::u64(1),
+            rlp::u64(2),
+            rlp::u64(60000),
+            rlp::bytes(&[0x35; 20]),
+            rlp::u64(0),
+            rlp::bytes(&[0x12, 0x34]),
+            rlp::list(&[]),
+        ]));
+        assert_eq!(tx.encode(None).unwrap(), expected);
+
+        let hash = AuthorizationRequest {
+            chain_id: 1.into(),
+            address: authorization.address,
+            nonce: 0.into(),
+        }
+        .signing_hash();
+        let mut signature = RecoverableSignature {
+            r: [0u8; 32],
+            s: [0u8; 32],
+            recovery_id: authorization.y_parity,
+        };
+        signature.r[32 - authorization.r.as_bytes().len()..]
+            .copy_from_slice(authorization.r.as_bytes());
+        signature.s[32 - authorization.s.as_bytes().len()..]
+            .copy_from_slice(authorization.s.as_bytes());
+        assert_eq!(
+            recover_address(&hash, &signature).unwrap(),
+            signer.address().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_transactions() {
+        let base = json!({ "chain_id": 1, "nonce": 0, "gas_limit": 21000 });
+        let invalid = [
+            json!({ "type": "legacy" }),
+            json!({ "type": "eip1559", "gas_price": 1 }),
+            json!({ "type": "eip7702", "max_fee_per_gas": 2, "max_priority_fee_per_gas": 1, "to": "0x3535353535353535353535353535353535353535" }),
+        ];
+        for fields in invalid.iter() {
+            let mut object = base.clone();
+            for (key, value) in fields.as_object().unwrap() {
+                object[key] = value.clone();
+            }
+            let error = transaction(object).signing_hash().unwrap_err();
+            assert!(matches!(error, EthereumError::InvalidTransaction(_)));
+        }
+        assert!(
+            serde_json::from_value::<Transaction>(json!({ "type": "eip1559", "gas": 1 })).is_err()
+        );
+        for kind in [json!(3), json!("0x3"), json!("eip4844"), json!(-1)].iter() {
+            let mut object = base.clone();
+            object["type"] = kind.clone();
+            assert!(serde_json::from_value::<Transaction>(object).is_err());
+        }
+        let mut object = base;
+        object["type"] = json!("eip1559");
+        object["gasLimitt"] = json!(1);
+        assert!(serde_json::from_value::<Transaction>(object).is_err());
+    }
+
+    #[test]
+    fn test_viem_transaction() {
+        // viem names the gas limit `gas`
+        let tx = transaction(json!({
+            "type": "eip1559",
+            "chainId": 1,
+            "nonce": 0,
+            "maxFeePerGas": 2,
+            "maxPriorityFeePerGas": 1,
+            "gas": 60000,
+            "to": "0x3535353535353535353535353535353535353535",
+            "data": "0x1234",
+        }));
+        assert_eq!(tx.kind, TransactionType::Eip1559);
+        assert_eq!(
+            signer().sign_tx(&tx).unwrap().raw,
+            "0x02f8640180010282ea6094353535353535353535353535353535353535353580821234c080a01e02a0497cfc0a7e578a052309ea8d37182a52a7bcb3a35868d83830c761df35a075dd2ea3226036208b07104c2d3e6f7a173466bae51984b659fc9762488947fd"
+        );
+        let authorization = serde_json::to_value(
+            signer()
+                .authorize(&AuthorizationRequest {
+                    chain_id: 1.into(),
+                    address: "0x3535353535353535353535353535353535353535"
+                        .parse()
+                        .unwrap(),
+                    nonce: 0.into(),
+                })
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(authorization["chainId"], "0x1");
+        assert_eq!(authorization["yParity"], 0);
+    }
+}
@@ -0,0 +1,634 @@
+//! Signed substrate extrinsics: a SCALE encoded call with the signed extensions of
+//! polkadot-sdk runtimes, as a version 4 signed transaction or a version 5 general
+//! transaction.
+//!
+//! The signed extensions default to those of the polkadot and kusama relay chains and most
+//! parachains, [`DEFAULT_EXTENSIONS`]. Other runtimes pass their own list, the
+//! `signedExtensions` of polkadot.js, and extensions this module can not encode are rejected.
+
+use serde::Deserialize;
+use sp_core::crypto::AccountId32;
+use sp_core::hashing::blake2_256;
+
+use crate::chains::errors::PolkadotError;
+use crate::chains::evm::{HexBytes, Quantity};
+use crate::Serialize;
+
+/// Version 4 signing payloads longer than this are signed as their blake2-256 hash
+pub const MAX_PAYLOAD_LEN: usize = 256;
+/// The version byte of a version 4 signed transaction
+const SIGNED_V4: u8 = 0x84;
+/// The version byte of a version 5 general transaction, extensions without an origin
+const GENERAL_V5: u8 = 0x45;
+/// The version of the extension pipeline of version 5 transactions
+pub const EXTENSION_VERSION: u8 = 0;
+/// The signed extensions of the polkadot and kusama relay chains, in order
+pub const DEFAULT_EXTENSIONS: &[&str] = &[
+    "CheckNonZeroSender",
+    "CheckSpecVersion",
+    "CheckTxVersion",
+    "CheckGenesis",
+    "CheckMortality",
+    "CheckNonce",
+    "CheckWeight",
+    "ChargeTransactionPayment",
+    "CheckMetadataHash",
+];
+/// Signed extensions that add nothing to the extrinsic or its payload
+const EMPTY_EXTENSIONS: &[&str] = &["CheckNonZeroSender", "CheckWeight", "PrevalidateAttests"];
+
+fn invalid(message: impl Into<String>) -> PolkadotError {
+    PolkadotError::InvalidExtrinsic(message.into())
+}
+
+/// Appends the SCALE compact encoding of an integer
+pub fn write_compact(out: &mut Vec<u8>, value: u128) {
+    match value {
+        0..=0x3f => out.push((value as u8) << 2),
+        0x40..=0x3fff => out.extend_from_slice(&(((value as u16) << 2) | 0b01).to_le_bytes()),
+        0x4000..=0x3fff_ffff => {
+            out.extend_from_slice(&(((value as u32) << 2) | 0b10).to_le_bytes())
+        }
+        _ => {
+            let len = 16 - value.leading_zeros() as usize / 8;
+            out.push((((len - 4) as u8) << 2) | 0b11);
+            out.extend_from_slice(&value.to_le_bytes()[..len]);
+        }
+    }
+}
+
+/// The validity period of a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Era {
+    Immortal,
+    /// Valid for `period` blocks from the block at `phase` modulo `period`
+    Mortal {
+        period: u64,
+        phase: u64,
+    },
+}
+
+impl Era {
+    /// The era starting at `block_number`, with the period rounded up to a power of two
+    /// between 4 and 65536 like `sp_runtime::generic::Era::mortal`
+    pub fn mortal(period: u64, block_number: u64) -> Self {
+        let period = period
+            .checked_next_power_of_two()
+            .unwrap_or(1 << 16)
+            .clamp(4, 1 << 16);
+        let quantize_factor = (period >> 12).max(1);
+        let phase = block_number % period / quantize_factor * quantize_factor;
+        Era::Mortal { period, phase }
+    }
+
+    /// Decodes the one byte immortal or two byte mortal encoding
+    pub fn decode(bytes: &[u8]) -> Result<Self, PolkadotError> {
+        match *bytes {
+            [0] => Ok(Era::Immortal),
+            [low, high] => {
+                let encoded = u64::from(u16::from_le_bytes([low, high]));
+                let period = 2 << (encoded % (1 << 4));
+                let quantize_factor = (period >> 12).max(1);
+                let phase = (encoded >> 4) * quantize_factor;
+                if period < 4 || phase >= period {
+                    return Err(invalid("The era is malformed"));
+                }
+                Ok(Era::Mortal { period, phase })
+            }
+            _ => Err(invalid("The era is neither one nor two bytes")),
+        }
+    }
+
+    /// Appends the one byte immortal or two byte mortal encoding
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match *self {
+            Era::Immortal => out.push(0),
+            Era::Mortal { period, phase } => {
+                let quantize_factor = (period >> 12).max(1);
+                let low = u64::from(period.trailing_zeros())
+                    .saturating_sub(1)
+                    .clamp(1, 15);
+                let encoded = (low | ((phase / quantize_factor) << 4)) as u16;
+                out.extend_from_slice(&encoded.to_le_bytes());
+            }
+        }
+    }
+}
+
+/// The mortality of a transaction: the number of blocks it stays valid for, from the block
+/// at `blockNumber` whose hash is the `blockHash` of the extrinsic
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct Mortality {
+    pub period: u64,
+    #[serde(alias = "block_number")]
+    pub block_number: u64,
+}
+
+fn default_version() -> u8 {
+    4
+}
+
+/// An extrinsic to sign, the `SignerPayloadJSON` that polkadot.js passes to the
+/// `signer.signPayload` of extensions. The snake_case field names are accepted too.
+///
+/// Hashes, the call and the era are `0x` hex. The nonce, tip, block number and versions may
+/// be numbers, decimal strings or `0x` hex strings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct Extrinsic {
+    /// The SCALE encoded call: pallet index, call index and arguments
+    #[serde(alias = "method")]
+    pub call: HexBytes,
+    pub nonce: Quantity,
+    #[serde(default)]
+    pub tip: Quantity,
+    /// Omitted for an immortal transaction
+    #[serde(default)]
+    pub mortality: Option<Mortality>,
+    /// The encoded era, as polkadot.js gives it, instead of `mortality`
+    #[serde(default)]
+    pub era: Option<HexBytes>,
+    /// The block the encoded era starts at, checked against its phase
+    #[serde(default, alias = "block_number")]
+    pub block_number: Option<Quantity>,
+    #[serde(alias = "spec_version")]
+    pub spec_version: Quantity,
+    #[serde(alias = "transaction_version")]
+    pub transaction_version: Quantity,
+    #[serde(alias = "genesis_hash")]
+    pub genesis_hash: HexBytes,
+    /// The hash of the block the mortality starts at. Immortal transactions are checked
+    /// against the genesis hash, which is the default.
+    #[serde(default, alias = "block_hash")]
+    pub block_hash: Option<HexBytes>,
+    /// The metadata hash that `CheckMetadataHash` compares, omitted to disable the check
+    #[serde(default, alias = "metadata_hash")]
+    pub metadata_hash: Option<HexBytes>,
+    /// The `CheckMetadataHash` mode of polkadot.js: 1 checks `metadataHash`, 0 ignores it
+    #[serde(default)]
+    pub mode: Option<u8>,
+    /// The SCALE encoded asset `ChargeAssetTxPayment` pays the fees in, omitted for the
+    /// native token
+    #[serde(default, alias = "asset_id")]
+    pub asset_id: Option<HexBytes>,
+    /// The signed extensions of the runtime in order, [`DEFAULT_EXTENSIONS`] when omitted
+    #[serde(default, alias = "signed_extensions")]
+    pub signed_extensions: Option<Vec<String>>,
+    /// The SS58 address of the signer, checked against the signing key
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Accepted for polkadot.js, the signed extrinsic is always returned
+    #[serde(default, alias = "with_signed_transaction")]
+    pub with_signed_transaction: bool,
+    /// 4 for a signed transaction, 5 for a general transaction of runtimes that verify
+    /// signatures with the `VerifySignature` extension
+    #[serde(default = "default_version")]
+    pub version: u8,
+}
+
+/// A signed extrinsic, ready for `author_submitExtrinsic`
+#[derive(Debug, Clone, Serialize)]
+pub struct SignedExtrinsic {
+    /// `0x` hex of the length prefixed extrinsic
+    pub extrinsic: String,
+    /// `0x` hex of its blake2-256 hash, the transaction hash
+    pub hash: String,
+}
+
+fn hash32(value: &HexBytes, name: &str) -> Result<[u8; 32], PolkadotError> {
+    let mut hash = [0u8; 32];
+    if value.0.len() != 32 {
+        return Err(invalid(format!("The {} is not 32 bytes", name)));
+    }
+    hash.copy_from_slice(&value.0);
+    Ok(hash)
+}
+
+fn uint(value: &Quantity, max_bytes: usize, name: &str) -> Result<u128, PolkadotError> {
+    let bytes = value.as_bytes();
+    if bytes.len() > max_bytes {
+        return Err(invalid(format!(
+            "The {} exceeds {} bits",
+            name,
+            max_bytes * 8
+        )));
+    }
+    let mut padded = [0u8; 16];
+    padded[16 - bytes.len()..].copy_from_slice(bytes);
+    Ok(u128::from_be_bytes(padded))
+}
+
+impl Extrinsic {
+    fn era(&self) -> Result<Era, PolkadotError> {
+        let era = match (&self.era, self.mortality) {
+            (Some(_), Some(_)) => {
+                return Err(invalid("Give either the encoded era or the mortality"));
+            }
+            (Some(era), None) => Era::decode(&era.0)?,
+            (None, Some(mortality)) => Era::mortal(mortality.period, mortality.block_number),
+            (None, None) => Era::Immortal,
+        };
+        if let (Era::Mortal { period, phase }, Some(block_number)) = (era, &self.block_number) {
+            let block_number = uint(block_number, 8, "block number")? as u64;
+            if Era::mortal(period, block_number) != (Era::Mortal { period, phase }) {
+                return Err(invalid("The era does not start at the block number"));
+            }
+        }
+        Ok(era)
+    }
+
+    /// The metadata hash `CheckMetadataHash` checks, if enabled
+    fn metadata_hash(&self) -> Result<Option<&HexBytes>, PolkadotError> {
+        match (self.mode, &self.metadata_hash) {
+            (None, hash) | (Some(1), hash @ Some(_)) => Ok(hash.as_ref()),
+            (Some(0), _) => Ok(None),
+            (Some(1), None) => Err(invalid("Metadata hash mode 1 needs the metadata hash")),
+            (Some(mode), _) => Err(invalid(format!("Unknown metadata hash mode {}", mode))),
+        }
+    }
+
+    fn validate(&self) -> Result<(), PolkadotError> {
+        if self.call.0.is_empty() {
+            return Err(invalid("The call is empty"));
+        }
+        if self.version != 4 && self.version != 5 {
+            return Err(invalid(format!(
+                "Unsupported extrinsic version {}",
+                self.version
+            )));
+        }
+        let genesis_hash = hash32(&self.genesis_hash, "genesis hash")?;
+        let immortal = self.era()? == Era::Immortal;
+        match &self.block_hash {
+            Some(block_hash) => {
+                let block_hash = hash32(block_hash, "block hash")?;
+                if immortal && block_hash != genesis_hash {
+                    return Err(invalid(
+                        "An immortal extrinsic is checked against the genesis hash",
+                    ));
+                }
+            }
+            None if !immortal => {
+                return Err(invalid("A mortal extrinsic needs the hash of its block"));
+            }
+            None => {}
+        }
+        if let Some(metadata_hash) = self.metadata_hash()? {
+            hash32(metadata_hash, "metadata hash")?;
+        }
+        Ok(())
+    }
+
+    /// The data the signed extensions carry in the extrinsic, like the era, nonce and tip,
+    /// and the data they add to the signed payload only, like the versions and hashes
+    fn extensions(&self) -> Result<(Vec<u8>, Vec<u8>), PolkadotError> {
+        let names: Vec<&str> = match &self.signed_extensions {
+            Some(names) => names.iter().map(String::as_str).collect(),
+            None => DEFAULT_EXTENSIONS.to_vec(),
+        };
+        let metadata_hash = self.metadata_hash()?;
+        if metadata_hash.is_some() && !names.contains(&"CheckMetadataHash") {
+            return Err(invalid("The runtime has no CheckMetadataHash extension"));
+        }
+        if self.asset_id.is_some() && !names.contains(&"ChargeAssetTxPayment") {
+            return Err(invalid("The runtime has no ChargeAssetTxPayment extension"));
+        }
+
+        let (mut extra, mut implicit) = (Vec::new(), Vec::new());
+        for name in names {
+            match name {
+                "CheckSpecVersion" => {
+                    let version = uint(&self.spec_version, 4, "spec version")? as u32;
+                    implicit.extend_from_slice(&version.to_le_bytes());
+                }
+                "CheckTxVersion" => {
+                    let version = uint(&self.transaction_version, 4, "transaction version")? as u32;
+                    implicit.extend_from_slice(&version.to_le_bytes());
+                }
+                "CheckGenesis" => implicit.extend_from_slice(&self.genesis_hash.0),
+                // `CheckEra` is the former name of `CheckMortality`
+                "CheckMortality" | "CheckEra" => {
+                    self.era()?.encode(&mut extra);
+                    let block_hash = self.block_hash.as_ref().unwrap_or(&self.genesis_hash);
+                    implicit.extend_from_slice(&block_hash.0);
+                }
+                "CheckNonce" => write_compact(&mut extra, uint(&self.nonce, 8, "nonce")?),
+                "ChargeTransactionPayment" => {
+                    write_compact(&mut extra, uint(&self.tip, 16, "tip")?)
+                }
+                "ChargeAssetTxPayment" => {
+                    write_compact(&mut extra, uint(&self.tip, 16, "tip")?);
+                    match &self.asset_id {
+                        Some(asset_id) => {
+                            extra.push(1);
+                            extra.extend_from_slice(&asset_id.0);
+                        }
+                        None => extra.push(0),
+                    }
+                }
+                "CheckMetadataHash" => {
+                    extra.push(u8::from(metadata_hash.is_some()));
+                    match metadata_hash {
+                        Some(hash) => {
+                            implicit.push(1);
+                            implicit.extend_from_slice(&hash.0);
+                        }
+                        None => implicit.push(0),
+                    }
+                }
+                name if EMPTY_EXTENSIONS.contains(&name) => {}
+                name => {
+                    return Err(invalid(format!("Unsupported signed extension '{}'", name)));
+                }
+            }
+        }
+        Ok((extra, implicit))
+    }
+
+    /// The bytes to sign. Version 4 signs the call, extra and implicit data, as their
+    /// blake2-256 hash when longer than 256 bytes. Version 5 always signs the hash, with the
+    /// extension version first.
+    pub fn signing_payload(&self) -> Result<Vec<u8>, PolkadotError> {
+        self.validate()?;
+        let mut payload = Vec::new();
+        if self.version == 5 {
+            payload.push(EXTENSION_VERSION);
+        }
+        let (extra, implicit) = self.extensions()?;
+        payload.extend_from_slice(&self.call.0);
+        payload.extend(extra);
+        payload.extend(implicit);
+        if self.version == 5 || payload.len() > MAX_PAYLOAD_LEN {
+            return Ok(blake2_256(&payload).to_vec());
+        }
+        Ok(payload)
+    }
+
+    /// Encodes the extrinsic with the `MultiSignature` of `signer` over the signing payload
+    pub fn encode(&self, signer: &AccountId32, signature: &[u8]) -> Result<Vec<u8>, PolkadotError> {
+        self.validate()?;
+        let account: &[u8] = signer.as_ref();
+        let mut body = Vec::new();
+        if self.version == 5 {
+            // `VerifySignature::Signed { signature, account }` leads the extensions
+            body.extend_from_slice(&[GENERAL_V5, EXTENSION_VERSION, 0]);
+            body.extend_from_slice(signature);
+            body.extend_from_slice(account);
+        } else {
+            // the `MultiAddress::Id` of the signer
+            body.extend_from_slice(&[SIGNED_V4, 0]);
+            body.extend_from_slice(account);
+            body.extend_from_slice(signature);
+        }
+        body.extend(self.extensions()?.0);
+        body.extend_from_slice(&self.call.0);
+
+        let mut extrinsic = Vec::with_capacity(body.len() + 5);
+        write_compact(&mut extrinsic, body.len() as u128);
+        extrinsic.extend(body);
+        Ok(extrinsic)
+    }
+
+    /// The encoded extrinsic and its hash
+    pub fn signed(
+        &self,
+        signer: &AccountId32,
+        signature: &[u8],
+    ) -> Result<SignedExtrinsic, PolkadotError> {
+        let extrinsic = self.encode(signer, signature)?;
+        Ok(SignedExtrinsic {
+            hash: format!("0x{}", hex::encode(blake2_256(&extrinsic))),
+            extrinsic: format!("0x{}", hex::encode(extrinsic)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn compact(value: u128) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_compact(&mut out, value);
+        out
+    }
+
+    fn era(era: Era) -> Vec<u8> {
+        let mut out = Vec::new();
+        era.encode(&mut out);
+        out
+    }
+
+    fn remark(version: u8, mortality: serde_json::Value) -> Extrinsic {
+        let mut extrinsic = json!({
+            // System.remark("era-js")
+            "call": "0x0000186572612d6a73",
+            "nonce": 3,
+            "tip": "1000",
+            "spec_version": 1_003_000,
+            "transaction_version": 26,
+            "genesis_hash": format!("0x{}", "91".repeat(32)),
+            "version": version,
+        });
+        if !mortality.is_null() {
+            extrinsic["mortality"] = mortality;
+            extrinsic["block_hash"] = json!(format!("0x{}", "ab".repeat(32)));
+        }
+        serde_json::from_value(extrinsic).unwrap()
+    }
+
+    #[test]
+    fn test_compact() {
+        assert_eq!(compact(0), [0x00]);
+        assert_eq!(compact(1), [0x04]);
+        assert_eq!(compact(63), [0xfc]);
+        assert_eq!(compact(64), [0x01, 0x01]);
+        assert_eq!(compact(16_383), [0xfd, 0xff]);
+        assert_eq!(compact(16_384), [0x02, 0x00, 0x01, 0x00]);
+        assert_eq!(compact(1_073_741_823), [0xfe, 0xff, 0xff, 0xff]);
+        assert_eq!(compact(1_073_741_824), [0x03, 0x00, 0x00, 0x00, 0x40]);
+        assert_eq!(
+            compact(u64::MAX.into()),
+            [0x13, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+        );
+        assert_eq!(compact(u128::MAX)[0], 0x33);
+    }
+
+    #[test]
+    fn test_eras() {
+        // the vectors of sp_runtime
+        assert_eq!(era(Era::Immortal), [0x00]);
+        assert_eq!(Era::decode(&[0x00]).unwrap(), Era::Immortal);
+        assert_eq!(Era::decode(&[0xa5, 0x02]).unwrap(), Era::mortal(64, 42));
+        assert!(Era::decode(&[0x00, 0x00]).is_err());
+        assert!(Era::decode(&[0x01]).is_err());
+        assert_eq!(
+            Era::mortal(64, 42),
+            Era::Mortal {
+                period: 64,
+                phase: 42
+            }
+        );
+        assert_eq!(era(Era::mortal(64, 42)), [5 + 42 % 16 * 16, 42 / 16]);
+        assert_eq!(
+            era(Era::mortal(32_768, 20_000)),
+            [(14 + 2500 % 16 * 16) as u8, (2500 / 16) as u8]
+        );
+        assert_eq!(
+            Era::mortal(100, 1_000),
+            Era::Mortal {
+                period: 128,
+                phase: 1_000 % 128
+            }
+        );
+        assert_eq!(
+            Era::mortal(1, 7),
+            Era::Mortal {
+                period: 4,
+                phase: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_payloads() {
+        let extrinsic = remark(4, json!({ "period": 64, "block_number": 42 }));
+        let payload = extrinsic.signing_payload().unwrap();
+        let mut expected = hex::decode("0000186572612d6a73").unwrap();
+        expected.extend_from_slice(&[0xa5, 0x02, 0x0c, 0xa1, 0x0f, 0x00]);
+        expected.extend_from_slice(&1_003_000u32.to_le_bytes());
+        expected.extend_from_slice(&26u32.to_le_bytes());
+        expected.extend_from_slice(&[0x91; 32]);
+        expected.extend_from_slice(&[0xab; 32]);
+        expected.push(0);
+        assert_eq!(payload, expected);
+
+        // immortal transactions are checked against the genesis hash
+        let immortal = remark(4, json!(null));
+        let (extra, implicit) = immortal.extensions().unwrap();
+        assert_eq!(extra[0], 0);
+        assert_eq!(implicit[8..40], implicit[40..72]);
+
+        let mut long = remark(4, json!(null));
+        long.call.0.extend_from_slice(&[0u8; 300]);
+        let (extra, implicit) = long.extensions().unwrap();
+        assert_eq!(
+            long.signing_payload().unwrap(),
+            blake2_256(&[&long.call.0[..], &extra, &implicit].concat())
+        );
+
+        let general = remark(5, json!(null));
+        let (extra, implicit) = general.extensions().unwrap();
+        let mut hashed = vec![EXTENSION_VERSION];
+        hashed.extend(&general.call.0);
+        hashed.extend(extra);
+        hashed.extend(implicit);
+        assert_eq!(general.signing_payload().unwrap(), blake2_256(&hashed));
+    }
+
+    #[test]
+    fn test_invalid_extrinsics() {
+        let mut extrinsic = remark(4, json!({ "period": 64, "block_number": 42 }));
+        extrinsic.block_hash = None;
+        assert!(extrinsic.signing_payload().is_err());
+
+        let mut extrinsic = remark(4, json!(null));
+        extrinsic.block_hash = Some(HexBytes(vec![0xab; 32]));
+        assert!(extrinsic.signing_payload().is_err());
+        extrinsic.block_hash = Some(extrinsic.genesis_hash.clone());
+        assert!(extrinsic.signing_payload().is_ok());
+
+        assert!(remark(6, json!(null)).signing_payload().is_err());
+        let mut extrinsic = remark(4, json!(null));
+        extrinsic.metadata_hash = Some(HexBytes(vec![1; 31]));
+        assert!(extrinsic.signing_payload().is_err());
+        extrinsic.call.0.clear();
+        assert!(extrinsic.signing_payload().is_err());
+    }
+
+    #[test]
+    fn test_signer_payload() {
+        // the SignerPayloadJSON of polkadot.js for the mortal remark above
+        let payload = json!({
+            "address": "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5",
+            "blockHash": format!("0x{}", "ab".repeat(32)),
+            "blockNumber": "0x0000002a",
+            "era": "0xa502",
+            "genesisHash": format!("0x{}", "91".repeat(32)),
+            "method": "0x0000186572612d6a73",
+            "mode": 0,
+            "nonce": "0x00000003",
+            "signedExtensions": DEFAULT_EXTENSIONS,
+            "specVersion": "0x000f4df8",
+            "tip": "0x000000000000000000000000000003e8",
+            "transactionVersion": "0x0000001a",
+            "version": 4,
+            "withSignedTransaction": true,
+        });
+        let extrinsic: Extrinsic = serde_json::from_value(payload.clone()).unwrap();
+        let expected = remark(4, json!({ "period": 64, "block_number": 42 }));
+        assert_eq!(
+            extrinsic.signing_payload().unwrap(),
+            expected.signing_payload().unwrap()
+        );
+
+        let mut moved = payload.clone();
+        moved["blockNumber"] = json!(43);
+        let extrinsic: Extrinsic = serde_json::from_value(moved).unwrap();
+        assert!(extrinsic.signing_payload().is_err());
+        let mut unchecked = payload.clone();
+        unchecked["mode"] = json!(1);
+        let extrinsic: Extrinsic = serde_json::from_value(unchecked).unwrap();
+        assert!(extrinsic.signing_payload().is_err());
+    }
+
+    #[test]
+    fn test_signed_extensions() {
+        let mut extrinsic = remark(4, json!(null));
+        let (default_extra, default_implicit) = extrinsic.extensions().unwrap();
+
+        // a runtime without CheckMetadataHash drops its mode byte and hash option
+        let mut names: Vec<String> = DEFAULT_EXTENSIONS
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        names.pop();
+        extrinsic.signed_extensions = Some(names.clone());
+        let (extra, implicit) = extrinsic.extensions().unwrap();
+        assert_eq!(extra, default_extra[..default_extra.len() - 1]);
+        assert_eq!(implicit, default_implicit[..default_implicit.len() - 1]);
+        extrinsic.metadata_hash = Some(HexBytes(vec![7; 32]));
+        assert!(extrinsic.signing_payload().is_err());
+        extrinsic.metadata_hash = None;
+
+        // ChargeAssetTxPayment follows the tip with the optional asset id
+        let charge = names
+            .iter()
+            .position(|name| name == "ChargeTransactionPayment")
+            .unwrap();
+        names[charge] = "ChargeAssetTxPayment".into();
+        extrinsic.signed_extensions = Some(names.clone());
+        assert_eq!(
+            extrinsic.extensions().unwrap().0,
+            [&extra[..], &[0]].concat()
+        );
+        extrinsic.asset_id = Some(HexBytes(vec![0x01, 0x00, 0x00, 0x00]));
+        assert_eq!(
+            extrinsic.extensions().unwrap().0,
+            [&extra[..], &[1, 1, 0, 0, 0]].concat()
+        );
+        extrinsic.signed_extensions = None;
+        assert!(extrinsic.signing_payload().is_err());
+
+        extrinsic.asset_id = None;
+        names.push("CheckAppId".into());
+        extrinsic.signed_extensions = Some(names);
+        let error = extrinsic.signing_payload().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid extrinsic: Unsupported signed extension 'CheckAppId'"
+        );
+    }
+}
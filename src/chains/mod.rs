@@ -1,2 +1,13 @@
+pub mod bech32;
+pub mod bitcoin;
+pub mod cosmos;
+pub mod eip712;
+pub mod errors;
+pub mod ethereum;
+pub mod evm;
+pub mod extrinsic;
 pub mod polkadot;
-pub mod errors;
\ No newline at end of file
+pub mod psbt;
+pub mod rlp;
+pub mod solana;
+pub mod ss58;
@@ -1,69 +1,483 @@
+use crate::chains::errors::PolkadotError;
+use crate::chains::extrinsic::{Extrinsic, SignedExtrinsic};
+use crate::chains::ss58;
+use crate::errors::EraError;
+#[cfg(feature = "wasm")]
 use crate::wasm_bindgen;
-use crate::{Serialize,Deserialize};
-use sp_core::sr25519::Signature;
-use sp_core::{sr25519,crypto::Ss58Codec, Pair};
-use crate::to_value;
-use crate::JsValue;
+#[cfg(feature = "wasm")]
+use crate::{errors::from_js, to_value, JsValue};
+use crate::{Deserialize, Serialize};
+use sp_core::crypto::AccountId32;
+use sp_core::{ecdsa, ed25519, sr25519, Pair};
+use std::str::FromStr;
+use zeroize::{Zeroize, Zeroizing};
 
-#[derive(Serialize,Deserialize)]
+/// The signature scheme of a substrate account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheme {
+    Ed25519,
+    /// Schnorrkel, the default of polkadot wallets
+    #[default]
+    Sr25519,
+    /// secp256k1, used by Ledger apps and bridged or EVM-compatible parachains
+    Ecdsa,
+}
+
+impl FromStr for Scheme {
+    type Err = PolkadotError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "ed25519" => Ok(Scheme::Ed25519),
+            "sr25519" => Ok(Scheme::Sr25519),
+            "ecdsa" => Ok(Scheme::Ecdsa),
+            _ => Err(PolkadotError::UnknownScheme(value.into())),
+        }
+    }
+}
+
+impl Scheme {
+    /// The variant index of the scheme in `MultiSignature` and `MultiSigner`
+    pub fn multi_signature_tag(self) -> u8 {
+        match self {
+            Scheme::Ed25519 => 0,
+            Scheme::Sr25519 => 1,
+            Scheme::Ecdsa => 2,
+        }
+    }
+
+    /// Derives the secret of a secret URI, the bytes `PolkadotSigner` holds
+    pub fn secret_from_uri(self, uri: &str) -> Result<Zeroizing<Vec<u8>>, PolkadotError> {
+        let secret = match self {
+            Scheme::Ed25519 => ed25519::Pair::from_string(uri, None).map(|pair| pair.to_raw_vec()),
+            Scheme::Sr25519 => sr25519::Pair::from_string(uri, None).map(|pair| pair.to_raw_vec()),
+            Scheme::Ecdsa => ecdsa::Pair::from_string(uri, None).map(|pair| pair.to_raw_vec()),
+        };
+        secret
+            .map(Zeroizing::new)
+            .map_err(|_| PolkadotError::InvalidSecretUri)
+    }
+}
+
+/// The derivation of the first account of a wallet in earlier versions, `//polkadot//0`
+pub const DEFAULT_DERIVATION: &str = "//polkadot//0";
+
+/// The derivation part of a secret URI: its junctions and optional password
+pub struct Derivation {
+    /// The junctions, with numeric junctions in their canonical form
+    pub path: String,
+    pub password: Option<Zeroizing<String>>,
+}
+
+impl Derivation {
+    /// Parses `//hard/soft///password` junctions. Junctions are numbers or non-empty
+    /// strings, the password follows the first `///` and may be empty. An empty URI is
+    /// rejected rather than read as the root account.
+    pub fn parse(uri: &str) -> Result<Self, PolkadotError> {
+        if uri.is_empty() {
+            return Err(PolkadotError::InvalidSecretUri);
+        }
+        let (junctions, password) = match uri.find("///") {
+            Some(start) => (
+                &uri[..start],
+                Some(Zeroizing::new(uri[start + 3..].to_string())),
+            ),
+            None => (uri, None),
+        };
+        if !junctions.is_empty() && !junctions.starts_with('/') {
+            return Err(PolkadotError::InvalidSecretUri);
+        }
+
+        let mut path = String::new();
+        let mut rest = junctions;
+        while !rest.is_empty() {
+            let hard = rest.starts_with("//");
+            rest = rest.trim_start_matches('/');
+            let end = rest.find('/').unwrap_or(rest.len());
+            let junction = &rest[..end];
+            if junction.is_empty() {
+                return Err(PolkadotError::InvalidSecretUri);
+            }
+            path.push_str(if hard { "//" } else { "/" });
+            // substrate reads numeric junctions as u64, `//007` and `//7` are one account
+            match junction.parse::<u64>() {
+                Ok(index) => path.push_str(&index.to_string()),
+                Err(_) => path.push_str(junction),
+            }
+            rest = &rest[end..];
+        }
+        Ok(Self { path, password })
+    }
+
+    /// The secret URI of the derivation from a mnemonic
+    pub fn uri(&self, phrase: &str) -> Zeroizing<String> {
+        let mut uri = Zeroizing::new(format!("{}{}", phrase, self.path));
+        if let Some(password) = &self.password {
+            uri.push_str("///");
+            uri.push_str(password);
+        }
+        uri
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct KeyObject {
     pub private_key: String,
+    /// 32 bytes, or the 33 byte compressed key for ecdsa
     pub public_key: String,
     pub address: String,
     pub path: String,
+    #[serde(default)]
+    pub scheme: Scheme,
+    /// The SS58 prefix of the address
+    #[serde(default)]
+    pub ss58_prefix: u16,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct PolkadotSigner {
-    signer: Vec<u8>,
-    path: String
+    signer: Zeroizing<Vec<u8>>,
+    path: String,
+    #[serde(default)]
+    scheme: Scheme,
+    /// The SS58 prefix of the addresses, 0 for the polkadot relay chain
+    #[serde(default)]
+    ss58_prefix: u16,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl PolkadotSigner {
-    
+    #[cfg(feature = "wasm")]
     #[wasm_bindgen]
     pub fn fetch_key(&self) -> Result<JsValue, JsValue> {
-        Ok(to_value(&self.get_key()).unwrap())
+        Ok(to_value(&self.get_key()?)?)
     }
 
+    /// Signs a message with the scheme of the signer: 64 bytes, or 65 for ecdsa. Extrinsic
+    /// payloads longer than 256 bytes must be hashed first, `sign_extrinsic` does it.
+    #[cfg(feature = "wasm")]
     #[wasm_bindgen]
     pub fn sign_transaction(&self, message: &[u8]) -> Result<Vec<u8>, JsValue> {
-        let sig = self.sign(message);
-        Ok(sig.to_vec())
+        Ok(self.sign(message)?)
     }
-}
 
+    /// Signs a message and prefixes the signature with its `MultiSignature` variant, the
+    /// encoding extrinsics carry
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn multi_signature(&self, message: &[u8]) -> Result<Vec<u8>, EraError> {
+        Ok(self.multi_sign(message)?)
+    }
+
+    /// Builds and signs an extrinsic from the `SignerPayloadJSON` of polkadot.js, `{ address,
+    /// method, nonce, tip, era, blockNumber, blockHash, specVersion, transactionVersion,
+    /// genesisHash, signedExtensions, mode, metadataHash, version }`. Returns `{ extrinsic, hash }`.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn sign_extrinsic(&self, extrinsic: JsValue) -> Result<JsValue, JsValue> {
+        let extrinsic: Extrinsic = from_js(extrinsic, "extrinsic")?;
+        Ok(to_value(&self.build_extrinsic(&extrinsic)?)?)
+    }
+
+    /// Selects the network of the addresses, by SS58 registry name like `kusama` or
+    /// `substrate`, or by prefix number
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_network(&mut self, network: &str) -> Result<(), EraError> {
+        self.ss58_prefix = ss58::parse_format(network)?.prefix();
+        Ok(())
+    }
+
+    /// Zeroes the key of the signer. The signer is also wiped when it is freed.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn wipe(&mut self) {
+        self.signer.zeroize();
+    }
+}
 
 impl PolkadotSigner {
+    /// An sr25519 signer
     pub fn new(signer: Vec<u8>, path: String) -> Self {
-        Self { signer, path }
+        Self::with_scheme(Scheme::Sr25519, signer, path)
     }
 
-    fn get_key(&self) -> KeyObject {
-        let pair = sr25519::Pair::from_seed_slice(&self.signer).unwrap();
-        // Extract public and private keys
-        let public = pair.public();
-        let private = pair.to_raw_vec();
-        // default to polkadot prefix
-        let address = public.to_ss58check_with_version(sp_core::crypto::Ss58AddressFormat::custom(0));
-        //let address = public.to_ss58check();
+    pub fn with_scheme(scheme: Scheme, signer: Vec<u8>, path: String) -> Self {
+        Self {
+            signer: Zeroizing::new(signer),
+            path,
+            scheme,
+            ss58_prefix: 0,
+        }
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    pub fn ss58_prefix(&self) -> u16 {
+        self.ss58_prefix
+    }
+
+    /// The raw public key
+    pub fn public(&self) -> Result<Vec<u8>, PolkadotError> {
+        let invalid = |_| PolkadotError::InvalidKey;
+        Ok(match self.scheme {
+            Scheme::Ed25519 => ed25519::Pair::from_seed_slice(&self.signer)
+                .map_err(invalid)?
+                .public()
+                .to_vec(),
+            Scheme::Sr25519 => sr25519::Pair::from_seed_slice(&self.signer)
+                .map_err(invalid)?
+                .public()
+                .to_vec(),
+            Scheme::Ecdsa => ecdsa::Pair::from_seed_slice(&self.signer)
+                .map_err(invalid)?
+                .public()
+                .to_vec(),
+        })
+    }
+
+    /// The account id: the public key, or its blake2-256 hash for ecdsa
+    pub fn account_id(&self) -> Result<AccountId32, PolkadotError> {
+        let public = self.public()?;
+        let id = match self.scheme {
+            Scheme::Ecdsa => sp_core::hashing::blake2_256(&public),
+            Scheme::Ed25519 | Scheme::Sr25519 => {
+                let mut id = [0u8; 32];
+                id.copy_from_slice(&public);
+                id
+            }
+        };
+        Ok(AccountId32::new(id))
+    }
 
-        KeyObject {
-            private_key: format!("0x{}",hex::encode(private)),
-            public_key: format!("0x{}",hex::encode(public)),
+    /// The keys and address of the signer on its network
+    pub fn get_key(&self) -> Result<KeyObject, PolkadotError> {
+        let public = self.public()?;
+        let address = ss58::encode(&self.account_id()?, self.ss58_prefix.into());
+
+        Ok(KeyObject {
+            private_key: format!("0x{}", hex::encode(&*self.signer)),
+            public_key: format!("0x{}", hex::encode(public)),
             address,
-            path: self.path.clone(),   
+            path: self.path.clone(),
+            scheme: self.scheme,
+            ss58_prefix: self.ss58_prefix,
+        })
+    }
+
+    fn multi_sign(&self, message: &[u8]) -> Result<Vec<u8>, PolkadotError> {
+        let mut signature = vec![self.scheme.multi_signature_tag()];
+        signature.extend(self.sign(message)?);
+        Ok(signature)
+    }
+
+    /// Signs the payload of an extrinsic and encodes it with the signer's account
+    pub fn build_extrinsic(&self, extrinsic: &Extrinsic) -> Result<SignedExtrinsic, PolkadotError> {
+        let account = self.account_id()?;
+        if let Some(address) = &extrinsic.address {
+            if ss58::decode(address)?.0 != account {
+                return Err(PolkadotError::InvalidExtrinsic(format!(
+                    "The address {} is not the signer's",
+                    address
+                )));
+            }
         }
+        let signature = self.multi_sign(&extrinsic.signing_payload()?)?;
+        extrinsic.signed(&account, &signature)
     }
 
-    
+    /// Signs a message, ecdsa signs its blake2-256 hash like substrate
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, PolkadotError> {
+        let invalid = |_| PolkadotError::InvalidKey;
+        Ok(match self.scheme {
+            Scheme::Ed25519 => ed25519::Pair::from_seed_slice(&self.signer)
+                .map_err(invalid)?
+                .sign(message)
+                .0
+                .to_vec(),
+            Scheme::Sr25519 => sr25519::Pair::from_seed_slice(&self.signer)
+                .map_err(invalid)?
+                .sign(message)
+                .0
+                .to_vec(),
+            Scheme::Ecdsa => ecdsa::Pair::from_seed_slice(&self.signer)
+                .map_err(invalid)?
+                .sign(message)
+                .0
+                .to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    fn alice(scheme: Scheme) -> PolkadotSigner {
+        let secret = scheme.secret_from_uri("//Alice").unwrap();
+        PolkadotSigner::with_scheme(scheme, secret.to_vec(), "//Alice".into())
+    }
+
+    #[test]
+    fn test_schemes() {
+        let sr25519 = alice(Scheme::Sr25519).get_key().unwrap();
+        assert_eq!(
+            sr25519.address,
+            "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"
+        );
+        assert_eq!(
+            sr25519.public_key,
+            "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d"
+        );
+        let ed25519 = alice(Scheme::Ed25519).get_key().unwrap();
+        assert_eq!(
+            ed25519.public_key,
+            "0x88dc3417d5058ec4b4503e0c12ea1a0a89be200fe98922423d4334014fa6b0ee"
+        );
+        let ecdsa = alice(Scheme::Ecdsa);
+        let key = ecdsa.get_key().unwrap();
+        assert_eq!(key.public_key.len(), 2 + 66);
+        let id: [u8; 32] = ecdsa.account_id().unwrap().into();
+        assert_eq!(id, sp_core::hashing::blake2_256(&ecdsa.public().unwrap()));
+        assert!("bls".parse::<Scheme>().is_err());
+    }
+
+    #[test]
+    fn test_derivations() {
+        let derivation = Derivation::parse("//polkadot/007//1///secret/pass").unwrap();
+        assert_eq!(derivation.path, "//polkadot/7//1");
+        assert_eq!(
+            derivation.password.as_deref().map(String::as_str),
+            Some("secret/pass")
+        );
+        assert_eq!(
+            derivation.uri("bottom drive").as_str(),
+            "bottom drive//polkadot/7//1///secret/pass"
+        );
+        assert!(Derivation::parse("///only").unwrap().path.is_empty());
+
+        for invalid in ["", "polkadot", "//a//", "/", "//a/"].iter() {
+            assert!(Derivation::parse(invalid).is_err(), "{}", invalid);
+        }
+        // three slashes start the password, four leave a slash in it
+        assert_eq!(
+            Derivation::parse("//a////b")
+                .unwrap()
+                .password
+                .as_deref()
+                .map(String::as_str),
+            Some("/b")
+        );
+    }
+
+    #[test]
+    fn test_networks() {
+        let mut signer = alice(Scheme::Sr25519);
+        signer.set_network("kusama").unwrap();
+        let key = signer.get_key().unwrap();
+        assert_eq!(
+            key.address,
+            "HNZata7iMYWmk5RvZRTiAsSDhV8366zq2YGb3tLH5Upf74F"
+        );
+        assert_eq!(key.ss58_prefix, 2);
+        signer.set_network("42").unwrap();
+        assert_eq!(
+            signer.get_key().unwrap().address,
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+        );
+        assert!(signer.set_network("atlantis").is_err());
+        assert_eq!(signer.ss58_prefix(), 42);
+    }
+
+    #[test]
+    fn test_extrinsics() {
+        let signer = alice(Scheme::Sr25519);
+        let public = sr25519::Public::from_raw(signer.public().unwrap().try_into().unwrap());
+        let extrinsic = |version: u8| -> Extrinsic {
+            serde_json::from_value(serde_json::json!({
+                "call": "0x0000186572612d6a73",
+                "nonce": 1,
+                "mortality": { "period": 64, "block_number": 42 },
+                "spec_version": 1_003_000,
+                "transaction_version": 26,
+                "genesis_hash": format!("0x{}", "91".repeat(32)),
+                "block_hash": format!("0x{}", "ab".repeat(32)),
+                "version": version,
+            }))
+            .unwrap()
+        };
+
+        let v4 = extrinsic(4);
+        let signed = signer.build_extrinsic(&v4).unwrap();
+        let encoded = hex::decode(&signed.extrinsic[2..]).unwrap();
+        assert_eq!(
+            signed.hash,
+            format!("0x{}", hex::encode(sp_core::hashing::blake2_256(&encoded)))
+        );
+        // a two byte compact length, the version, MultiAddress::Id and MultiSignature::Sr25519
+        let length = u16::from_le_bytes([encoded[0], encoded[1]]) >> 2;
+        let body = &encoded[2..];
+        assert_eq!(usize::from(length), body.len());
+        assert_eq!(body[..2], [0x84, 0x00]);
+        assert_eq!(body[2..34], public.0);
+        assert_eq!(body[34], 1);
+        let raw = sr25519::Signature::from_raw(body[35..99].try_into().unwrap());
+        let payload = v4.signing_payload().unwrap();
+        assert!(sr25519::Pair::verify(&raw, &payload, &public));
+        // era, nonce, tip, metadata hash mode and the call
+        assert_eq!(body[99..104], [0xa5, 0x02, 0x04, 0x00, 0x00]);
+        assert_eq!(body[104..], hex::decode("0000186572612d6a73").unwrap()[..]);
+
+        let v5 = extrinsic(5);
+        let encoded = hex::decode(&signer.build_extrinsic(&v5).unwrap().extrinsic[2..]).unwrap();
+        // the extension version and VerifySignature::Signed lead the extensions
+        let body = &encoded[2..];
+        assert_eq!(body[..3], [0x45, 0x00, 0x00]);
+        assert_eq!(body[3], 1);
+        let raw = sr25519::Signature::from_raw(body[4..68].try_into().unwrap());
+        let payload = v5.signing_payload().unwrap();
+        assert!(sr25519::Pair::verify(&raw, &payload, &public));
+        assert_eq!(body[68..100], public.0);
+
+        let ecdsa = alice(Scheme::Ecdsa);
+        let encoded = hex::decode(&ecdsa.build_extrinsic(&v4).unwrap().extrinsic[2..]).unwrap();
+        let id: [u8; 32] = ecdsa.account_id().unwrap().into();
+        assert_eq!(encoded[4..36], id);
+        assert_eq!((encoded[36], encoded.len()), (2, 2 + 2 + 32 + 66 + 5 + 9));
+
+        let mut other = v4;
+        other.address = Some(ecdsa.get_key().unwrap().address);
+        assert!(signer.build_extrinsic(&other).is_err());
+        other.address = Some(signer.get_key().unwrap().address);
+        assert!(signer.build_extrinsic(&other).is_ok());
+    }
+
+    #[test]
+    fn test_multi_signatures() {
+        let message = b"era-js";
+
+        let signer = alice(Scheme::Ed25519);
+        let signature = signer.multi_signature(message).unwrap();
+        assert_eq!((signature[0], signature.len()), (0, 65));
+        let public = ed25519::Public::from_raw(signer.public().unwrap().try_into().unwrap());
+        let raw = ed25519::Signature::from_raw(signature[1..].try_into().unwrap());
+        assert!(ed25519::Pair::verify(&raw, message, &public));
+
+        let signer = alice(Scheme::Sr25519);
+        let signature = signer.multi_signature(message).unwrap();
+        assert_eq!((signature[0], signature.len()), (1, 65));
+        let public = sr25519::Public::from_raw(signer.public().unwrap().try_into().unwrap());
+        let raw = sr25519::Signature::from_raw(signature[1..].try_into().unwrap());
+        assert!(sr25519::Pair::verify(&raw, message, &public));
 
-    pub fn sign(&self, message: &[u8]) -> Signature {
-        let pair = sr25519::Pair::from_seed_slice(&self.signer).unwrap();
-        pair.sign(message)
+        let signer = alice(Scheme::Ecdsa);
+        let signature = signer.multi_signature(message).unwrap();
+        assert_eq!((signature[0], signature.len()), (2, 66));
+        let public = ecdsa::Public::from_raw(signer.public().unwrap().try_into().unwrap());
+        let raw = ecdsa::Signature::from_raw(signature[1..].try_into().unwrap());
+        assert!(ecdsa::Pair::verify(&raw, message, &public));
     }
-        
 }
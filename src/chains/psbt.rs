@@ -0,0 +1,731 @@
+//! Bitcoin transactions and BIP-174 partially signed transactions, with the signature hashes
+//! of legacy, segwit v0 (BIP-143) and taproot (BIP-341) inputs.
+
+use sha2::{Digest, Sha256};
+
+use crate::chains::errors::BitcoinError;
+
+const MAGIC: &[u8; 5] = b"psbt\xff";
+
+pub const SIGHASH_DEFAULT: u32 = 0x00;
+pub const SIGHASH_ALL: u32 = 0x01;
+pub const SIGHASH_NONE: u32 = 0x02;
+pub const SIGHASH_SINGLE: u32 = 0x03;
+pub const SIGHASH_ANYONECANPAY: u32 = 0x80;
+
+/// Key types of the global map
+pub const GLOBAL_UNSIGNED_TX: u8 = 0x00;
+pub const GLOBAL_VERSION: u8 = 0xfb;
+
+/// Key types of an input map
+pub const IN_NON_WITNESS_UTXO: u8 = 0x00;
+pub const IN_WITNESS_UTXO: u8 = 0x01;
+pub const IN_PARTIAL_SIG: u8 = 0x02;
+pub const IN_SIGHASH_TYPE: u8 = 0x03;
+pub const IN_REDEEM_SCRIPT: u8 = 0x04;
+pub const IN_BIP32_DERIVATION: u8 = 0x06;
+pub const IN_FINAL_SCRIPTSIG: u8 = 0x07;
+pub const IN_FINAL_SCRIPTWITNESS: u8 = 0x08;
+pub const IN_TAP_KEY_SIG: u8 = 0x13;
+pub const IN_TAP_BIP32_DERIVATION: u8 = 0x16;
+pub const IN_TAP_INTERNAL_KEY: u8 = 0x17;
+pub const IN_TAP_MERKLE_ROOT: u8 = 0x18;
+
+fn invalid(message: impl Into<String>) -> BitcoinError {
+    BitcoinError::InvalidPsbt(message.into())
+}
+
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+pub fn sha256d(data: &[u8]) -> [u8; 32] {
+    sha256(&sha256(data))
+}
+
+/// `sha256(sha256(tag) || sha256(tag) || data)` of BIP-340
+pub fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
+    let tag = sha256(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag);
+    hasher.update(tag);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+pub fn write_compact_size(out: &mut Vec<u8>, value: usize) {
+    match value {
+        0..=0xfc => out.push(value as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(value as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+    }
+}
+
+fn write_var_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_compact_size(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position == self.bytes.len()
+    }
+
+    fn read(&mut self, len: usize) -> Result<&'a [u8], BitcoinError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("Unexpected end of data"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, BitcoinError> {
+        Ok(self.read(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, BitcoinError> {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(self.read(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64, BitcoinError> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.read(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn compact_size(&mut self) -> Result<usize, BitcoinError> {
+        let value = match self.u8()? {
+            0xfd => u64::from(u16::from_le_bytes([self.u8()?, self.u8()?])),
+            0xfe => u64::from(self.u32()?),
+            0xff => self.u64()?,
+            value => u64::from(value),
+        };
+        // longer than any input, the read of the bytes would fail anyway
+        if value > self.bytes.len() as u64 {
+            return Err(invalid("Length exceeds the data"));
+        }
+        Ok(value as usize)
+    }
+
+    fn var_bytes(&mut self) -> Result<&'a [u8], BitcoinError> {
+        let len = self.compact_size()?;
+        self.read(len)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxIn {
+    /// The txid of the spent output in its internal byte order
+    pub txid: [u8; 32],
+    pub vout: u32,
+    pub script_sig: Vec<u8>,
+    pub sequence: u32,
+}
+
+impl TxIn {
+    fn write_outpoint(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.txid);
+        out.extend_from_slice(&self.vout.to_le_bytes());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxOut {
+    pub value: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+impl TxOut {
+    fn parse(reader: &mut Reader) -> Result<Self, BitcoinError> {
+        Ok(Self {
+            value: reader.u64()?,
+            script_pubkey: reader.var_bytes()?.to_vec(),
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = self.value.to_le_bytes().to_vec();
+        write_var_bytes(&mut out, &self.script_pubkey);
+        out
+    }
+}
+
+/// A transaction without its witnesses, which PSBTs and signature hashes leave out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub version: u32,
+    pub inputs: Vec<TxIn>,
+    pub outputs: Vec<TxOut>,
+    pub lock_time: u32,
+}
+
+impl Transaction {
+    /// Parses a transaction, dropping its witnesses
+    pub fn parse(bytes: &[u8]) -> Result<Self, BitcoinError> {
+        let mut reader = Reader::new(bytes);
+        let version = reader.u32()?;
+        let mut count = reader.compact_size()?;
+        let segwit = count == 0;
+        if segwit {
+            if reader.u8()? != 1 {
+                return Err(invalid("Unknown segwit flag"));
+            }
+            count = reader.compact_size()?;
+        }
+        let mut inputs = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut txid = [0u8; 32];
+            txid.copy_from_slice(reader.read(32)?);
+            inputs.push(TxIn {
+                txid,
+                vout: reader.u32()?,
+                script_sig: reader.var_bytes()?.to_vec(),
+                sequence: reader.u32()?,
+            });
+        }
+        let count = reader.compact_size()?;
+        let mut outputs = Vec::with_capacity(count);
+        for _ in 0..count {
+            outputs.push(TxOut::parse(&mut reader)?);
+        }
+        if segwit {
+            for _ in 0..inputs.len() {
+                for _ in 0..reader.compact_size()? {
+                    reader.var_bytes()?;
+                }
+            }
+        }
+        let lock_time = reader.u32()?;
+        if !reader.is_empty() {
+            return Err(invalid("Trailing bytes after the transaction"));
+        }
+        Ok(Self {
+            version,
+            inputs,
+            outputs,
+            lock_time,
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = self.version.to_le_bytes().to_vec();
+        write_compact_size(&mut out, self.inputs.len());
+        for input in &self.inputs {
+            input.write_outpoint(&mut out);
+            write_var_bytes(&mut out, &input.script_sig);
+            out.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        write_compact_size(&mut out, self.outputs.len());
+        for output in &self.outputs {
+            out.extend(output.serialize());
+        }
+        out.extend_from_slice(&self.lock_time.to_le_bytes());
+        out
+    }
+
+    /// The txid in its internal byte order
+    pub fn txid(&self) -> [u8; 32] {
+        sha256d(&self.serialize())
+    }
+
+    fn check_input(&self, index: usize, hash_type: u32) -> Result<(), BitcoinError> {
+        if index >= self.inputs.len() {
+            return Err(invalid(format!("No input {}", index)));
+        }
+        if hash_type & 0x1f == SIGHASH_SINGLE && index >= self.outputs.len() {
+            return Err(invalid("SIGHASH_SINGLE without a matching output"));
+        }
+        Ok(())
+    }
+
+    /// The signature hash of a legacy input, `script_code` is the script of the spent output
+    pub fn legacy_sighash(
+        &self,
+        index: usize,
+        script_code: &[u8],
+        hash_type: u32,
+    ) -> Result<[u8; 32], BitcoinError> {
+        self.check_input(index, hash_type)?;
+        let base = hash_type & 0x1f;
+        let mut tx = self.clone();
+        for (i, input) in tx.inputs.iter_mut().enumerate() {
+            input.script_sig = if i == index {
+                script_code.to_vec()
+            } else {
+                Vec::new()
+            };
+            if i != index && (base == SIGHASH_NONE || base == SIGHASH_SINGLE) {
+                input.sequence = 0;
+            }
+        }
+        if base == SIGHASH_NONE {
+            tx.outputs.clear();
+        } else if base == SIGHASH_SINGLE {
+            tx.outputs.truncate(index + 1);
+            for output in tx.outputs.iter_mut().take(index) {
+                output.value = u64::MAX;
+                output.script_pubkey.clear();
+            }
+        }
+        if hash_type & SIGHASH_ANYONECANPAY != 0 {
+            tx.inputs = vec![tx.inputs.swap_remove(index)];
+        }
+        let mut preimage = tx.serialize();
+        preimage.extend_from_slice(&hash_type.to_le_bytes());
+        Ok(sha256d(&preimage))
+    }
+
+    /// The BIP-143 signature hash of a segwit v0 input
+    pub fn segwit_v0_sighash(
+        &self,
+        index: usize,
+        script_code: &[u8],
+        value: u64,
+        hash_type: u32,
+    ) -> Result<[u8; 32], BitcoinError> {
+        self.check_input(index, hash_type)?;
+        let base = hash_type & 0x1f;
+        let anyone_can_pay = hash_type & SIGHASH_ANYONECANPAY != 0;
+        let input = &self.inputs[index];
+
+        let mut hash_prevouts = [0u8; 32];
+        let mut hash_sequence = [0u8; 32];
+        let mut hash_outputs = [0u8; 32];
+        if !anyone_can_pay {
+            let mut prevouts = Vec::new();
+            for input in &self.inputs {
+                input.write_outpoint(&mut prevouts);
+            }
+            hash_prevouts = sha256d(&prevouts);
+        }
+        if !anyone_can_pay && base != SIGHASH_SINGLE && base != SIGHASH_NONE {
+            let sequences: Vec<u8> = self
+                .inputs
+                .iter()
+                .flat_map(|input| input.sequence.to_le_bytes())
+                .collect();
+            hash_sequence = sha256d(&sequences);
+        }
+        if base != SIGHASH_SINGLE && base != SIGHASH_NONE {
+            let outputs: Vec<u8> = self.outputs.iter().flat_map(TxOut::serialize).collect();
+            hash_outputs = sha256d(&outputs);
+        } else if base == SIGHASH_SINGLE {
+            hash_outputs = sha256d(&self.outputs[index].serialize());
+        }
+
+        let mut preimage = self.version.to_le_bytes().to_vec();
+        preimage.extend_from_slice(&hash_prevouts);
+        preimage.extend_from_slice(&hash_sequence);
+        input.write_outpoint(&mut preimage);
+        write_var_bytes(&mut preimage, script_code);
+        preimage.extend_from_slice(&value.to_le_bytes());
+        preimage.extend_from_slice(&input.sequence.to_le_bytes());
+        preimage.extend_from_slice(&hash_outputs);
+        preimage.extend_from_slice(&self.lock_time.to_le_bytes());
+        preimage.extend_from_slice(&hash_type.to_le_bytes());
+        Ok(sha256d(&preimage))
+    }
+
+    /// The BIP-341 signature hash of a taproot key path spend. `prevouts` are the outputs
+    /// spent by every input, or by this input alone with `SIGHASH_ANYONECANPAY`.
+    pub fn taproot_key_sighash(
+        &self,
+        index: usize,
+        prevouts: &[TxOut],
+        hash_type: u32,
+    ) -> Result<[u8; 32], BitcoinError> {
+        self.check_input(index, hash_type)?;
+        let base = hash_type & 0x03;
+        let anyone_can_pay = hash_type & SIGHASH_ANYONECANPAY != 0;
+        if !matches!(hash_type, 0x00..=0x03 | 0x81..=0x83) {
+            return Err(BitcoinError::UnsupportedSighash(hash_type));
+        }
+        let expected = if anyone_can_pay { 1 } else { self.inputs.len() };
+        if prevouts.len() != expected {
+            return Err(invalid(
+                "Taproot inputs need the outputs spent by every input",
+            ));
+        }
+
+        let mut message = vec![0x00, hash_type as u8];
+        message.extend_from_slice(&self.version.to_le_bytes());
+        message.extend_from_slice(&self.lock_time.to_le_bytes());
+        if !anyone_can_pay {
+            let mut outpoints = Vec::new();
+            for input in &self.inputs {
+                input.write_outpoint(&mut outpoints);
+            }
+            let amounts: Vec<u8> = prevouts
+                .iter()
+                .flat_map(|o| o.value.to_le_bytes())
+                .collect();
+            let mut scripts = Vec::new();
+            for prevout in prevouts {
+                write_var_bytes(&mut scripts, &prevout.script_pubkey);
+            }
+            let sequences: Vec<u8> = self
+                .inputs
+                .iter()
+                .flat_map(|input| input.sequence.to_le_bytes())
+                .collect();
+            message.extend_from_slice(&sha256(&outpoints));
+            message.extend_from_slice(&sha256(&amounts));
+            message.extend_from_slice(&sha256(&scripts));
+            message.extend_from_slice(&sha256(&sequences));
+        }
+        if base != SIGHASH_NONE && base != SIGHASH_SINGLE {
+            let outputs: Vec<u8> = self.outputs.iter().flat_map(TxOut::serialize).collect();
+            message.extend_from_slice(&sha256(&outputs));
+        }
+        // key path spend without annex
+        message.push(0x00);
+        if anyone_can_pay {
+            let input = &self.inputs[index];
+            input.write_outpoint(&mut message);
+            message.extend_from_slice(&prevouts[0].value.to_le_bytes());
+            write_var_bytes(&mut message, &prevouts[0].script_pubkey);
+            message.extend_from_slice(&input.sequence.to_le_bytes());
+        } else {
+            message.extend_from_slice(&(index as u32).to_le_bytes());
+        }
+        if base == SIGHASH_SINGLE {
+            message.extend_from_slice(&sha256(&self.outputs[index].serialize()));
+        }
+        Ok(tagged_hash("TapSighash", &message))
+    }
+}
+
+/// The key-value pairs of a PSBT map, in their serialized order
+pub type Map = Vec<(Vec<u8>, Vec<u8>)>;
+
+fn parse_map(reader: &mut Reader) -> Result<Map, BitcoinError> {
+    let mut map: Map = Vec::new();
+    loop {
+        let key = reader.var_bytes()?;
+        if key.is_empty() {
+            return Ok(map);
+        }
+        if map.iter().any(|(existing, _)| existing.as_slice() == key) {
+            return Err(invalid("Duplicate key"));
+        }
+        let value = reader.var_bytes()?;
+        map.push((key.to_vec(), value.to_vec()));
+    }
+}
+
+fn write_map(out: &mut Vec<u8>, map: &Map) {
+    for (key, value) in map {
+        write_var_bytes(out, key);
+        write_var_bytes(out, value);
+    }
+    out.push(0x00);
+}
+
+/// A version 0 PSBT, keeping every record it does not interpret
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Psbt {
+    pub global: Map,
+    pub tx: Transaction,
+    pub inputs: Vec<Map>,
+    pub outputs: Vec<Map>,
+}
+
+/// The value of the record with a key of `key_type` and no key data
+pub fn get(map: &Map, key_type: u8) -> Option<&[u8]> {
+    map.iter()
+        .find(|(key, _)| key.as_slice() == [key_type])
+        .map(|(_, value)| value.as_slice())
+}
+
+/// Inserts or replaces a record
+pub fn insert(map: &mut Map, key: Vec<u8>, value: Vec<u8>) {
+    match map.iter_mut().find(|(existing, _)| *existing == key) {
+        Some(record) => record.1 = value,
+        None => map.push((key, value)),
+    }
+}
+
+impl Psbt {
+    pub fn parse(bytes: &[u8]) -> Result<Self, BitcoinError> {
+        let mut reader = Reader::new(bytes);
+        if reader.read(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(invalid("Missing the PSBT magic bytes"));
+        }
+        let global = parse_map(&mut reader)?;
+        if let Some(version) = get(&global, GLOBAL_VERSION) {
+            if version != [0, 0, 0, 0] {
+                return Err(invalid("Only version 0 PSBTs are supported"));
+            }
+        }
+        let tx = get(&global, GLOBAL_UNSIGNED_TX)
+            .ok_or_else(|| invalid("Missing the unsigned transaction"))?;
+        let tx = Transaction::parse(tx)?;
+        if tx.inputs.iter().any(|input| !input.script_sig.is_empty()) {
+            return Err(invalid("The unsigned transaction has script signatures"));
+        }
+        let mut inputs = Vec::with_capacity(tx.inputs.len());
+        for _ in 0..tx.inputs.len() {
+            inputs.push(parse_map(&mut reader)?);
+        }
+        let mut outputs = Vec::with_capacity(tx.outputs.len());
+        for _ in 0..tx.outputs.len() {
+            outputs.push(parse_map(&mut reader)?);
+        }
+        if !reader.is_empty() {
+            return Err(invalid("Trailing bytes after the PSBT"));
+        }
+        Ok(Self {
+            global,
+            tx,
+            inputs,
+            outputs,
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        write_map(&mut out, &self.global);
+        for map in self.inputs.iter().chain(self.outputs.iter()) {
+            write_map(&mut out, map);
+        }
+        out
+    }
+
+    /// The output spent by an input, from its witness or non-witness UTXO
+    pub fn spent_output(&self, index: usize) -> Result<TxOut, BitcoinError> {
+        let input = &self.inputs[index];
+        let outpoint = &self.tx.inputs[index];
+        if let Some(previous) = get(input, IN_NON_WITNESS_UTXO) {
+            let previous = Transaction::parse(previous)?;
+            if previous.txid() != outpoint.txid {
+                return Err(invalid(format!(
+                    "The non-witness UTXO of input {} is not the spent transaction",
+                    index
+                )));
+            }
+            return previous
+                .outputs
+                .get(outpoint.vout as usize)
+                .cloned()
+                .ok_or_else(|| invalid(format!("Input {} spends a missing output", index)));
+        }
+        match get(input, IN_WITNESS_UTXO) {
+            Some(output) => {
+                let mut reader = Reader::new(output);
+                let output = TxOut::parse(&mut reader)?;
+                if !reader.is_empty() {
+                    return Err(invalid("Trailing bytes after the witness UTXO"));
+                }
+                Ok(output)
+            }
+            None => Err(invalid(format!("Input {} has no UTXO", index))),
+        }
+    }
+
+    /// The sighash type an input asks for, if any
+    pub fn sighash_type(&self, index: usize) -> Result<Option<u32>, BitcoinError> {
+        match get(&self.inputs[index], IN_SIGHASH_TYPE) {
+            Some(value) if value.len() == 4 => Ok(Some(u32::from_le_bytes([
+                value[0], value[1], value[2], value[3],
+            ]))),
+            Some(_) => Err(invalid("Invalid sighash type")),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::signature::hazmat::PrehashVerifier;
+
+    #[test]
+    fn test_segwit_v0_sighash() {
+        // the native P2WPKH example of BIP-143
+        let tx = Transaction::parse(&hex::decode("0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000").unwrap()).unwrap();
+        let script_code =
+            hex::decode("76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac").unwrap();
+        let sighash = tx
+            .segwit_v0_sighash(1, &script_code, 600_000_000, SIGHASH_ALL)
+            .unwrap();
+        assert_eq!(
+            hex::encode(sighash),
+            "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+        );
+        assert!(tx
+            .segwit_v0_sighash(2, &script_code, 1, SIGHASH_ALL)
+            .is_err());
+    }
+
+    #[test]
+    fn test_legacy_sighash() {
+        // input 0 of 452c629d67e41baec3ac6f04fe744b4b9617f8f859c63b3002f8684e7a4fee03,
+        // its signature checks the sighash against the key that signed it on chain
+        let tx = Transaction::parse(&hex::decode("0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600").unwrap()).unwrap();
+        let script_code =
+            hex::decode("76a914a802fc56c704ce87c42d7c92eb75e7896bdc41ae88ac").unwrap();
+        let sighash = tx.legacy_sighash(0, &script_code, SIGHASH_ALL).unwrap();
+        assert_eq!(
+            hex::encode(sighash),
+            "27e0c5994dec7824e56dec6b2fcb342eb7cdb0d0957c2fce9882f715e85d81a6"
+        );
+
+        let script_sig = &tx.inputs[0].script_sig;
+        let signature_length = usize::from(script_sig[0]);
+        let signature = k256::ecdsa::Signature::from_der(&script_sig[1..signature_length]).unwrap();
+        assert_eq!(script_sig[signature_length] as u32, SIGHASH_ALL);
+        let key = k256::ecdsa::VerifyingKey::from_sec1_bytes(&script_sig[signature_length + 2..])
+            .unwrap();
+        assert!(key.verify_prehash(&sighash, &signature).is_ok());
+    }
+
+    #[test]
+    fn test_taproot_key_sighash() {
+        // keyPathSpending of the BIP-341 wallet test vectors
+        let tx = Transaction::parse(&hex::decode("02000000097de20cbff686da83a54981d2b9bab3586f4ca7e48f57f5b55963115f3b334e9c010000000000000000d7b7cab57b1393ace2d064f4d4a2cb8af6def61273e127517d44759b6dafdd990000000000fffffffff8e1f583384333689228c5d28eac13366be082dc57441760d957275419a418420000000000fffffffff0689180aa63b30cb162a73c6d2a38b7eeda2a83ece74310fda0843ad604853b0100000000feffffffaa5202bdf6d8ccd2ee0f0202afbbb7461d9264a25e5bfd3c5a52ee1239e0ba6c0000000000feffffff956149bdc66faa968eb2be2d2faa29718acbfe3941215893a2a3446d32acd050000000000000000000e664b9773b88c09c32cb70a2a3e4da0ced63b7ba3b22f848531bbb1d5d5f4c94010000000000000000e9aa6b8e6c9de67619e6a3924ae25696bb7b694bb677a632a74ef7eadfd4eabf0000000000ffffffffa778eb6a263dc090464cd125c466b5a99667720b1c110468831d058aa1b82af10100000000ffffffff0200ca9a3b000000001976a91406afd46bcdfd22ef94ac122aa11f241244a37ecc88ac807840cb0000000020ac9a87f5594be208f8532db38cff670c450ed2fea8fcdefcc9a663f78bab962b0065cd1d").unwrap()).unwrap();
+        let spent: Vec<TxOut> = [
+            (
+                420_000_000,
+                "512053a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343",
+            ),
+            (
+                462_000_000,
+                "5120147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3",
+            ),
+            (
+                294_000_000,
+                "76a914751e76e8199196d454941c45d1b3a323f1433bd688ac",
+            ),
+            (
+                504_000_000,
+                "5120e4d810fd50586274face62b8a807eb9719cef49c04177cc6b76a9a4251d5450e",
+            ),
+            (
+                630_000_000,
+                "512091b64d5324723a985170e4dc5a0f84c041804f2cd12660fa5dec09fc21783605",
+            ),
+            (378_000_000, "00147dd65592d0ab2fe0d0257d571abf032cd9db93dc"),
+            (
+                672_000_000,
+                "512075169f4001aa68f15bbed28b218df1d0a62cbbcf1188c6665110c293c907b831",
+            ),
+            (
+                546_000_000,
+                "5120712447206d7a5238acc7ff53fbe94a3b64539ad291c7cdbc490b7577e4b17df5",
+            ),
+            (
+                588_000_000,
+                "512077e30a5522dd9f894c3f8b8bd4c4b2cf82ca7da8a3ea6a239655c39c050ab220",
+            ),
+        ]
+        .iter()
+        .map(|(value, script)| TxOut {
+            value: *value,
+            script_pubkey: hex::decode(script).unwrap(),
+        })
+        .collect();
+        let vectors = [
+            (
+                0,
+                0x03,
+                "2514a6272f85cfa0f45eb907fcb0d121b808ed37c6ea160a5a9046ed5526d555",
+            ),
+            (
+                1,
+                0x83,
+                "325a644af47e8a5a2591cda0ab0723978537318f10e6a63d4eed783b96a71a4d",
+            ),
+            (
+                3,
+                0x01,
+                "bf013ea93474aa67815b1b6cc441d23b64fa310911d991e713cd34c7f5d46669",
+            ),
+            (
+                4,
+                0x00,
+                "4f900a0bae3f1446fd48490c2958b5a023228f01661cda3496a11da502a7f7ef",
+            ),
+            (
+                6,
+                0x02,
+                "15f25c298eb5cdc7eb1d638dd2d45c97c4c59dcaec6679cfc16ad84f30876b85",
+            ),
+            (
+                7,
+                0x82,
+                "cd292de50313804dabe4685e83f923d2969577191a3e1d2882220dca88cbeb10",
+            ),
+            (
+                8,
+                0x81,
+                "cccb739eca6c13a8a89e6e5cd317ffe55669bbda23f2fd37b0f18755e008edd2",
+            ),
+        ];
+        for (index, hash_type, expected) in vectors.iter() {
+            let prevouts = if hash_type & SIGHASH_ANYONECANPAY != 0 {
+                &spent[*index..*index + 1]
+            } else {
+                &spent[..]
+            };
+            let sighash = tx
+                .taproot_key_sighash(*index, prevouts, *hash_type)
+                .unwrap();
+            assert_eq!(hex::encode(sighash), *expected, "input {}", index);
+        }
+        assert!(tx
+            .taproot_key_sighash(0, &spent[..1], SIGHASH_DEFAULT)
+            .is_err());
+    }
+
+    #[test]
+    fn test_psbt_round_trip() {
+        let tx = Transaction {
+            version: 2,
+            inputs: vec![TxIn {
+                txid: [7u8; 32],
+                vout: 1,
+                script_sig: Vec::new(),
+                sequence: 0xffff_fffd,
+            }],
+            outputs: vec![TxOut {
+                value: 1000,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+        };
+        let psbt = Psbt {
+            global: vec![(vec![GLOBAL_UNSIGNED_TX], tx.serialize())],
+            tx,
+            inputs: vec![vec![(vec![0xfc, 0x01], vec![0x02])]],
+            outputs: vec![Vec::new()],
+        };
+        let bytes = psbt.serialize();
+        assert_eq!(Psbt::parse(&bytes).unwrap(), psbt);
+        assert!(Psbt::parse(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Psbt::parse(&bytes[1..]).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Recursive length prefix encoding, as used by ethereum transactions.
+//!
+//! Items are encoded on their own and lists wrap already encoded items.
+
+/// Encodes a byte string
+pub fn bytes(value: &[u8]) -> Vec<u8> {
+    if value.len() == 1 && value[0] < 0x80 {
+        return value.to_vec();
+    }
+    let mut encoded = header(value.len(), 0x80);
+    encoded.extend_from_slice(value);
+    encoded
+}
+
+/// Encodes an unsigned integer given as big-endian bytes, without its leading zeros
+pub fn uint(value: &[u8]) -> Vec<u8> {
+    let start = value.iter().position(|b| *b != 0).unwrap_or(value.len());
+    bytes(&value[start..])
+}
+
+pub fn u64(value: u64) -> Vec<u8> {
+    uint(&value.to_be_bytes())
+}
+
+/// Encodes a list of encoded items
+pub fn list(items: &[Vec<u8>]) -> Vec<u8> {
+    let len = items.iter().map(Vec::len).sum();
+    let mut encoded = header(len, 0xc0);
+    for item in items {
+        encoded.extend_from_slice(item);
+    }
+    encoded
+}
+
+fn header(len: usize, offset: u8) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+    let len = len.to_be_bytes();
+    let start = len.iter().position(|b| *b != 0).unwrap_or(len.len() - 1);
+    let mut header = vec![offset + 55 + (len.len() - start) as u8];
+    header.extend_from_slice(&len[start..]);
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(bytes(b"dog"), hex::decode("83646f67").unwrap());
+        assert_eq!(bytes(b""), [0x80]);
+        assert_eq!(bytes(&[0x0f]), [0x0f]);
+        assert_eq!(u64(0), [0x80]);
+        assert_eq!(u64(1024), [0x82, 0x04, 0x00]);
+        assert_eq!(
+            list(&[bytes(b"cat"), bytes(b"dog")]),
+            hex::decode("c88363617483646f67").unwrap()
+        );
+        assert_eq!(list(&[]), [0xc0]);
+        // [ [], [[]], [ [], [[]] ] ]
+        let set = list(&[
+            list(&[]),
+            list(&[list(&[])]),
+            list(&[list(&[]), list(&[list(&[])])]),
+        ]);
+        assert_eq!(set, hex::decode("c7c0c1c0c3c0c1c0").unwrap());
+
+        let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        let encoded = bytes(lorem);
+        assert_eq!(encoded[..2], [0xb8, 0x38]);
+        assert_eq!(&encoded[2..], &lorem[..]);
+    }
+}
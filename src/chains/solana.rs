@@ -0,0 +1,289 @@
+use crate::chains::errors::SolanaError;
+use crate::errors::EraError;
+#[cfg(feature = "wasm")]
+use crate::wasm_bindgen;
+use crate::Serialize;
+#[cfg(feature = "wasm")]
+use crate::{to_value, JsValue};
+use sp_core::{ed25519, Pair};
+use zeroize::{Zeroize, Zeroizing};
+
+/// The SLIP-0010 path of solana account `i`, as derived by Phantom, Solflare and the CLI
+pub fn account_path(index: u32) -> String {
+    format!("m/44'/501'/{}'/0'", index)
+}
+
+/// The domain that prefixes off-chain messages, so they never parse as a transaction
+pub const SIGNING_DOMAIN: &[u8; 16] = b"\xffsolana offchain";
+/// The longest off-chain message a Ledger displays, formats 0 and 1
+pub const MAX_LEDGER_MESSAGE_LEN: usize = 1212;
+/// The longest off-chain message, format 2
+pub const MAX_MESSAGE_LEN: usize = 65515;
+
+/// Reads a compact-u16, the length prefix of solana arrays
+fn short_vec(bytes: &[u8], offset: &mut usize) -> Result<usize, SolanaError> {
+    let mut value = 0usize;
+    for i in 0..3 {
+        let byte = *bytes
+            .get(*offset)
+            .ok_or_else(|| SolanaError::InvalidMessage("Truncated length".into()))?;
+        *offset += 1;
+        value |= usize::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(SolanaError::InvalidMessage("Length overflows a u16".into()))
+}
+
+/// The position of `signer` among the required signers of a legacy or v0 message
+fn signer_index(message: &[u8], signer: &[u8; 32]) -> Result<usize, SolanaError> {
+    let mut offset = 0;
+    match message.first() {
+        Some(prefix) if prefix & 0x80 != 0 => {
+            if prefix & 0x7f != 0 {
+                return Err(SolanaError::InvalidMessage(format!(
+                    "Unsupported message version {}",
+                    prefix & 0x7f
+                )));
+            }
+            offset += 1;
+        }
+        Some(_) => {}
+        None => return Err(SolanaError::InvalidMessage("Empty message".into())),
+    }
+    let required = usize::from(
+        *message
+            .get(offset)
+            .ok_or_else(|| SolanaError::InvalidMessage("Truncated header".into()))?,
+    );
+    offset += 3;
+    let count = short_vec(message, &mut offset)?;
+    let keys = message
+        .get(offset..offset + 32 * count)
+        .ok_or_else(|| SolanaError::InvalidMessage("Truncated account keys".into()))?;
+    keys.chunks(32)
+        .take(required)
+        .position(|key| key == signer)
+        .ok_or(SolanaError::NotASigner)
+}
+
+/// The message format of an off-chain message: 0 for restricted ASCII, 1 for UTF-8 a Ledger
+/// can show, 2 for longer UTF-8
+fn message_format(message: &str) -> Result<u8, SolanaError> {
+    if message.is_empty() {
+        return Err(SolanaError::InvalidMessage("Empty message".into()));
+    }
+    let ascii = message.bytes().all(|b| (0x20..=0x7e).contains(&b));
+    match message.len() {
+        len if len <= MAX_LEDGER_MESSAGE_LEN && ascii => Ok(0),
+        len if len <= MAX_LEDGER_MESSAGE_LEN => Ok(1),
+        len if len <= MAX_MESSAGE_LEN => Ok(2),
+        len => Err(SolanaError::InvalidMessage(format!(
+            "Off-chain messages are at most {} bytes, got {}",
+            MAX_MESSAGE_LEN, len
+        ))),
+    }
+}
+
+/// Serializes a version 0 off-chain message: the signing domain, version, format, little
+/// endian length and the message
+pub fn offchain_message(message: &str) -> Result<Vec<u8>, SolanaError> {
+    let format = message_format(message)?;
+    let mut encoded = SIGNING_DOMAIN.to_vec();
+    encoded.push(0);
+    encoded.push(format);
+    encoded.extend_from_slice(&(message.len() as u16).to_le_bytes());
+    encoded.extend_from_slice(message.as_bytes());
+    Ok(encoded)
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyObject {
+    /// Base58 of the 64 byte secret and public key, the format wallets import
+    pub private_key: String,
+    pub public_key: String,
+    /// Base58 public key
+    pub address: String,
+    pub path: String,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct SolanaSigner {
+    signer: Zeroizing<Vec<u8>>,
+    path: String,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SolanaSigner {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn fetch_key(&self) -> Result<JsValue, JsValue> {
+        Ok(to_value(&self.get_key()?)?)
+    }
+
+    /// The base58 address of the signer
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn address(&self) -> Result<String, EraError> {
+        Ok(bs58::encode(self.pair()?.public().0).into_string())
+    }
+
+    /// Signs a serialized legacy or v0 transaction message, the signer must be one of its
+    /// required signers. Returns the 64 byte signature.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, EraError> {
+        let pair = self.pair()?;
+        signer_index(message, &pair.public().0)?;
+        Ok(pair.sign(message).0.to_vec())
+    }
+
+    /// Signs a serialized transaction, the signatures followed by the message, and returns
+    /// it with the signature of the signer in its slot
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn sign_transaction(&self, transaction: &[u8]) -> Result<Vec<u8>, EraError> {
+        let pair = self.pair()?;
+        let mut offset = 0;
+        let count = short_vec(transaction, &mut offset)?;
+        let start = offset;
+        let message = transaction
+            .get(start + 64 * count..)
+            .ok_or_else(|| SolanaError::InvalidMessage("Truncated signatures".into()))?;
+        let index = signer_index(message, &pair.public().0)?;
+        if index >= count {
+            return Err(SolanaError::InvalidMessage(
+                "The transaction has fewer signature slots than signers".into(),
+            )
+            .into());
+        }
+        let signature = pair.sign(message);
+        let mut signed = transaction.to_vec();
+        signed[start + 64 * index..start + 64 * (index + 1)].copy_from_slice(&signature.0);
+        Ok(signed)
+    }
+
+    /// Signs a message in the off-chain message format of solana wallets. Returns the 64
+    /// byte signature over the serialized message.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn sign_offchain_message(&self, message: &str) -> Result<Vec<u8>, EraError> {
+        let message = offchain_message(message)?;
+        Ok(self.pair()?.sign(&message).0.to_vec())
+    }
+
+    /// Zeroes the key of the signer. The signer is also wiped when it is freed.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn wipe(&mut self) {
+        self.signer.zeroize();
+    }
+}
+
+impl SolanaSigner {
+    pub fn new(signer: Vec<u8>, path: String) -> Self {
+        Self {
+            signer: Zeroizing::new(signer),
+            path,
+        }
+    }
+
+    fn pair(&self) -> Result<ed25519::Pair, SolanaError> {
+        ed25519::Pair::from_seed_slice(&self.signer).map_err(|_| SolanaError::InvalidKey)
+    }
+
+    /// The keys and base58 address of the signer
+    pub fn get_key(&self) -> Result<KeyObject, SolanaError> {
+        let public = self.pair()?.public().0;
+        let mut keypair = Zeroizing::new(self.signer.to_vec());
+        keypair.extend_from_slice(&public);
+        Ok(KeyObject {
+            private_key: bs58::encode(&*keypair).into_string(),
+            public_key: format!("0x{}", hex::encode(public)),
+            address: bs58::encode(public).into_string(),
+            path: self.path.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::ed25519::{Public, Signature};
+    use std::convert::TryInto;
+
+    fn signer() -> SolanaSigner {
+        SolanaSigner::new(vec![7u8; 32], account_path(0))
+    }
+
+    /// A message with `signers` required signers, the first of them writable and paying
+    fn message(version: Option<u8>, signers: &[[u8; 32]]) -> Vec<u8> {
+        let mut message = Vec::new();
+        if let Some(version) = version {
+            message.push(0x80 | version);
+        }
+        message.extend_from_slice(&[signers.len() as u8, 0, 1, signers.len() as u8 + 1]);
+        for signer in signers {
+            message.extend_from_slice(signer);
+        }
+        message.extend_from_slice(&[0u8; 32]);
+        message.extend_from_slice(&[9u8; 32]);
+        // one instruction of the system program without accounts or data
+        message.extend_from_slice(&[1, signers.len() as u8, 0, 0]);
+        if version.is_some() {
+            // no address table lookups
+            message.push(0);
+        }
+        message
+    }
+
+    fn verify(signature: &[u8], message: &[u8], public: [u8; 32]) -> bool {
+        let signature = Signature::from_raw(signature.try_into().unwrap());
+        ed25519::Pair::verify(&signature, message, &Public::from_raw(public))
+    }
+
+    #[test]
+    fn test_sign_messages() {
+        let signer = signer();
+        let public = signer.pair().unwrap().public().0;
+        let other = [3u8; 32];
+
+        for version in [None, Some(0)].iter() {
+            let message = message(*version, &[other, public]);
+            let signature = signer.sign_message(&message).unwrap();
+            assert!(verify(&signature, &message, public));
+
+            let mut transaction = vec![2u8];
+            transaction.extend_from_slice(&[0u8; 128]);
+            transaction.extend_from_slice(&message);
+            let signed = signer.sign_transaction(&transaction).unwrap();
+            assert_eq!(signed[1..65], [0u8; 64]);
+            assert!(verify(&signed[65..129], &message, public));
+            assert_eq!(signed[129..], message[..]);
+        }
+
+        let message = message(None, &[other]);
+        let error = signer.sign_message(&message).err().unwrap();
+        assert_eq!(error.message(), SolanaError::NotASigner.to_string());
+        let mut unknown = message.clone();
+        unknown.insert(0, 0x81);
+        assert!(signer.sign_message(&unknown).is_err());
+        assert!(signer.sign_message(&message[..20]).is_err());
+    }
+
+    #[test]
+    fn test_offchain_message() {
+        let encoded = offchain_message("Test message").unwrap();
+        assert_eq!(encoded[..16], SIGNING_DOMAIN[..]);
+        assert_eq!(encoded[16..20], [0, 0, 12, 0]);
+        assert_eq!(&encoded[20..], b"Test message");
+        assert_eq!(offchain_message("Привет").unwrap()[17], 1);
+        assert_eq!(offchain_message(&"a".repeat(2000)).unwrap()[17], 2);
+        assert!(offchain_message("").is_err());
+        assert!(offchain_message(&"a".repeat(MAX_MESSAGE_LEN + 1)).is_err());
+
+        let signer = signer();
+        let signature = signer.sign_offchain_message("Test message").unwrap();
+        assert!(verify(
+            &signature,
+            &encoded,
+            signer.pair().unwrap().public().0
+        ));
+    }
+}
@@ -0,0 +1,160 @@
+//! SS58 network formats of substrate addresses, from the registry embedded by `ss58-registry`.
+
+use crate::chains::errors::PolkadotError;
+#[cfg(feature = "wasm")]
+use crate::wasm_bindgen;
+use crate::Serialize;
+#[cfg(feature = "wasm")]
+use crate::{to_value, JsValue};
+use sp_core::crypto::{AccountId32, PublicError, Ss58AddressFormat, Ss58Codec};
+
+/// The highest prefix SS58 can encode, prefixes are 14 bits
+pub const MAX_PREFIX: u16 = 16_383;
+
+/// The format of a network, given by its registry name (`polkadot`, `kusama`, `substrate`,
+/// ...) or its prefix number
+pub fn parse_format(network: &str) -> Result<Ss58AddressFormat, PolkadotError> {
+    let unknown = || PolkadotError::UnknownNetwork(network.into());
+    let format = match network.trim().parse::<u16>() {
+        Ok(prefix) if prefix <= MAX_PREFIX => Ss58AddressFormat::custom(prefix),
+        Ok(_) => return Err(unknown()),
+        Err(_) => {
+            let names = Ss58AddressFormat::all_names();
+            let index = names
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(network.trim()))
+                .ok_or_else(unknown)?;
+            Ss58AddressFormat::all()[index].into()
+        }
+    };
+    if format.is_reserved() {
+        return Err(unknown());
+    }
+    Ok(format)
+}
+
+/// The registry name of a format, `None` for prefixes outside the registry
+pub fn network_name(format: Ss58AddressFormat) -> Option<&'static str> {
+    Ss58AddressFormat::all()
+        .iter()
+        .position(|known| Ss58AddressFormat::from(*known) == format)
+        .map(|index| Ss58AddressFormat::all_names()[index])
+}
+
+/// Encodes a 32 byte account id for a network
+pub fn encode(account: &AccountId32, format: Ss58AddressFormat) -> String {
+    account.to_ss58check_with_version(format)
+}
+
+/// Decodes an address, checking its checksum, length and prefix
+pub fn decode(address: &str) -> Result<(AccountId32, Ss58AddressFormat), PolkadotError> {
+    AccountId32::from_ss58check_with_version(address.trim()).map_err(|e| {
+        let reason = match e {
+            PublicError::BadBase58 => "is not base58",
+            PublicError::BadLength => "has the wrong length",
+            PublicError::InvalidChecksum => "has an invalid checksum",
+            PublicError::InvalidPrefix | PublicError::FormatNotAllowed => "has an invalid prefix",
+            _ => "is not an SS58 address",
+        };
+        PolkadotError::InvalidAddress(format!("'{}' {}", address, reason))
+    })
+}
+
+/// A decoded address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AddressInfo {
+    pub prefix: u16,
+    /// The registry name of the network, if it is registered
+    pub network: Option<String>,
+    /// The `0x` hex of the account id
+    pub account_id: String,
+}
+
+/// Checks the checksum and prefix of an address, and that it belongs to `network` when one
+/// is given
+pub fn validate(address: &str, network: Option<&str>) -> Result<AddressInfo, PolkadotError> {
+    let (account, format) = decode(address)?;
+    if let Some(network) = network {
+        let expected = parse_format(network)?;
+        if expected != format {
+            return Err(PolkadotError::InvalidAddress(format!(
+                "'{}' has prefix {}, expected {}",
+                address,
+                format.prefix(),
+                expected.prefix()
+            )));
+        }
+    }
+    Ok(AddressInfo {
+        prefix: format.prefix(),
+        network: network_name(format).map(String::from),
+        account_id: format!("0x{}", hex::encode(AsRef::<[u8]>::as_ref(&account))),
+    })
+}
+
+/// Re-encodes an address of any network for another network
+pub fn reencode(address: &str, network: &str) -> Result<String, PolkadotError> {
+    let (account, _) = decode(address)?;
+    Ok(encode(&account, parse_format(network)?))
+}
+
+/// Re-encodes an address for the network given by name or prefix number
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = ss58Reencode)]
+pub fn ss58_reencode(address: &str, network: &str) -> Result<String, JsValue> {
+    Ok(reencode(address, network)?)
+}
+
+/// Checks an address, and its network when one is given. Returns
+/// `{ prefix, network, account_id }`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = ss58Validate)]
+pub fn ss58_validate(address: &str, network: Option<String>) -> Result<JsValue, JsValue> {
+    Ok(to_value(&validate(address, network.as_deref())?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[test]
+    fn test_formats() {
+        assert_eq!(parse_format("polkadot").unwrap().prefix(), 0);
+        assert_eq!(parse_format("Kusama").unwrap().prefix(), 2);
+        assert_eq!(parse_format("substrate").unwrap().prefix(), 42);
+        assert_eq!(parse_format("1284").unwrap().prefix(), 1284);
+        assert_eq!(network_name(parse_format("2").unwrap()), Some("kusama"));
+        assert_eq!(network_name(parse_format("16000").unwrap()), None);
+        assert!(parse_format("16384").is_err());
+        assert!(parse_format("nowhere").is_err());
+        // reserved for other purposes
+        assert!(parse_format("46").is_err());
+    }
+
+    #[test]
+    fn test_reencode_and_validate() {
+        let polkadot = reencode(ALICE, "polkadot").unwrap();
+        assert_eq!(polkadot, "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5");
+        assert_eq!(
+            reencode(&polkadot, "kusama").unwrap(),
+            "HNZata7iMYWmk5RvZRTiAsSDhV8366zq2YGb3tLH5Upf74F"
+        );
+        assert_eq!(reencode(&polkadot, "42").unwrap(), ALICE);
+
+        let info = validate(ALICE, Some("substrate")).unwrap();
+        assert_eq!(info.prefix, 42);
+        assert_eq!(info.network.as_deref(), Some("substrate"));
+        assert_eq!(
+            info.account_id,
+            "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d"
+        );
+        assert!(validate(ALICE, Some("polkadot")).is_err());
+
+        let mut corrupt = ALICE.to_string();
+        corrupt.replace_range(10..11, "G");
+        assert!(validate(&corrupt, None).is_err());
+        assert!(validate("0x1234", None).is_err());
+    }
+}
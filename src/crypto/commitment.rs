@@ -0,0 +1,222 @@
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+
+use crate::envelope::{EnvelopeError, ShardEnvelope};
+use crate::feldman::verify_share;
+use crate::layout::ShardScheme;
+#[cfg(feature = "wasm")]
+use crate::{errors::from_js, wasm_bindgen, JsValue};
+use crate::{Deserialize, Serialize};
+
+/// Feldman commitments to the polynomials of one wallet generation, published when the
+/// shards are issued. Only wallets of the `feldman` scheme have them.
+///
+/// The entropy is split in 31 byte chunks, each the constant term `a_0` of its own polynomial
+/// over the ristretto255 scalar field. `coefficients` holds `a_j·G` for every coefficient of
+/// every polynomial, so a shard `s` at `x = index + 1` is valid when `s·G = Σ x^j·(a_j·G)`
+/// for each chunk. A shard that passes lies on the committed polynomials, the ones every other
+/// valid shard lies on, so a dealer can not hand out shards that rebuild different seeds.
+///
+/// The check needs the shard itself: plaintext shards, like the system shard, are checked
+/// before anything is decrypted, the encrypted ones as soon as they are. The commitments
+/// only depend on the polynomials, so they stay valid when a shard is resealed by
+/// `change_pin` or `recover`; `refresh` deals new polynomials and returns new commitments.
+/// `a_0·G` hides the seed computationally, which is safe for uniformly random entropy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShardCommitments {
+    pub wallet_id: String,
+    pub generation: u32,
+    /// Hex encoded compressed points `a_j·G`, `data_shards` of them per chunk of the entropy
+    pub coefficients: Vec<Vec<String>>,
+}
+
+impl ShardCommitments {
+    pub fn new(
+        wallet_id: String,
+        generation: u32,
+        coefficients: &[Vec<CompressedRistretto>],
+    ) -> Self {
+        Self {
+            wallet_id,
+            generation,
+            coefficients: coefficients
+                .iter()
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .map(|point| hex::encode(point.as_bytes()))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Decodes the commitments of polynomials with `threshold` coefficients
+    fn points(&self, threshold: usize) -> Result<Vec<Vec<RistrettoPoint>>, EnvelopeError> {
+        if self.coefficients.is_empty() {
+            return Err(EnvelopeError::InvalidCommitments);
+        }
+        self.coefficients
+            .iter()
+            .map(|chunk| {
+                if chunk.len() != threshold {
+                    return Err(EnvelopeError::InvalidCommitments);
+                }
+                chunk
+                    .iter()
+                    .map(|point| {
+                        hex::decode(point)
+                            .ok()
+                            .and_then(|bytes| CompressedRistretto::from_slice(&bytes).ok())
+                            .and_then(|point| point.decompress())
+                            .ok_or(EnvelopeError::InvalidCommitments)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Checks a decrypted shard payload at `index` of a wallet whose polynomials have
+    /// `threshold` coefficients
+    pub fn verify_share(
+        &self,
+        index: usize,
+        threshold: usize,
+        share: &[u8],
+    ) -> Result<(), EnvelopeError> {
+        if !verify_share(share, index, &self.points(threshold)?) {
+            return Err(EnvelopeError::CommitmentMismatch(index));
+        }
+        Ok(())
+    }
+
+    /// Checks that an encoded envelope belongs to the committed wallet generation and, unless
+    /// it is encrypted, that its shard lies on the committed polynomials.
+    /// An encrypted shard fails with `SealedShard` once everything else checks out.
+    pub fn verify(&self, shard: &[u8]) -> Result<(), EnvelopeError> {
+        let envelope = ShardEnvelope::decode(shard)?;
+        if envelope.wallet_id_hex() != self.wallet_id {
+            return Err(EnvelopeError::WalletMismatch);
+        }
+        if envelope.generation != self.generation {
+            return Err(EnvelopeError::GenerationMismatch {
+                expected: self.generation,
+                found: envelope.generation,
+            });
+        }
+        if envelope.scheme != ShardScheme::Feldman {
+            return Err(EnvelopeError::SchemeMismatch);
+        }
+        let index = envelope.index as usize;
+        if envelope.encrypted {
+            return Err(EnvelopeError::SealedShard(index));
+        }
+        self.verify_share(index, envelope.data_shards as usize, &envelope.payload)
+    }
+}
+
+/// Verifies that a shard belongs to a wallet without reconstructing anything.
+/// The shard is checked against the Feldman commitments of the wallet, so a shard that does
+/// not lie on the polynomials of the other shards is caught, not only an altered one.
+///
+/// # Arguments
+///
+/// * `shard` - The enveloped shard, it must not be encrypted
+/// * `commitments` - The commitments published when the shard was issued
+///
+/// # Returns
+///
+/// `true` when the shard lies on the committed polynomials and `false` when it does not.
+/// An error is returned when the shard is not an envelope, is encrypted, or belongs to another
+/// wallet, generation or scheme.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = verifyShard)]
+pub fn verify_shard(shard: Vec<u8>, commitments: JsValue) -> Result<bool, JsValue> {
+    let commitments: ShardCommitments = from_js(commitments, "shard commitments")?;
+    match commitments.verify(&shard) {
+        Ok(()) => Ok(true),
+        Err(EnvelopeError::CommitmentMismatch(_)) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::ENVELOPE_VERSION;
+    use crate::feldman::split_secret_internal;
+
+    fn envelope(index: usize, payload: Vec<u8>) -> ShardEnvelope {
+        ShardEnvelope {
+            version: ENVELOPE_VERSION,
+            scheme: ShardScheme::Feldman,
+            data_shards: 2,
+            parity_shards: 3,
+            index: index as u16,
+            generation: 0,
+            wallet_id: [1u8; 16],
+            encrypted: false,
+            payload,
+        }
+    }
+
+    #[test]
+    fn test_verify_commitment() {
+        let secret = [9u8; 16];
+        let split = split_secret_internal(&secret, 2, 5).unwrap();
+        let commitments = ShardCommitments::new(hex::encode([1u8; 16]), 0, &split.commitments);
+        let shard = envelope(3, split.shares[3].clone());
+        assert_eq!(commitments.verify(&shard.encode()), Ok(()));
+
+        // a well formed shard with another payload
+        let mut forged = shard.clone();
+        forged.payload[0] ^= 1;
+        assert_eq!(
+            commitments.verify(&forged.encode()),
+            Err(EnvelopeError::CommitmentMismatch(3))
+        );
+
+        // a dealer handing out a shard of another polynomial, with a valid envelope
+        let other = split_secret_internal(&secret, 2, 5).unwrap();
+        let inconsistent = envelope(3, other.shares[3].clone());
+        assert_eq!(
+            commitments.verify(&inconsistent.encode()),
+            Err(EnvelopeError::CommitmentMismatch(3))
+        );
+
+        // a valid shard in the wrong position
+        let moved = envelope(4, split.shares[3].clone());
+        assert_eq!(
+            commitments.verify(&moved.encode()),
+            Err(EnvelopeError::CommitmentMismatch(4))
+        );
+
+        let sealed = ShardEnvelope {
+            encrypted: true,
+            ..shard.clone()
+        };
+        assert_eq!(
+            commitments.verify(&sealed.encode()),
+            Err(EnvelopeError::SealedShard(3))
+        );
+        let shamir = ShardEnvelope {
+            scheme: ShardScheme::Shamir,
+            ..shard.clone()
+        };
+        assert_eq!(
+            commitments.verify(&shamir.encode()),
+            Err(EnvelopeError::SchemeMismatch)
+        );
+
+        // commitments to polynomials of a higher degree than the layout
+        let wider = ShardEnvelope {
+            data_shards: 3,
+            parity_shards: 2,
+            ..shard
+        };
+        assert_eq!(
+            commitments.verify(&wider.encode()),
+            Err(EnvelopeError::InvalidCommitments)
+        );
+    }
+}
@@ -1,5 +1,9 @@
+use crate::errors::{EraError, ErrorCode};
 use crate::{wallet::Signer, Serialize};
 use bip32::Prefix;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use zeroize::Zeroizing;
 
 use crate::{key::Account, DerivationPath, XPrv, XPub};
 
@@ -17,29 +21,96 @@ pub trait  Ecdsa {
     type KeyObject;
 
     /// Generates the root private key from the seed
-    fn generate_root_key(&self) -> XPrv;
+    fn generate_root_key(&self) -> Result<XPrv, EraError>;
     /// Generates the root public key from the seed
-    fn generate_root_public_key(&self) -> XPub;
+    fn generate_root_public_key(&self) -> Result<XPub, EraError>;
     /// Generates an extended key from the seed
-    fn generate_extended_key(&self, path: &str) -> Self::KeyObject;
+    fn generate_extended_key(&self, path: &str) -> Result<Self::KeyObject, EraError>;
+}
+
+fn root_key(seed: &[u8]) -> Result<XPrv, EraError> {
+    XPrv::new(seed).map_err(|e| EraError::new(ErrorCode::InvalidKey, format!("Invalid seed: {}", e)))
+}
+
+/// Parses a BIP-32 path and derives the private key at it. A bare "/" is the root key, the
+/// same as "m".
+pub(crate) fn derive_key(seed: &[u8], path: &str) -> Result<(DerivationPath, XPrv), EraError> {
+    let path = if path == "/" { "m" } else { path };
+    let path = <DerivationPath as std::str::FromStr>::from_str(path).map_err(|e| {
+        EraError::new(
+            ErrorCode::InvalidPath,
+            format!("Invalid derivation path '{}': {}", path, e),
+        )
+    })?;
+    let xpriv = XPrv::derive_from_path(seed, &path).map_err(|e| {
+        EraError::new(ErrorCode::InvalidPath, format!("Could not derive key: {}", e))
+    })?;
+    Ok((path, xpriv))
+}
+
+/// Derives the ed25519 key at a SLIP-0010 path. Ed25519 only has hardened children, every
+/// index of the path must be hardened. Returns the normalized path and the 32 byte secret key.
+pub(crate) fn derive_ed25519_key(
+    seed: &[u8],
+    path: &str,
+) -> Result<(String, Zeroizing<[u8; 32]>), EraError> {
+    let invalid = |reason: &str| {
+        EraError::new(
+            ErrorCode::InvalidPath,
+            format!("Invalid derivation path '{}': {}", path, reason),
+        )
+    };
+    let mut steps = path.split('/');
+    if steps.next() != Some("m") {
+        return Err(invalid("it must start with m"));
+    }
+
+    let mut key = Zeroizing::new([0u8; 64]);
+    let mut mac = Hmac::<Sha512>::new_from_slice(b"ed25519 seed").map_err(|_| invalid("hmac"))?;
+    mac.update(seed);
+    key.copy_from_slice(&mac.finalize().into_bytes());
+
+    let mut normalized = String::from("m");
+    for step in steps {
+        let index = step
+            .strip_suffix('\'')
+            .or_else(|| step.strip_suffix('h'))
+            .or_else(|| step.strip_suffix('H'))
+            .ok_or_else(|| invalid("ed25519 derivation is hardened only"))?;
+        let index: u32 = index
+            .parse()
+            .ok()
+            .filter(|index| *index < 0x8000_0000)
+            .ok_or_else(|| invalid("indexes are below 2^31"))?;
+        normalized.push_str(&format!("/{}'", index));
+
+        let mut mac = Hmac::<Sha512>::new_from_slice(&key[32..]).map_err(|_| invalid("hmac"))?;
+        mac.update(&[0]);
+        mac.update(&key[..32]);
+        mac.update(&(index | 0x8000_0000).to_be_bytes());
+        key.copy_from_slice(&mac.finalize().into_bytes());
+    }
+
+    let mut secret = Zeroizing::new([0u8; 32]);
+    secret.copy_from_slice(&key[..32]);
+    Ok((normalized, secret))
 }
 
 impl Ecdsa for Account {
     type KeyObject = KeyObject;
 
-    fn generate_root_key(&self) -> XPrv {
-        XPrv::new(&self.to_bytes()).unwrap()
+    fn generate_root_key(&self) -> Result<XPrv, EraError> {
+        root_key(self.seed())
     }
 
-    fn generate_root_public_key(&self) -> XPub {
-        let xprv = self.generate_root_key();
-        xprv.public_key()
+    fn generate_root_public_key(&self) -> Result<XPub, EraError> {
+        let xprv = self.generate_root_key()?;
+        Ok(xprv.public_key())
     }
 
-    fn generate_extended_key(&self, path: &str) -> Self::KeyObject {
-        let path = <DerivationPath as std::str::FromStr>::from_str(path).unwrap();
+    fn generate_extended_key(&self, path: &str) -> Result<Self::KeyObject, EraError> {
+        let (path, xpriv) = derive_key(self.seed(), path)?;
         let prefix = Prefix::XPUB;
-        let xpriv = XPrv::derive_from_path(self.to_bytes(), &path).unwrap();
         let private_key = format!("0x{}", hex::encode(xpriv.to_bytes()));
         let key_object = KeyObject {
             private_key,
@@ -49,26 +120,25 @@ impl Ecdsa for Account {
             index: xpriv.attrs().child_number.index(),
             depth: xpriv.attrs().depth,
         };
-        key_object
+        Ok(key_object)
     }
 }
 
 impl Ecdsa for Signer {
     type KeyObject = KeyObject;
 
-    fn generate_root_key(&self) -> XPrv {
-        XPrv::new(&self.to_bytes()).unwrap()
+    fn generate_root_key(&self) -> Result<XPrv, EraError> {
+        root_key(self.seed())
     }
 
-    fn generate_root_public_key(&self) -> XPub {
-        let xprv = self.generate_root_key();
-        xprv.public_key()
+    fn generate_root_public_key(&self) -> Result<XPub, EraError> {
+        let xprv = self.generate_root_key()?;
+        Ok(xprv.public_key())
     }
 
-    fn generate_extended_key(&self, path: &str) -> Self::KeyObject {
-        let path = <DerivationPath as std::str::FromStr>::from_str(path).unwrap();
+    fn generate_extended_key(&self, path: &str) -> Result<Self::KeyObject, EraError> {
+        let (path, xpriv) = derive_key(self.seed(), path)?;
         let prefix = Prefix::XPUB;
-        let xpriv = XPrv::derive_from_path(self.to_bytes(), &path).unwrap();
         let private_key = format!("0x{}", hex::encode(xpriv.to_bytes()));
         let key_object = KeyObject {
             private_key,
@@ -78,20 +148,54 @@ impl Ecdsa for Signer {
             index: xpriv.attrs().child_number.index(),
             depth: xpriv.attrs().depth,
         };
-        key_object
+        Ok(key_object)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::str::FromStr;
 
     #[test]
     fn test_key_path() {
         let account = Account::from_str(
             "right pave sketch blanket across oppose route shell favorite domain comfort super",
-        );
-        let key_object = account.generate_extended_key("/");
+        )
+        .unwrap();
+        let key_object = account.generate_extended_key("/").unwrap();
         assert!(key_object.private_key.starts_with("0x"));
     }
+
+    #[test]
+    fn test_invalid_key_path() {
+        let account: Account =
+            "right pave sketch blanket across oppose route shell favorite domain comfort super"
+                .parse()
+                .unwrap();
+        let error = account.generate_extended_key("m/x").unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidPath);
+    }
+
+    #[test]
+    fn test_ed25519_derivation() {
+        // test vector 1 of SLIP-0010
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let (path, key) = derive_ed25519_key(&seed, "m").unwrap();
+        assert_eq!(path, "m");
+        assert_eq!(
+            hex::encode(*key),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        let (path, key) = derive_ed25519_key(&seed, "m/0H/1'/2h").unwrap();
+        assert_eq!(path, "m/0'/1'/2'");
+        assert_eq!(
+            hex::encode(*key),
+            "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9"
+        );
+
+        let error = derive_ed25519_key(&seed, "m/44'/0").err().unwrap();
+        assert_eq!(error.code(), ErrorCode::InvalidPath);
+        assert!(derive_ed25519_key(&seed, "44'").is_err());
+    }
 }
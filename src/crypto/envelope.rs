@@ -0,0 +1,437 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::Display;
+
+use crate::layout::ShardScheme;
+use crate::Serialize;
+#[cfg(feature = "wasm")]
+use crate::{to_value, wasm_bindgen, JsValue};
+
+/// Marks the start of every enveloped shard
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"ERAS";
+/// The current envelope format version. Version 2 added the generation counter.
+pub const ENVELOPE_VERSION: u8 = 2;
+
+pub const WALLET_ID_LENGTH: usize = 16;
+const CHECKSUM_LENGTH: usize = 8;
+// magic | version | scheme | flags | data | parity | index
+const PREFIX_LENGTH: usize = 4 + 1 + 1 + 1 + 2 + 2 + 2;
+const GENERATION_LENGTH: usize = 4;
+const FLAG_ENCRYPTED: u8 = 0b0000_0001;
+
+#[derive(Debug, PartialEq)]
+pub enum EnvelopeError {
+    /// The bytes are truncated or the header is malformed
+    InvalidEncoding,
+    /// The envelope was written by a newer version of the format
+    UnsupportedVersion(u8),
+    /// The scheme id is not known
+    UnknownScheme(u8),
+    /// The checksum does not match the envelope content
+    ChecksumMismatch,
+    /// The shard belongs to another wallet
+    WalletMismatch,
+    /// The shard was produced with another scheme
+    SchemeMismatch,
+    /// The shard was produced with other threshold parameters
+    ThresholdMismatch,
+    /// The shard does not belong in the slot it was given for
+    IndexMismatch { expected: usize, found: usize },
+    /// The shard was issued by another refresh of the wallet
+    GenerationMismatch { expected: u32, found: u32 },
+    /// The commitments are malformed or were made for other threshold parameters
+    InvalidCommitments,
+    /// The shard is encrypted, it can only be checked against the commitments once decrypted
+    SealedShard(usize),
+    /// The shard does not lie on the polynomials of the commitments
+    CommitmentMismatch(usize),
+    /// A bare shard, from before the envelope format, was mixed with enveloped shards
+    MixedFormats,
+}
+
+impl std::error::Error for EnvelopeError {}
+
+impl Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeError::InvalidEncoding => write!(f, "Invalid shard envelope"),
+            EnvelopeError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported shard envelope version {}", version)
+            }
+            EnvelopeError::UnknownScheme(scheme) => write!(f, "Unknown shard scheme {}", scheme),
+            EnvelopeError::ChecksumMismatch => write!(f, "Shard checksum mismatch"),
+            EnvelopeError::WalletMismatch => write!(f, "Shard belongs to another wallet"),
+            EnvelopeError::SchemeMismatch => write!(f, "Shard was produced with another scheme"),
+            EnvelopeError::ThresholdMismatch => {
+                write!(f, "Shard was produced with other threshold parameters")
+            }
+            EnvelopeError::IndexMismatch { expected, found } => write!(
+                f,
+                "Shard index mismatch: expected {}, found {}",
+                expected, found
+            ),
+            EnvelopeError::GenerationMismatch { expected, found } => write!(
+                f,
+                "Shard generation mismatch: expected {}, found {}",
+                expected, found
+            ),
+            EnvelopeError::InvalidCommitments => write!(f, "Invalid shard commitments"),
+            EnvelopeError::SealedShard(index) => write!(
+                f,
+                "Shard {} is encrypted, it can only be checked once decrypted",
+                index
+            ),
+            EnvelopeError::CommitmentMismatch(index) => {
+                write!(f, "Shard {} does not match its commitment", index)
+            }
+            EnvelopeError::MixedFormats => write!(
+                f,
+                "Bare shards can not be combined with enveloped shards, re-encode them first"
+            ),
+        }
+    }
+}
+
+/// A self-describing shard.
+///
+/// The binary layout is, with integers in big endian:
+///
+/// | field          | size          |
+/// |----------------|---------------|
+/// | magic `ERAS`   | 4             |
+/// | version        | 1             |
+/// | scheme id      | 1             |
+/// | flags          | 1             |
+/// | data shards    | 2             |
+/// | parity shards  | 2             |
+/// | shard index    | 2             |
+/// | generation     | 4             |
+/// | wallet id      | 16            |
+/// | payload length | 4             |
+/// | payload        | payload length|
+/// | checksum       | 8             |
+///
+/// The checksum is the first 8 bytes of the blake3 hash of everything before it.
+/// Version 1 envelopes have no generation field and are read as generation 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardEnvelope {
+    pub version: u8,
+    pub scheme: ShardScheme,
+    pub data_shards: u16,
+    pub parity_shards: u16,
+    pub index: u16,
+    /// Incremented every time the shards of the wallet are refreshed
+    pub generation: u32,
+    pub wallet_id: [u8; WALLET_ID_LENGTH],
+    /// Whether the payload is sealed with a pin or password
+    pub encrypted: bool,
+    pub payload: Vec<u8>,
+}
+
+impl ShardEnvelope {
+    /// Generates a random wallet id
+    pub fn new_wallet_id() -> Result<[u8; WALLET_ID_LENGTH], getrandom::Error> {
+        let mut wallet_id = [0u8; WALLET_ID_LENGTH];
+        getrandom::getrandom(&mut wallet_id)?;
+        Ok(wallet_id)
+    }
+
+    /// Returns true when the bytes start with the envelope magic
+    pub fn is_envelope(bytes: &[u8]) -> bool {
+        bytes.starts_with(&ENVELOPE_MAGIC)
+    }
+
+    /// Serializes the envelope into the binary form of its version
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(header_length(self.version) + self.payload.len() + CHECKSUM_LENGTH);
+        bytes.extend_from_slice(&ENVELOPE_MAGIC);
+        bytes.push(self.version);
+        bytes.push(scheme_id(self.scheme));
+        bytes.push(if self.encrypted { FLAG_ENCRYPTED } else { 0 });
+        bytes.extend_from_slice(&self.data_shards.to_be_bytes());
+        bytes.extend_from_slice(&self.parity_shards.to_be_bytes());
+        bytes.extend_from_slice(&self.index.to_be_bytes());
+        if self.version >= 2 {
+            bytes.extend_from_slice(&self.generation.to_be_bytes());
+        }
+        bytes.extend_from_slice(&self.wallet_id);
+        bytes.extend_from_slice(&(self.payload.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.payload);
+        let checksum = blake3::hash(&bytes);
+        bytes.extend_from_slice(&checksum.as_bytes()[..CHECKSUM_LENGTH]);
+        bytes
+    }
+
+    /// Parses and checks an envelope from its binary form
+    pub fn decode(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        if bytes.len() <= 4 || !Self::is_envelope(bytes) {
+            return Err(EnvelopeError::InvalidEncoding);
+        }
+        let version = bytes[4];
+        if version == 0 || version > ENVELOPE_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(version));
+        }
+        let header_length = header_length(version);
+        if bytes.len() < header_length + CHECKSUM_LENGTH {
+            return Err(EnvelopeError::InvalidEncoding);
+        }
+        let (content, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LENGTH);
+        if blake3::hash(content).as_bytes()[..CHECKSUM_LENGTH] != *checksum {
+            return Err(EnvelopeError::ChecksumMismatch);
+        }
+        let read_u16 = |at: usize| u16::from_be_bytes([content[at], content[at + 1]]);
+        let read_u32 = |at: usize| {
+            u32::from_be_bytes([
+                content[at],
+                content[at + 1],
+                content[at + 2],
+                content[at + 3],
+            ])
+        };
+        let (generation, mut at) = if version >= 2 {
+            (read_u32(PREFIX_LENGTH), PREFIX_LENGTH + GENERATION_LENGTH)
+        } else {
+            (0, PREFIX_LENGTH)
+        };
+        let mut wallet_id = [0u8; WALLET_ID_LENGTH];
+        wallet_id.copy_from_slice(&content[at..at + WALLET_ID_LENGTH]);
+        at += WALLET_ID_LENGTH;
+        // the sum can overflow a 32 bit usize on wasm32
+        let length = usize::try_from(read_u32(at))
+            .ok()
+            .and_then(|payload_length| header_length.checked_add(payload_length));
+        if length != Some(content.len()) {
+            return Err(EnvelopeError::InvalidEncoding);
+        }
+        Ok(Self {
+            version,
+            scheme: scheme_from_id(content[5])?,
+            encrypted: content[6] & FLAG_ENCRYPTED != 0,
+            data_shards: read_u16(7),
+            parity_shards: read_u16(9),
+            index: read_u16(11),
+            generation,
+            wallet_id,
+            payload: content[header_length..].to_vec(),
+        })
+    }
+
+    /// Checks that the envelope was produced for the given scheme, thresholds and position,
+    /// and for the given wallet and generation when they are already known.
+    pub fn check(
+        &self,
+        scheme: ShardScheme,
+        data_shards: usize,
+        parity_shards: usize,
+        index: usize,
+        wallet_id: Option<&[u8; WALLET_ID_LENGTH]>,
+        generation: Option<u32>,
+    ) -> Result<(), EnvelopeError> {
+        if self.scheme != scheme {
+            return Err(EnvelopeError::SchemeMismatch);
+        }
+        if self.data_shards as usize != data_shards || self.parity_shards as usize != parity_shards
+        {
+            return Err(EnvelopeError::ThresholdMismatch);
+        }
+        if self.index as usize != index {
+            return Err(EnvelopeError::IndexMismatch {
+                expected: index,
+                found: self.index as usize,
+            });
+        }
+        match wallet_id {
+            Some(wallet_id) if *wallet_id != self.wallet_id => {
+                return Err(EnvelopeError::WalletMismatch)
+            }
+            _ => {}
+        }
+        match generation {
+            Some(generation) if generation != self.generation => {
+                Err(EnvelopeError::GenerationMismatch {
+                    expected: generation,
+                    found: self.generation,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Hex encoded wallet id
+    pub fn wallet_id_hex(&self) -> String {
+        hex::encode(self.wallet_id)
+    }
+
+    /// The header of the envelope, without its payload
+    pub fn info(&self) -> ShardInfo {
+        ShardInfo {
+            version: self.version,
+            scheme: self.scheme,
+            data_shards: self.data_shards,
+            parity_shards: self.parity_shards,
+            index: self.index,
+            generation: self.generation,
+            wallet_id: self.wallet_id_hex(),
+            encrypted: self.encrypted,
+        }
+    }
+}
+
+/// The header of a shard envelope as returned by `inspectShard`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShardInfo {
+    pub version: u8,
+    pub scheme: ShardScheme,
+    pub data_shards: u16,
+    pub parity_shards: u16,
+    pub index: u16,
+    pub generation: u32,
+    pub wallet_id: String,
+    pub encrypted: bool,
+}
+
+/// Reads the header of an enveloped shard without touching its payload.
+///
+/// # Arguments
+///
+/// * `shard` - The enveloped shard
+///
+/// # Returns
+///
+/// An object with the `version`, `scheme`, `data_shards`, `parity_shards`, `index`, `generation`, `wallet_id` and `encrypted` fields.
+/// If the shard is not a valid envelope, an error is returned.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = inspectShard)]
+pub fn inspect_shard(shard: Vec<u8>) -> Result<JsValue, JsValue> {
+    let info = ShardEnvelope::decode(&shard)?.info();
+    Ok(to_value(&info)?)
+}
+
+/// Length of everything before the payload for an envelope version
+fn header_length(version: u8) -> usize {
+    let generation = if version >= 2 { GENERATION_LENGTH } else { 0 };
+    PREFIX_LENGTH + generation + WALLET_ID_LENGTH + 4
+}
+
+fn scheme_id(scheme: ShardScheme) -> u8 {
+    match scheme {
+        ShardScheme::ReedSolomon => 0,
+        ShardScheme::Shamir => 1,
+        ShardScheme::Feldman => 2,
+    }
+}
+
+fn scheme_from_id(id: u8) -> Result<ShardScheme, EnvelopeError> {
+    match id {
+        0 => Ok(ShardScheme::ReedSolomon),
+        1 => Ok(ShardScheme::Shamir),
+        2 => Ok(ShardScheme::Feldman),
+        _ => Err(EnvelopeError::UnknownScheme(id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope() -> ShardEnvelope {
+        ShardEnvelope {
+            version: ENVELOPE_VERSION,
+            scheme: ShardScheme::Shamir,
+            data_shards: 2,
+            parity_shards: 3,
+            index: 4,
+            generation: 7,
+            wallet_id: [9u8; WALLET_ID_LENGTH],
+            encrypted: true,
+            payload: vec![1, 2, 3, 4, 5],
+        }
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let bytes = envelope().encode();
+        assert!(ShardEnvelope::is_envelope(&bytes));
+        assert_eq!(ShardEnvelope::decode(&bytes), Ok(envelope()));
+
+        let legacy = ShardEnvelope {
+            version: 1,
+            generation: 0,
+            ..envelope()
+        };
+        let bytes = legacy.encode();
+        assert_eq!(bytes.len(), envelope().encode().len() - GENERATION_LENGTH);
+        assert_eq!(ShardEnvelope::decode(&bytes), Ok(legacy));
+    }
+
+    #[test]
+    fn test_envelope_rejects_corruption() {
+        let mut bytes = envelope().encode();
+        bytes[header_length(ENVELOPE_VERSION)] ^= 1;
+        assert_eq!(
+            ShardEnvelope::decode(&bytes),
+            Err(EnvelopeError::ChecksumMismatch)
+        );
+
+        let mut bytes = envelope().encode();
+        bytes[4] = ENVELOPE_VERSION + 1;
+        assert_eq!(
+            ShardEnvelope::decode(&bytes),
+            Err(EnvelopeError::UnsupportedVersion(ENVELOPE_VERSION + 1))
+        );
+
+        let bytes = envelope().encode();
+        assert_eq!(
+            ShardEnvelope::decode(&bytes[..bytes.len() - 1]),
+            Err(EnvelopeError::ChecksumMismatch)
+        );
+        // a payload length that does not fit the shard, with a valid checksum
+        let mut bytes = envelope().encode();
+        bytes.truncate(bytes.len() - CHECKSUM_LENGTH);
+        let at = header_length(ENVELOPE_VERSION) - 4;
+        bytes[at..at + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        let checksum = blake3::hash(&bytes);
+        bytes.extend_from_slice(&checksum.as_bytes()[..CHECKSUM_LENGTH]);
+        assert_eq!(
+            ShardEnvelope::decode(&bytes),
+            Err(EnvelopeError::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn test_envelope_check() {
+        let envelope = envelope();
+        let wallet_id = envelope.wallet_id;
+        assert_eq!(
+            envelope.check(ShardScheme::Shamir, 2, 3, 4, Some(&wallet_id), Some(7)),
+            Ok(())
+        );
+        assert_eq!(
+            envelope.check(ShardScheme::ReedSolomon, 2, 3, 4, None, None),
+            Err(EnvelopeError::SchemeMismatch)
+        );
+        assert_eq!(
+            envelope.check(ShardScheme::Shamir, 3, 2, 4, None, None),
+            Err(EnvelopeError::ThresholdMismatch)
+        );
+        assert_eq!(
+            envelope.check(ShardScheme::Shamir, 2, 3, 3, None, None),
+            Err(EnvelopeError::IndexMismatch {
+                expected: 3,
+                found: 4
+            })
+        );
+        assert_eq!(
+            envelope.check(ShardScheme::Shamir, 2, 3, 4, Some(&[0u8; 16]), None),
+            Err(EnvelopeError::WalletMismatch)
+        );
+        assert_eq!(
+            envelope.check(ShardScheme::Shamir, 2, 3, 4, None, Some(6)),
+            Err(EnvelopeError::GenerationMismatch {
+                expected: 6,
+                found: 7
+            })
+        );
+    }
+}
@@ -1,32 +1,44 @@
-#![allow(unused_imports, unused_variables)]
 use std::fmt;
 use std::fmt::Display;
 
-use crate::{to_value, wasm_bindgen, JsValue, ReedSolomon, Uint8Array};
+use crate::envelope::{EnvelopeError, ShardEnvelope};
+use crate::errors::EraError;
+use crate::layout::ShardScheme;
+use crate::ReedSolomon;
+#[cfg(feature = "wasm")]
+use crate::{envelope::ENVELOPE_VERSION, to_value, wasm_bindgen, JsValue, Uint8Array};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum ErasureError {
-    FragmentationError,
+    /// The data and parity shard counts are not supported by Reed-Solomon over GF(2^8)
+    InvalidShardCount,
+    /// The data does not split evenly into the data shards
+    MisalignedData,
+    /// Fewer shards than data shards were supplied
+    TooFewShards,
+    /// The shards are not a valid Reed-Solomon codeword
+    VerificationFailed,
 }
 
 impl std::error::Error for ErasureError {}
 
-impl Into<JsValue> for ErasureError {
-    fn into(self) -> JsValue {
-        JsValue::from_str(&self.to_string())
-    }
-}
-
 impl Display for ErasureError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ErasureError::FragmentationError => write!(f, "Fragmentation error"),
+            ErasureError::InvalidShardCount => write!(f, "Invalid data or parity shard count"),
+            ErasureError::MisalignedData => {
+                write!(f, "Data length is not a multiple of the data shards")
+            }
+            ErasureError::TooFewShards => write!(f, "Not enough shards to reconstruct the data"),
+            ErasureError::VerificationFailed => write!(f, "Shards failed verification"),
         }
     }
 }
 
 /// Encodes a byte array into fragments using Reed-Solomon erasure coding.
 /// The byte array must be a multiple of the data shards.
+/// Every fragment is wrapped in a shard envelope carrying its index and a random wallet id,
+/// see `ShardEnvelope`.
 ///
 /// # Arguments
 ///
@@ -36,30 +48,139 @@ impl Display for ErasureError {
 ///
 /// # Returns
 ///
-/// A Vec of byte arrays containing the encoded data.
+/// A Vec of byte arrays containing the enveloped shards.
+#[cfg(feature = "wasm")]
 #[wasm_bindgen(js_name = encodeShards)]
 pub fn encode_shards(
     data: Vec<u8>,
     data_shards: usize,
     parity_shards: usize,
 ) -> Result<JsValue, JsValue> {
-    if data.len() % data_shards != 0 {
-        panic!("fragmentation error")
+    let shards = encode_shards_internal(&data, data_shards, parity_shards)?;
+    let wallet_id = ShardEnvelope::new_wallet_id().map_err(EraError::from)?;
+    let envelopes: Vec<Vec<u8>> = shards
+        .into_iter()
+        .enumerate()
+        .map(|(index, payload)| {
+            ShardEnvelope {
+                version: ENVELOPE_VERSION,
+                scheme: ShardScheme::ReedSolomon,
+                data_shards: data_shards as u16,
+                parity_shards: parity_shards as u16,
+                index: index as u16,
+                generation: 0,
+                wallet_id,
+                encrypted: false,
+                payload,
+            }
+            .encode()
+        })
+        .collect();
+    Ok(to_value(&envelopes)?)
+}
+
+/// Splits `data` into `data_shards` equal pieces and appends `parity_shards`
+/// Reed-Solomon parity shards. The returned Vec is ordered data shards first.
+pub fn encode_shards_internal(
+    data: &[u8],
+    data_shards: usize,
+    parity_shards: usize,
+) -> Result<Vec<Vec<u8>>, ErasureError> {
+    let reed_solomon = new_reed_solomon(data_shards, parity_shards)?;
+    if !data.len().is_multiple_of(data_shards) {
+        return Err(ErasureError::MisalignedData);
     }
-    let reed_solomon = ReedSolomon::new(data_shards, parity_shards).unwrap();
     let size_per_shard = data.len() / data_shards;
-    let mut shards = Vec::new();
-    for i in 0..data_shards {
-        shards.push(data[(size_per_shard * i)..size_per_shard * (i + 1)].to_vec());
-    }
+    let mut shards: Vec<Vec<u8>> = data.chunks(size_per_shard).map(|x| x.to_vec()).collect();
     (0..parity_shards).for_each(|_| shards.push(vec![0; size_per_shard]));
     reed_solomon
         .encode(&mut shards)
-        .map_err(|_| <ErasureError as Into<JsValue>>::into(ErasureError::FragmentationError))?;
-    Ok(to_value(&shards)?)
+        .map_err(|_| ErasureError::MisalignedData)?;
+    Ok(shards)
 }
 
-/// Decodes a Vec of byte arrays using Reed-Solomon erasure coding.
+/// Fills in the missing shards of aligned shards in place and verifies the result.
+/// `None` marks a missing shard.
+pub fn reconstruct_shards_internal(
+    shards: &mut [Option<Vec<u8>>],
+    data_shards: usize,
+    parity_shards: usize,
+) -> Result<(), ErasureError> {
+    let reed_solomon = new_reed_solomon(data_shards, parity_shards)?;
+    reed_solomon.reconstruct(shards).map_err(|e| match e {
+        reed_solomon_erasure::Error::TooFewShardsPresent => ErasureError::TooFewShards,
+        reed_solomon_erasure::Error::TooFewShards | reed_solomon_erasure::Error::TooManyShards => {
+            ErasureError::InvalidShardCount
+        }
+        _ => ErasureError::VerificationFailed,
+    })?;
+    let shard_refs = shards
+        .iter()
+        .map(Option::as_deref)
+        .collect::<Option<Vec<&[u8]>>>()
+        .ok_or(ErasureError::TooFewShards)?;
+    match reed_solomon.verify(&shard_refs) {
+        Ok(true) => Ok(()),
+        _ => Err(ErasureError::VerificationFailed),
+    }
+}
+
+/// Unwraps aligned shards to their payloads, checking that enveloped shards belong to one
+/// wallet, generation and set of thresholds. Bare shards, as encoded before envelopes, are
+/// passed through as they are, but can not be mixed with enveloped ones.
+pub fn open_shards_internal(
+    shards: Vec<Option<Vec<u8>>>,
+    data_shards: usize,
+    parity_shards: usize,
+) -> Result<Vec<Option<Vec<u8>>>, EraError> {
+    let enveloped = shards
+        .iter()
+        .flatten()
+        .any(|shard| ShardEnvelope::is_envelope(shard));
+    let bare = shards
+        .iter()
+        .flatten()
+        .any(|shard| !ShardEnvelope::is_envelope(shard));
+    if !enveloped {
+        return Ok(shards);
+    }
+    if bare {
+        return Err(EnvelopeError::MixedFormats.into());
+    }
+    let mut wallet_id = None;
+    let mut generation = None;
+    let mut aligned = Vec::with_capacity(shards.len());
+    for (index, shard) in shards.into_iter().enumerate() {
+        let shard = match shard {
+            Some(shard) => shard,
+            None => {
+                aligned.push(None);
+                continue;
+            }
+        };
+        let envelope = ShardEnvelope::decode(&shard)?;
+        envelope.check(
+            ShardScheme::ReedSolomon,
+            data_shards,
+            parity_shards,
+            index,
+            wallet_id.as_ref(),
+            generation,
+        )?;
+        wallet_id = Some(envelope.wallet_id);
+        generation = Some(envelope.generation);
+        aligned.push(Some(envelope.payload));
+    }
+    Ok(aligned)
+}
+
+fn new_reed_solomon(data_shards: usize, parity_shards: usize) -> Result<ReedSolomon, ErasureError> {
+    ReedSolomon::new(data_shards, parity_shards).map_err(|_| ErasureError::InvalidShardCount)
+}
+
+/// Decodes a Vec of enveloped shards using Reed-Solomon erasure coding.
+/// Shards from another wallet, another scheme or other threshold parameters are rejected.
+/// Bare shards from before envelopes still decode, as long as none of the shards is enveloped.
 /// The alignment of shards in the reconstruction.
 /// e.g if shard_1, shard_2, shard_3 is missing.
 /// the array should be `[null,null,null,shard_4,shard_5]`
@@ -74,14 +195,14 @@ pub fn encode_shards(
 ///
 /// A Vec of byte arrays containing the decoded data.
 /// If the data could not be decoded, an error is returned.
+#[cfg(feature = "wasm")]
 #[wasm_bindgen(js_name = decodeShards)]
 pub fn reconstruct_shards(
     shards: Vec<JsValue>,
     data_shards: usize,
     parity_shards: usize,
 ) -> Result<JsValue, JsValue> {
-    let reed_solomon = ReedSolomon::new(data_shards, parity_shards).unwrap();
-    let mut shards: Vec<Option<Vec<u8>>> = shards
+    let shards = shards
         .into_iter()
         .map(|js_value| {
             if js_value.is_null() || js_value.is_undefined() {
@@ -91,19 +212,82 @@ pub fn reconstruct_shards(
             }
         })
         .collect();
-    reed_solomon
-        .reconstruct_data(&mut shards)
-        .map_err(|_| <ErasureError as Into<JsValue>>::into(ErasureError::FragmentationError))?;
-    let shard_refs: Vec<&[u8]> = shards.iter().map(|x| x.as_deref().unwrap()).collect();
-
-    reed_solomon
-        .verify(&shard_refs)
-        .map_err(|e| JsValue::from_str(&format!("Verification error: {:?}", e)))?;
+    let mut aligned = open_shards_internal(shards, data_shards, parity_shards)?;
+    reconstruct_shards_internal(&mut aligned, data_shards, parity_shards)?;
 
     // Combine only the data shards
-    let mut full_data = Vec::new();
-    for shard in shards.iter().take(data_shards) {
-        full_data.extend_from_slice(shard.as_ref().unwrap());
-    }
+    let full_data: Vec<u8> = aligned
+        .into_iter()
+        .take(data_shards)
+        .flatten()
+        .flatten()
+        .collect();
     Ok(Uint8Array::from(full_data.as_slice()).into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_and_reconstruct() {
+        let data = b"era wallet entropy".to_vec();
+        assert_eq!(
+            encode_shards_internal(&data, 4, 2),
+            Err(ErasureError::MisalignedData)
+        );
+        let shards = encode_shards_internal(&data, 3, 2).unwrap();
+        let mut aligned: Vec<Option<Vec<u8>>> = shards.iter().cloned().map(Some).collect();
+        // a missing parity shard is rebuilt too
+        aligned[1] = None;
+        aligned[4] = None;
+        reconstruct_shards_internal(&mut aligned, 3, 2).unwrap();
+        assert_eq!(aligned, shards.into_iter().map(Some).collect::<Vec<_>>());
+
+        aligned[0] = None;
+        aligned[1] = None;
+        aligned[2] = None;
+        assert_eq!(
+            reconstruct_shards_internal(&mut aligned, 3, 2),
+            Err(ErasureError::TooFewShards)
+        );
+    }
+
+    #[test]
+    fn test_open_bare_and_enveloped_shards() {
+        let data = b"era wallet entropy".to_vec();
+        let shards = encode_shards_internal(&data, 3, 2).unwrap();
+        let envelopes: Vec<Vec<u8>> = shards
+            .iter()
+            .enumerate()
+            .map(|(index, payload)| {
+                ShardEnvelope {
+                    version: crate::envelope::ENVELOPE_VERSION,
+                    scheme: ShardScheme::ReedSolomon,
+                    data_shards: 3,
+                    parity_shards: 2,
+                    index: index as u16,
+                    generation: 0,
+                    wallet_id: [7; 16],
+                    encrypted: false,
+                    payload: payload.clone(),
+                }
+                .encode()
+            })
+            .collect();
+
+        // bare shards of existing callers pass through
+        let mut bare: Vec<Option<Vec<u8>>> = shards.iter().cloned().map(Some).collect();
+        bare[0] = None;
+        assert_eq!(open_shards_internal(bare.clone(), 3, 2).unwrap(), bare);
+
+        let mut enveloped: Vec<Option<Vec<u8>>> = envelopes.iter().cloned().map(Some).collect();
+        enveloped[0] = None;
+        assert_eq!(open_shards_internal(enveloped.clone(), 3, 2).unwrap(), bare);
+
+        let mut mixed = enveloped;
+        mixed[1] = bare[1].clone();
+        let error = open_shards_internal(mixed, 3, 2).unwrap_err();
+        assert_eq!(error.code(), crate::errors::ErrorCode::ShardMismatch);
+    }
+}
@@ -0,0 +1,278 @@
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::Scalar;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::shamir::ShamirError;
+
+/// Bytes of the secret shared by one polynomial, small enough to always be a canonical scalar
+pub const CHUNK_LENGTH: usize = 31;
+/// Length of an encoded scalar or compressed ristretto255 point
+const ELEMENT_LENGTH: usize = 32;
+
+/// The shares of a secret and the Feldman commitments to the polynomials they lie on
+pub struct FeldmanShares {
+    /// Share `i` is the evaluation of every polynomial at `x = i + 1`, as 32 byte scalars
+    pub shares: Vec<Vec<u8>>,
+    /// `a_j·G` for every coefficient `a_j` of every polynomial, one list per chunk of the secret
+    pub commitments: Vec<Vec<CompressedRistretto>>,
+}
+
+/// A share x coordinate and the scalars of the share, one per chunk of the secret
+type Point = (Scalar, Zeroizing<Vec<Scalar>>);
+
+fn random_scalar() -> Result<Scalar, ShamirError> {
+    let mut bytes = Zeroizing::new([0u8; 64]);
+    getrandom::getrandom(&mut *bytes).map_err(|_| ShamirError::RandomnessError)?;
+    Ok(Scalar::from_bytes_mod_order_wide(&bytes))
+}
+
+/// Reads a share as one scalar per chunk of the secret
+fn decode_share(share: &[u8]) -> Result<Zeroizing<Vec<Scalar>>, ShamirError> {
+    if share.is_empty() || !share.len().is_multiple_of(ELEMENT_LENGTH) {
+        return Err(ShamirError::InconsistentShares);
+    }
+    let mut scalars = Zeroizing::new(Vec::with_capacity(share.len() / ELEMENT_LENGTH));
+    for chunk in share.chunks(ELEMENT_LENGTH) {
+        let mut bytes = Zeroizing::new([0u8; ELEMENT_LENGTH]);
+        bytes.copy_from_slice(chunk);
+        let scalar = Option::<Scalar>::from(Scalar::from_canonical_bytes(*bytes))
+            .ok_or(ShamirError::InconsistentShares)?;
+        scalars.push(scalar);
+    }
+    Ok(scalars)
+}
+
+fn encode_share(scalars: &[Scalar]) -> Vec<u8> {
+    let mut share = Vec::with_capacity(scalars.len() * ELEMENT_LENGTH);
+    for scalar in scalars {
+        share.extend_from_slice(scalar.as_bytes());
+    }
+    share
+}
+
+/// Evaluates the polynomials through `points` at `x` using Lagrange interpolation
+fn interpolate(points: &[Point], x: Scalar) -> Zeroizing<Vec<Scalar>> {
+    let mut result = Zeroizing::new(vec![Scalar::ZERO; points[0].1.len()]);
+    for (i, (xi, yi)) in points.iter().enumerate() {
+        let mut basis = Scalar::ONE;
+        for (j, (xj, _)) in points.iter().enumerate() {
+            if i != j {
+                basis *= (x - xj) * (xi - xj).invert();
+            }
+        }
+        for (value, y) in result.iter_mut().zip(yi.iter()) {
+            *value += basis * y;
+        }
+    }
+    result
+}
+
+/// Splits a secret into `shares` shares over the ristretto255 scalar field, any `threshold`
+/// of which recover it. The secret is cut in chunks of `CHUNK_LENGTH` bytes, the last one
+/// zero padded, and every chunk is the constant term of its own random polynomial of degree
+/// `threshold - 1`. Share `i` is the evaluation of every polynomial at `x = i + 1`.
+pub fn split_secret_internal(
+    secret: &[u8],
+    threshold: usize,
+    shares: usize,
+) -> Result<FeldmanShares, ShamirError> {
+    if threshold == 0 || threshold > shares || shares > 255 {
+        return Err(ShamirError::InvalidThreshold);
+    }
+    let mut polynomials = Vec::new();
+    for chunk in secret.chunks(CHUNK_LENGTH) {
+        let mut bytes = Zeroizing::new([0u8; ELEMENT_LENGTH]);
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        let mut coefficients = Zeroizing::new(vec![Scalar::from_bytes_mod_order(*bytes)]);
+        for _ in 1..threshold {
+            coefficients.push(random_scalar()?);
+        }
+        polynomials.push(coefficients);
+    }
+    let commitments = polynomials
+        .iter()
+        .map(|coefficients| {
+            coefficients
+                .iter()
+                .map(|a| RistrettoPoint::mul_base(a).compress())
+                .collect()
+        })
+        .collect();
+    let shares = (1..=shares as u64)
+        .map(|x| {
+            let x = Scalar::from(x);
+            let values: Zeroizing<Vec<Scalar>> = Zeroizing::new(
+                polynomials
+                    .iter()
+                    .map(|coefficients| {
+                        // horner evaluation from the highest coefficient down to the secret
+                        coefficients
+                            .iter()
+                            .rev()
+                            .fold(Scalar::ZERO, |acc, a| acc * x + a)
+                    })
+                    .collect(),
+            );
+            encode_share(&values)
+        })
+        .collect();
+    Ok(FeldmanShares {
+        shares,
+        commitments,
+    })
+}
+
+/// Checks that the share at `index` lies on the committed polynomials:
+/// `s·G = Σ x^j·C_j` for the scalar `s` of every chunk, with `x = index + 1`.
+pub fn verify_share(share: &[u8], index: usize, commitments: &[Vec<RistrettoPoint>]) -> bool {
+    let scalars = match decode_share(share) {
+        Ok(scalars) => scalars,
+        Err(_) => return false,
+    };
+    if scalars.len() != commitments.len() {
+        return false;
+    }
+    let x = Scalar::from(index as u64 + 1);
+    scalars
+        .iter()
+        .zip(commitments.iter())
+        .all(|(scalar, coefficients)| {
+            let mut power = Scalar::ONE;
+            let mut expected = RistrettoPoint::default();
+            for commitment in coefficients {
+                expected += commitment * power;
+                power *= x;
+            }
+            RistrettoPoint::mul_base(scalar) == expected
+        })
+}
+
+/// Collects the points of aligned shares and checks every share beyond the threshold
+/// against the polynomials through the first `threshold` ones, which are returned.
+fn checked_points(shares: &[Option<Vec<u8>>], threshold: usize) -> Result<Vec<Point>, ShamirError> {
+    if threshold == 0 || shares.len() > 255 {
+        return Err(ShamirError::InvalidThreshold);
+    }
+    let mut points = Vec::new();
+    for (i, share) in shares.iter().enumerate() {
+        if let Some(share) = share {
+            points.push((Scalar::from(i as u64 + 1), decode_share(share)?));
+        }
+    }
+    if points.len() < threshold {
+        return Err(ShamirError::InsufficientShares);
+    }
+    if points.iter().any(|(_, y)| y.len() != points[0].1.len()) {
+        return Err(ShamirError::InconsistentShares);
+    }
+    let extra = points.split_off(threshold);
+    for (x, y) in &extra {
+        if interpolate(&points, *x) != *y {
+            return Err(ShamirError::InconsistentShares);
+        }
+    }
+    Ok(points)
+}
+
+/// Recovers a secret from aligned shares, `None` marks a missing share.
+/// Every share beyond the threshold is checked against the recovered polynomials.
+/// The secret keeps the zero padding of its last chunk, `CHUNK_LENGTH` bytes per scalar.
+pub fn combine_shares_internal(
+    shares: &[Option<Vec<u8>>],
+    threshold: usize,
+) -> Result<Zeroizing<Vec<u8>>, ShamirError> {
+    let points = checked_points(shares, threshold)?;
+    let chunks = interpolate(&points, Scalar::ZERO);
+    let mut secret = Zeroizing::new(Vec::with_capacity(chunks.len() * CHUNK_LENGTH));
+    for chunk in chunks.iter() {
+        let bytes = chunk.as_bytes();
+        // a chunk never reaches the last byte, the shares were not split from a secret
+        if bytes[CHUNK_LENGTH] != 0 {
+            return Err(ShamirError::InconsistentShares);
+        }
+        secret.extend_from_slice(&bytes[..CHUNK_LENGTH]);
+    }
+    Ok(secret)
+}
+
+/// Recomputes every share of aligned shares, `None` marks a missing share.
+/// The returned shares lie on the same polynomials, so they match the same commitments.
+pub fn recover_shares_internal(
+    shares: &[Option<Vec<u8>>],
+    threshold: usize,
+) -> Result<Vec<Vec<u8>>, ShamirError> {
+    let points = checked_points(shares, threshold)?;
+    Ok(shares
+        .iter()
+        .enumerate()
+        .map(|(i, share)| match share {
+            Some(share) => share.clone(),
+            None => encode_share(&interpolate(&points, Scalar::from(i as u64 + 1))),
+        })
+        .collect())
+}
+
+impl Drop for FeldmanShares {
+    fn drop(&mut self) {
+        self.shares.iter_mut().for_each(Zeroize::zeroize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decompress(commitments: &[Vec<CompressedRistretto>]) -> Vec<Vec<RistrettoPoint>> {
+        commitments
+            .iter()
+            .map(|chunk| chunk.iter().map(|c| c.decompress().unwrap()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_verify_and_combine() {
+        // 32 bytes of entropy span two chunks
+        let secret: Vec<u8> = (1..=32).collect();
+        let split = split_secret_internal(&secret, 3, 5).unwrap();
+        let commitments = decompress(&split.commitments);
+        assert_eq!(commitments.len(), 2);
+        assert!(commitments.iter().all(|chunk| chunk.len() == 3));
+        for (index, share) in split.shares.iter().enumerate() {
+            assert!(verify_share(share, index, &commitments));
+        }
+        // a share is only valid at its own position
+        assert!(!verify_share(&split.shares[0], 1, &commitments));
+
+        let mut aligned: Vec<Option<Vec<u8>>> = split.shares.iter().cloned().map(Some).collect();
+        aligned[0] = None;
+        aligned[3] = None;
+        let combined = combine_shares_internal(&aligned, 3).unwrap();
+        assert_eq!(&combined[..32], secret.as_slice());
+        assert!(combined[32..].iter().all(|byte| *byte == 0));
+
+        let recovered = recover_shares_internal(&aligned, 3).unwrap();
+        assert_eq!(recovered, split.shares);
+
+        aligned[1] = None;
+        assert_eq!(
+            combine_shares_internal(&aligned, 3).err(),
+            Some(ShamirError::InsufficientShares)
+        );
+    }
+
+    #[test]
+    fn test_rejects_share_of_another_polynomial() {
+        let secret = vec![7u8; 16];
+        let split = split_secret_internal(&secret, 2, 3).unwrap();
+        let commitments = decompress(&split.commitments);
+
+        // a dealer handing out a share of another sharing of the same secret
+        let other = split_secret_internal(&secret, 2, 3).unwrap();
+        assert!(!verify_share(&other.shares[2], 2, &commitments));
+
+        let mut tampered = split.shares[2].clone();
+        tampered[0] ^= 1;
+        assert!(!verify_share(&tampered, 2, &commitments));
+        assert!(!verify_share(&tampered[..31], 2, &commitments));
+    }
+}
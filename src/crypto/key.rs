@@ -1,18 +1,20 @@
 use crate::crypto::crypto::Ecdsa;
-use crate::to_value;
-use crate::Uint8Array;
-use crate::{encode, wasm_bindgen, JsValue};
+use crate::encode;
+use crate::errors::{EraError, ErrorCode};
+#[cfg(feature = "wasm")]
+use crate::{to_value, wasm_bindgen, JsValue, Uint8Array};
 use crate::{Language, Mnemonic, MnemonicType, Seed};
 use bip32::Prefix;
+use zeroize::{Zeroize, Zeroizing};
 
 /// Represents a BIP-32 account. This object contains a seed and a it's mnemonic.
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct Account {
-    seed: Vec<u8>,
-    mnemonic: String,
+    seed: Zeroizing<Vec<u8>>,
+    mnemonic: Zeroizing<String>,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 /// Implementation of the `Account` struct, providing methods for account creation,
 /// serialization, and key derivation using BIP-39 and BIP-32 standards.
 ///
@@ -27,10 +29,10 @@ pub struct Account {
 /// - `as_mnemonic(&self) -> String`  
 ///   Returns the mnemonic phrase as a string.
 ///
-/// - `as_hex(&self) -> Result<JsValue, JsValue>`  
+/// - `as_hex(&self) -> String`  
 ///   Returns the seed as a hex string prefixed with `0x`.
 ///
-/// - `from_str(str: &str) -> Self`  
+/// - `from_str(str: &str) -> Result<Account, JsValue>`  
 ///   Creates an `Account` from an existing mnemonic phrase.
 ///
 /// - `to_bytes(&self) -> Vec<u8>`  
@@ -42,16 +44,21 @@ pub struct Account {
 /// - `to_mnemonic(&self) -> String`  
 ///   Returns the mnemonic phrase as a string.
 ///
-/// - `derive_root_key(&self) -> String`  
+/// - `derive_root_key(&self) -> Result<String, EraError>`  
 ///   Derives the root private key from the seed and returns it as a hex string prefixed with `0x`.
 ///
-/// - `derive_root_public_key(&self) -> String`  
+/// - `derive_root_public_key(&self) -> Result<String, EraError>`  
 ///   Derives the root public key from the seed and returns it as a string in XPUB format.
 ///
-/// - `derive_extended_key(&self, path: &str) -> JsValue`  
+/// - `derive_extended_key(&self, path: &str) -> Result<JsValue, JsValue>`  
 ///   Derives an extended key from the seed using the provided BIP-32 derivation path and returns it as a JavaScript value.
 ///
-/// All methods are exposed to JavaScript via `wasm_bindgen` for WebAssembly interoperability.
+/// - `wipe(&mut self)`  
+///   Zeroes the seed and the mnemonic of the account.
+///
+/// All methods are exposed to JavaScript via `wasm_bindgen` when the `wasm` feature is enabled.
+/// `as_bytes` and `derive_extended_key` are only compiled for javascript, Rust callers use
+/// `to_bytes` and `Ecdsa::generate_extended_key`.
 impl Account {
     /// Creates a new Account instance
     ///
@@ -63,21 +70,21 @@ impl Account {
     /// # Returns
     ///
     /// A new Account instance
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(length: KeyLength, lang: KeyLanguage) -> Self {
         let mnemonic = Mnemonic::new(MnemonicType::from(length), Language::from(lang));
         let seed = Seed::new(&mnemonic, "");
-        let value = Account {
-            seed: seed.as_bytes().to_vec(),
-            mnemonic: mnemonic.phrase().to_string(),
-        };
-        value
+        Account {
+            seed: Zeroizing::new(seed.as_bytes().to_vec()),
+            mnemonic: Zeroizing::new(mnemonic.phrase().to_string()),
+        }
     }
     /// Converts the account to a byte array
     ///  
     /// # Returns
     ///
     /// The seed as a byte array
+    #[cfg(feature = "wasm")]
     #[wasm_bindgen]
     pub fn as_bytes(&self) -> Uint8Array {
         Uint8Array::from(self.seed.as_slice())
@@ -88,9 +95,9 @@ impl Account {
     /// # Returns
     ///
     /// The mnemonic as a string
-    #[wasm_bindgen]
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn as_mnemonic(&self) -> String {
-        self.mnemonic.clone()
+        self.mnemonic.to_string()
     }
 
     /// Converts the account seed to a hex string
@@ -98,22 +105,16 @@ impl Account {
     /// # Returns
     ///
     /// The seed as a hex string. The seed is prefixed with `0x`
-    #[wasm_bindgen]
-    pub fn as_hex(&self) -> Result<JsValue, JsValue> {
-        let seed = format!("0x{}", encode(&self.seed));
-        Ok(to_value(&seed)?)
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn as_hex(&self) -> String {
+        format!("0x{}", encode(&*self.seed))
     }
 
-    #[wasm_bindgen]
-    pub fn from_str(str: &str) -> Self {
-        let seed = Seed::new(
-            &Mnemonic::from_phrase(str, bip39::Language::English).unwrap(),
-            "",
-        );
-        Account {
-            seed: seed.as_bytes().to_vec(),
-            mnemonic: str.to_string(),
-        }
+    /// Creates an account from an English mnemonic phrase
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = from_str)]
+    pub fn from_phrase(phrase: &str) -> Result<Account, JsValue> {
+        Ok(phrase.parse::<Account>()?)
     }
 
     /// Converts the account to a byte array
@@ -121,9 +122,9 @@ impl Account {
     /// # Returns
     ///
     /// The seed as a byte array
-    #[wasm_bindgen]
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn to_bytes(&self) -> Vec<u8> {
-        self.seed.clone()
+        self.seed.to_vec()
     }
 
     /// Converts the account to its coresponding mnemonic
@@ -131,9 +132,9 @@ impl Account {
     /// # Returns
     ///
     /// The mnemonic as a string
-    #[wasm_bindgen]
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn to_str(&self) -> String {
-        self.mnemonic.clone()
+        self.mnemonic.to_string()
     }
 
     /// Converts the account to its coresponding mnemonic
@@ -141,9 +142,9 @@ impl Account {
     /// # Returns
     ///
     /// The mnemonic as a string
-    #[wasm_bindgen]
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn to_mnemonic(&self) -> String {
-        self.mnemonic.clone()
+        self.mnemonic.to_string()
     }
 
     /// Derives the root private key from the seed. This is the `m` path in the BIP-32 derivation path
@@ -151,10 +152,10 @@ impl Account {
     /// # Returns
     ///
     /// The root private key as a hex string. The private key is prefixed with `0x`
-    #[wasm_bindgen]
-    pub fn derive_root_key(&self) -> String {
-        let xpriv = self.generate_root_key();
-        format!("0x{}", encode(xpriv.to_bytes()))
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn derive_root_key(&self) -> Result<String, EraError> {
+        let xpriv = self.generate_root_key()?;
+        Ok(format!("0x{}", encode(xpriv.to_bytes())))
     }
 
     /// Derives the root public key from the seed.
@@ -162,10 +163,10 @@ impl Account {
     /// # Returns
     ///
     /// The root public key as a hex string.
-    #[wasm_bindgen]
-    pub fn derive_root_public_key(&self) -> String {
-        let xpub = self.generate_root_public_key();
-        xpub.to_string(Prefix::XPUB)
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn derive_root_public_key(&self) -> Result<String, EraError> {
+        let xpub = self.generate_root_public_key()?;
+        Ok(xpub.to_string(Prefix::XPUB))
     }
 
     /// Derives an extended key from the seed when given a path.
@@ -176,10 +177,41 @@ impl Account {
     /// # Returns
     ///
     /// The root public key as a hex string.
+    #[cfg(feature = "wasm")]
     #[wasm_bindgen]
-    pub fn derive_extended_key(&self, path: &str) -> JsValue {
-        let key_object = self.generate_extended_key(path);
-        to_value(&key_object).unwrap()
+    pub fn derive_extended_key(&self, path: &str) -> Result<JsValue, JsValue> {
+        let key_object = self.generate_extended_key(path)?;
+        Ok(to_value(&key_object)?)
+    }
+
+    /// Zeroes the seed and the mnemonic of the account.
+    /// The account is also wiped when it is freed.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn wipe(&mut self) {
+        self.seed.zeroize();
+        self.mnemonic.zeroize();
+    }
+}
+
+impl std::str::FromStr for Account {
+    type Err = EraError;
+
+    /// Creates an account from an English mnemonic phrase
+    fn from_str(phrase: &str) -> Result<Self, Self::Err> {
+        let mnemonic = Mnemonic::from_phrase(phrase, bip39::Language::English)
+            .map_err(|e| EraError::new(ErrorCode::InvalidMnemonic, e.to_string()))?;
+        let seed = Seed::new(&mnemonic, "");
+        Ok(Account {
+            seed: Zeroizing::new(seed.as_bytes().to_vec()),
+            mnemonic: Zeroizing::new(mnemonic.phrase().to_string()),
+        })
+    }
+}
+
+impl Account {
+    /// Borrows the seed of the account
+    pub fn seed(&self) -> &[u8] {
+        &self.seed
     }
 }
 
@@ -191,19 +223,14 @@ impl Account {
 /// # Returns
 ///
 /// The Account Object.
+#[cfg(feature = "wasm")]
 #[wasm_bindgen(js_name = accountFromMnemonic)]
-pub fn from_mnemonic(mnemonic: &str) -> Result<Account, JsValue> {
-    let mnemonic = Mnemonic::from_phrase(mnemonic, bip39::Language::English)
-        .map_err(|e| JsValue::from_str(&e.to_string()))?;
-    let seed = Seed::new(&mnemonic, "");
-    Ok(Account {
-        seed: seed.as_bytes().to_vec(),
-        mnemonic: mnemonic.to_string(),
-    })
+pub fn from_mnemonic(mnemonic: &str) -> Result<Account, EraError> {
+    mnemonic.parse()
 }
 
 /// The keyLength enum represents the length of the mnemonic. It can be 12, 15, 18, 21 or 24 words.
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub enum KeyLength {
     Words12,
     Words15,
@@ -225,7 +252,7 @@ impl From<KeyLength> for MnemonicType {
 }
 
 /// The keyLanguage enum represents the language of the mnemonic. It can be English, Japanese, Spanish, ChineseSimplified, ChineseTraditional, French, Italian or Korean.
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub enum KeyLanguage {
     English,
     Japanese,
@@ -1,3 +1,9 @@
+pub mod commitment;
+#[allow(clippy::module_inception)]
 pub mod crypto;
+pub mod envelope;
 pub mod erasure_coding;
+pub mod feldman;
 pub mod key;
+pub mod oprf;
+pub mod shamir;
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::Scalar;
+use sha2::{Digest, Sha512};
+use zeroize::{Zeroize, Zeroizing};
+
+#[cfg(feature = "wasm")]
+use crate::{wasm_bindgen, JsValue};
+
+/// Domain separation context of the pin OPRF
+const OPRF_CONTEXT: &[u8] = b"era-js 2025 pin oprf v1";
+/// Length of an encoded group element
+const ELEMENT_LENGTH: usize = 32;
+/// Failed unlocks allowed by default before a key is locked
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+#[derive(Debug, PartialEq)]
+pub enum OprfError {
+    /// A blinded or evaluated element is not a valid ristretto255 point
+    InvalidElement,
+    /// The server holds no OPRF key for this key id
+    UnknownKey(String),
+    /// The server already holds an OPRF key for this key id
+    KeyExists(String),
+    /// Too many unlock attempts were made for this key id
+    Locked(String),
+    /// The platform random number generator failed
+    RandomnessError,
+}
+
+impl std::error::Error for OprfError {}
+
+impl Display for OprfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OprfError::InvalidElement => write!(f, "Invalid OPRF element"),
+            OprfError::UnknownKey(id) => write!(f, "No OPRF key for '{}'", id),
+            OprfError::KeyExists(id) => write!(f, "An OPRF key for '{}' already exists", id),
+            OprfError::Locked(id) => write!(f, "Too many unlock attempts for '{}'", id),
+            OprfError::RandomnessError => write!(f, "Could not generate randomness"),
+        }
+    }
+}
+
+fn random_scalar() -> Result<Scalar, OprfError> {
+    let mut bytes = Zeroizing::new([0u8; 64]);
+    getrandom::getrandom(&mut *bytes).map_err(|_| OprfError::RandomnessError)?;
+    Ok(Scalar::from_bytes_mod_order_wide(&bytes))
+}
+
+/// Hashes a length prefixed list of inputs under the OPRF context
+fn hash(label: &[u8], inputs: &[&[u8]]) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.update(OPRF_CONTEXT);
+    hasher.update(label);
+    for input in inputs {
+        hasher.update((input.len() as u64).to_be_bytes());
+        hasher.update(input);
+    }
+    hasher.finalize().into()
+}
+
+fn hash_to_group(key_id: &str, pin: &[u8]) -> RistrettoPoint {
+    RistrettoPoint::from_uniform_bytes(&hash(b"hash to group", &[key_id.as_bytes(), pin]))
+}
+
+fn decode_element(bytes: &[u8]) -> Result<RistrettoPoint, OprfError> {
+    if bytes.len() != ELEMENT_LENGTH {
+        return Err(OprfError::InvalidElement);
+    }
+    let point = CompressedRistretto::from_slice(bytes)
+        .map_err(|_| OprfError::InvalidElement)?
+        .decompress()
+        .ok_or(OprfError::InvalidElement)?;
+    if point == RistrettoPoint::default() {
+        return Err(OprfError::InvalidElement);
+    }
+    Ok(point)
+}
+
+/// Client side of the pin unlock protocol, a 2HashDH OPRF over ristretto255.
+///
+/// The pin is blinded before it leaves the client, so the server learns nothing about it,
+/// and the unlock key depends on a key only the server holds. Every pin guess therefore needs
+/// an evaluation by the server, which rate limits them, instead of being checked offline
+/// against a stolen project shard.
+///
+/// The unlock is a two step state machine: `new` blinds the pin and `finish` consumes the
+/// state with the server response. A blinding factor is never reused, and the pin and blinding
+/// factor are zeroed when the state is dropped.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PinUnlock {
+    key_id: String,
+    pin: Zeroizing<Vec<u8>>,
+    blind: Scalar,
+    request: Vec<u8>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PinUnlock {
+    /// Blinds a pin for the server OPRF key registered under `key_id`
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(key_id: String, pin: String) -> Result<PinUnlock, JsValue> {
+        Ok(Self::start(&key_id, pin.as_bytes())?)
+    }
+
+    /// The blinded pin to send to the server
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn request(&self) -> Vec<u8> {
+        self.request.clone()
+    }
+
+    /// Unblinds the server response.
+    ///
+    /// # Returns
+    ///
+    /// The hex encoded unlock key, used in place of the pin to encrypt and decrypt the project shard.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn finish(self, response: Vec<u8>) -> Result<String, JsValue> {
+        Ok(hex::encode(self.finish_internal(&response)?.as_slice()))
+    }
+}
+
+impl PinUnlock {
+    pub fn start(key_id: &str, pin: &[u8]) -> Result<Self, OprfError> {
+        let blind = random_scalar()?;
+        let request = (hash_to_group(key_id, pin) * blind)
+            .compress()
+            .to_bytes()
+            .to_vec();
+        Ok(Self {
+            key_id: key_id.into(),
+            pin: Zeroizing::new(pin.to_vec()),
+            blind,
+            request,
+        })
+    }
+
+    pub fn finish_internal(self, response: &[u8]) -> Result<Zeroizing<[u8; 32]>, OprfError> {
+        let evaluated = decode_element(response)?;
+        let unblinded = evaluated * self.blind.invert();
+        let output = Zeroizing::new(hash(
+            b"finalize",
+            &[
+                self.key_id.as_bytes(),
+                &self.pin,
+                unblinded.compress().as_bytes(),
+            ],
+        ));
+        let mut key = Zeroizing::new([0u8; 32]);
+        key.copy_from_slice(&output[..32]);
+        Ok(key)
+    }
+}
+
+impl Drop for PinUnlock {
+    fn drop(&mut self) {
+        self.blind.zeroize();
+    }
+}
+
+struct OprfKey {
+    key: Scalar,
+    attempts: u32,
+}
+
+/// Reference server side of the pin unlock protocol, holding one OPRF key per key id.
+///
+/// The server can not tell a right pin from a wrong one, so every evaluation counts as an attempt
+/// and a key locks after `max_attempts` evaluations. Call `reset` once the client proved it unlocked
+/// the wallet, e.g. with a signature from the rebuilt key. Keys only live in memory, this is meant
+/// for tests and local development.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct OprfServer {
+    keys: HashMap<String, OprfKey>,
+    max_attempts: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OprfServer {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(max_attempts: Option<u32>) -> OprfServer {
+        Self {
+            keys: HashMap::new(),
+            max_attempts: max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS),
+        }
+    }
+
+    /// Generates the OPRF key of a new key id, e.g. when a wallet is created
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn register(&mut self, key_id: String) -> Result<(), JsValue> {
+        Ok(self.register_internal(&key_id)?)
+    }
+
+    /// Evaluates a blinded pin and counts the attempt
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn evaluate(&mut self, key_id: String, request: Vec<u8>) -> Result<Vec<u8>, JsValue> {
+        Ok(self.evaluate_internal(&key_id, &request)?)
+    }
+
+    /// Clears the attempts of a key id after a successful unlock
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn reset(&mut self, key_id: String) -> Result<(), JsValue> {
+        Ok(self.reset_internal(&key_id)?)
+    }
+}
+
+impl OprfServer {
+    pub fn register_internal(&mut self, key_id: &str) -> Result<(), OprfError> {
+        if self.keys.contains_key(key_id) {
+            return Err(OprfError::KeyExists(key_id.into()));
+        }
+        let key = random_scalar()?;
+        self.keys
+            .insert(key_id.into(), OprfKey { key, attempts: 0 });
+        Ok(())
+    }
+
+    pub fn evaluate_internal(
+        &mut self,
+        key_id: &str,
+        request: &[u8],
+    ) -> Result<Vec<u8>, OprfError> {
+        let entry = self
+            .keys
+            .get_mut(key_id)
+            .ok_or_else(|| OprfError::UnknownKey(key_id.into()))?;
+        if entry.attempts >= self.max_attempts {
+            return Err(OprfError::Locked(key_id.into()));
+        }
+        let blinded = decode_element(request)?;
+        entry.attempts += 1;
+        Ok((blinded * entry.key).compress().to_bytes().to_vec())
+    }
+
+    pub fn reset_internal(&mut self, key_id: &str) -> Result<(), OprfError> {
+        let entry = self
+            .keys
+            .get_mut(key_id)
+            .ok_or_else(|| OprfError::UnknownKey(key_id.into()))?;
+        entry.attempts = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unlock(
+        server: &mut OprfServer,
+        key_id: &str,
+        pin: &str,
+    ) -> Result<Zeroizing<[u8; 32]>, OprfError> {
+        let client = PinUnlock::start(key_id, pin.as_bytes())?;
+        let response = server.evaluate_internal(key_id, &client.request)?;
+        client.finish_internal(&response)
+    }
+
+    #[test]
+    fn test_unlock_is_deterministic() {
+        let mut server = OprfServer::new(None);
+        server.register_internal("alice").unwrap();
+        server.register_internal("bob").unwrap();
+
+        let key = unlock(&mut server, "alice", "222333").unwrap();
+        // fresh blinding factors, same key
+        assert_eq!(unlock(&mut server, "alice", "222333").unwrap(), key);
+        assert_ne!(unlock(&mut server, "alice", "222334").unwrap(), key);
+        assert_ne!(unlock(&mut server, "bob", "222333").unwrap(), key);
+
+        // the blinded pin differs on every unlock
+        let first = PinUnlock::start("alice", b"222333").unwrap();
+        let second = PinUnlock::start("alice", b"222333").unwrap();
+        assert_ne!(first.request, second.request);
+    }
+
+    #[test]
+    fn test_server_rate_limits() {
+        let mut server = OprfServer::new(Some(3));
+        server.register_internal("alice").unwrap();
+        for pin in &["000000", "000001", "000002"] {
+            unlock(&mut server, "alice", pin).unwrap();
+        }
+        assert_eq!(
+            unlock(&mut server, "alice", "222333"),
+            Err(OprfError::Locked("alice".into()))
+        );
+        server.reset_internal("alice").unwrap();
+        assert!(unlock(&mut server, "alice", "222333").is_ok());
+        assert_eq!(
+            server.register_internal("alice"),
+            Err(OprfError::KeyExists("alice".into()))
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_elements() {
+        let mut server = OprfServer::new(None);
+        server.register_internal("alice").unwrap();
+        assert_eq!(
+            server.evaluate_internal("alice", &[0u8; ELEMENT_LENGTH]),
+            Err(OprfError::InvalidElement)
+        );
+        assert_eq!(
+            server.evaluate_internal("alice", &[0xffu8; ELEMENT_LENGTH]),
+            Err(OprfError::InvalidElement)
+        );
+        // rejected elements do not count as attempts
+        assert_eq!(server.keys["alice"].attempts, 0);
+
+        let client = PinUnlock::start("alice", b"222333").unwrap();
+        assert_eq!(
+            client.finish_internal(&[0u8; 31]),
+            Err(OprfError::InvalidElement)
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use std::fmt;
+use std::fmt::Display;
+
+#[cfg(feature = "wasm")]
+use crate::{to_value, wasm_bindgen, JsValue, Uint8Array};
+
+#[derive(Debug, PartialEq)]
+pub enum ShamirError {
+    /// The threshold is zero, above the share count, or there are more than 255 shares
+    InvalidThreshold,
+    /// Fewer shares than the threshold were supplied
+    InsufficientShares,
+    /// The supplied shares differ in length or do not lie on the same polynomial
+    InconsistentShares,
+    /// The platform random number generator failed
+    RandomnessError,
+}
+
+impl std::error::Error for ShamirError {}
+
+impl Display for ShamirError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShamirError::InvalidThreshold => write!(f, "Invalid threshold"),
+            ShamirError::InsufficientShares => write!(f, "Not enough shares to recover the secret"),
+            ShamirError::InconsistentShares => write!(f, "Shares are inconsistent"),
+            ShamirError::RandomnessError => write!(f, "Could not generate randomness"),
+        }
+    }
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial x^8 + x^4 + x^3 + x + 1.
+/// Runs in constant time, no lookup tables are indexed with secret data.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse in GF(2^8), computed as a^254
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exp = 254u8;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Evaluates the polynomial through `points` at `x` using Lagrange interpolation.
+/// Every point is a share x coordinate and the share bytes.
+fn interpolate(points: &[(u8, &[u8])], x: u8) -> Vec<u8> {
+    let length = points[0].1.len();
+    let mut result = vec![0u8; length];
+    for (i, (xi, yi)) in points.iter().enumerate() {
+        let mut basis = 1u8;
+        for (j, (xj, _)) in points.iter().enumerate() {
+            if i != j {
+                // addition and subtraction are both xor in GF(2^8)
+                basis = gf_mul(basis, gf_mul(x ^ xj, gf_inv(xi ^ xj)));
+            }
+        }
+        for (byte, y) in result.iter_mut().zip(yi.iter()) {
+            *byte ^= gf_mul(basis, *y);
+        }
+    }
+    result
+}
+
+/// Splits a secret into `shares` Shamir shares, any `threshold` of which recover it.
+/// Share `i` is the evaluation of a random polynomial at `x = i + 1`,
+/// so shares keep their position like Reed-Solomon shards.
+pub fn split_secret_internal(
+    secret: &[u8],
+    threshold: usize,
+    shares: usize,
+) -> Result<Vec<Vec<u8>>, ShamirError> {
+    if threshold == 0 || threshold > shares || shares > 255 {
+        return Err(ShamirError::InvalidThreshold);
+    }
+    // one random polynomial of degree threshold - 1 per secret byte
+    let mut coefficients = vec![0u8; secret.len() * (threshold - 1)];
+    getrandom::getrandom(&mut coefficients).map_err(|_| ShamirError::RandomnessError)?;
+    let result = (1..=shares as u8)
+        .map(|x| {
+            secret
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| {
+                    let row = &coefficients[i * (threshold - 1)..(i + 1) * (threshold - 1)];
+                    // horner evaluation from the highest coefficient down to the secret
+                    let acc = row.iter().rev().fold(0u8, |acc, c| gf_mul(acc, x) ^ c);
+                    gf_mul(acc, x) ^ byte
+                })
+                .collect()
+        })
+        .collect();
+    coefficients.iter_mut().for_each(|c| *c = 0);
+    Ok(result)
+}
+
+/// Collects the points of aligned shares and checks every share beyond the threshold
+/// against the polynomial through the first `threshold` ones, which are returned.
+fn checked_points(
+    shares: &[Option<Vec<u8>>],
+    threshold: usize,
+) -> Result<Vec<(u8, &[u8])>, ShamirError> {
+    if threshold == 0 || shares.len() > 255 {
+        return Err(ShamirError::InvalidThreshold);
+    }
+    let points: Vec<(u8, &[u8])> = shares
+        .iter()
+        .enumerate()
+        .filter_map(|(i, share)| share.as_deref().map(|share| (i as u8 + 1, share)))
+        .collect();
+    if points.len() < threshold {
+        return Err(ShamirError::InsufficientShares);
+    }
+    if points
+        .iter()
+        .any(|(_, share)| share.len() != points[0].1.len())
+    {
+        return Err(ShamirError::InconsistentShares);
+    }
+    let (base, extra) = points.split_at(threshold);
+    for (x, share) in extra {
+        if interpolate(base, *x) != *share {
+            return Err(ShamirError::InconsistentShares);
+        }
+    }
+    Ok(base.to_vec())
+}
+
+/// Recovers a secret from aligned shares, `None` marks a missing share.
+/// Every share beyond the threshold is checked against the recovered polynomial.
+pub fn combine_shares_internal(
+    shares: &[Option<Vec<u8>>],
+    threshold: usize,
+) -> Result<Vec<u8>, ShamirError> {
+    let base = checked_points(shares, threshold)?;
+    Ok(interpolate(&base, 0))
+}
+
+/// Recomputes every share of aligned shares, `None` marks a missing share.
+/// The returned shares lie on the same polynomial, so they recover the same secret.
+pub fn recover_shares_internal(
+    shares: &[Option<Vec<u8>>],
+    threshold: usize,
+) -> Result<Vec<Vec<u8>>, ShamirError> {
+    let base = checked_points(shares, threshold)?;
+    Ok(shares
+        .iter()
+        .enumerate()
+        .map(|(i, share)| match share {
+            Some(share) => share.clone(),
+            None => interpolate(&base, i as u8 + 1),
+        })
+        .collect())
+}
+
+/// Splits a secret into Shamir shares over GF(256).
+/// Fewer than `threshold` shares reveal nothing about the secret.
+///
+/// # Arguments
+///
+/// * `secret` - The secret to split
+/// * `threshold` - The number of shares needed to recover the secret
+/// * `shares` - The total number of shares, at most 255
+///
+/// # Returns
+///
+/// A Vec of byte arrays containing the shares.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = splitSecret)]
+pub fn split_secret(secret: Vec<u8>, threshold: usize, shares: usize) -> Result<JsValue, JsValue> {
+    let shares = split_secret_internal(&secret, threshold, shares)?;
+    Ok(to_value(&shares)?)
+}
+
+/// Recovers a secret from Shamir shares.
+/// The shares keep their position, missing shares are passed as `null`
+/// e.g `[null,share_2,null,share_4,null]`
+///
+/// # Arguments
+///
+/// * `shares` - The aligned shares
+/// * `threshold` - The number of shares needed to recover the secret
+///
+/// # Returns
+///
+/// The recovered secret.
+/// If there are not enough shares or they are inconsistent, an error is returned.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = combineShares)]
+pub fn combine_shares(shares: Vec<JsValue>, threshold: usize) -> Result<JsValue, JsValue> {
+    let shares: Vec<Option<Vec<u8>>> = shares
+        .into_iter()
+        .map(|js_value| {
+            if js_value.is_null() || js_value.is_undefined() {
+                None
+            } else {
+                Some(Uint8Array::from(js_value).to_vec())
+            }
+        })
+        .collect();
+    let secret = combine_shares_internal(&shares, threshold)?;
+    Ok(Uint8Array::from(secret.as_slice()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gf_inverse() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn test_split_and_combine() {
+        let secret = b"era wallet entropy".to_vec();
+        let shares = split_secret_internal(&secret, 3, 5).unwrap();
+
+        let shares_copy = shares.clone();
+        let mut aligned: Vec<Option<Vec<u8>>> = shares.into_iter().map(Some).collect();
+        assert_eq!(combine_shares_internal(&aligned, 3).unwrap(), secret);
+
+        aligned[0] = None;
+        aligned[2] = None;
+        assert_eq!(combine_shares_internal(&aligned, 3).unwrap(), secret);
+
+        let recovered = recover_shares_internal(&aligned, 3).unwrap();
+        assert_eq!(recovered, shares_copy);
+
+        aligned[1] = None;
+        assert_eq!(
+            combine_shares_internal(&aligned, 3),
+            Err(ShamirError::InsufficientShares)
+        );
+    }
+
+    #[test]
+    fn test_detects_tampered_share() {
+        let secret = vec![7u8; 16];
+        let shares = split_secret_internal(&secret, 2, 3).unwrap();
+        let mut aligned: Vec<Option<Vec<u8>>> = shares.into_iter().map(Some).collect();
+        aligned[2].as_mut().unwrap()[0] ^= 1;
+        assert_eq!(
+            combine_shares_internal(&aligned, 2),
+            Err(ShamirError::InconsistentShares)
+        );
+    }
+}
@@ -1,15 +1,298 @@
-use crate::{decrypt, encrypt, wasm_bindgen, JsValue, Uint8Array};
+use std::fmt::{self, Display};
 
+use aes_gcm_siv::aead::{Aead, KeyInit, Payload};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use zeroize::Zeroizing;
+
+use crate::{decrypt, Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use crate::{
+    errors::{from_js, EraError},
+    wasm_bindgen, JsValue, Uint8Array,
+};
+
+/// Magic bytes at the start of every Argon2id sealed shard
+const CIPHER_MAGIC: [u8; 4] = *b"ERAK";
+/// Current version of the sealed shard format
+const CIPHER_VERSION: u8 = 1;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const KEY_LENGTH: usize = 32;
+/// magic | version | memory u32 | iterations u32 | parallelism u32 | salt | nonce
+const HEADER_LENGTH: usize = 4 + 1 + 12 + SALT_LENGTH + NONCE_LENGTH;
+
+/// Upper bounds on the parameters read from a header, so a crafted shard can not exhaust the client.
+/// 256 MiB stays well within the memory a wasm instance can grow to in a browser tab.
+const MAX_MEMORY_KIB: u32 = 256 * 1024;
+const MAX_ITERATIONS: u32 = 64;
+const MAX_PARALLELISM: u32 = 16;
+
+#[derive(Debug, PartialEq)]
+pub enum CipherError {
+    /// The key derivation parameters are out of the supported range
+    InvalidParameters,
+    /// The sealed shard is truncated or malformed
+    InvalidCiphertext,
+    /// The sealed shard was written by a newer format version
+    UnsupportedVersion(u8),
+    /// The password is wrong or the sealed shard was altered
+    DecryptionFailed,
+    /// The shard could not be encrypted
+    EncryptionFailed,
+    /// The platform random number generator failed
+    RandomnessError,
+}
+
+impl std::error::Error for CipherError {}
+
+impl Display for CipherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CipherError::InvalidParameters => write!(f, "Invalid key derivation parameters"),
+            CipherError::InvalidCiphertext => write!(f, "Invalid encrypted shard"),
+            CipherError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported encrypted shard version {}", version)
+            }
+            CipherError::DecryptionFailed => {
+                write!(f, "Decryption error: wrong password or altered shard")
+            }
+            CipherError::EncryptionFailed => write!(f, "Encryption error"),
+            CipherError::RandomnessError => write!(f, "Could not generate randomness"),
+        }
+    }
+}
+
+/// Argon2id cost parameters used to derive a shard key from a pin or password.
+/// They are chosen per wallet and stored in the header of every sealed shard.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// 19 MiB, 2 iterations and 1 lane, the OWASP recommendation for Argon2id
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Reads the parameters from the header of a sealed shard.
+    /// Returns `None` for shards sealed by simple_crypt.
+    pub fn of(sealed: &[u8]) -> Option<Self> {
+        if sealed.len() < HEADER_LENGTH || sealed[..4] != CIPHER_MAGIC {
+            return None;
+        }
+        let word = |at: usize| {
+            u32::from_be_bytes([sealed[at], sealed[at + 1], sealed[at + 2], sealed[at + 3]])
+        };
+        Some(Self {
+            memory_kib: word(5),
+            iterations: word(9),
+            parallelism: word(13),
+        })
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>, CipherError> {
+        if self.memory_kib > MAX_MEMORY_KIB
+            || self.iterations > MAX_ITERATIONS
+            || self.parallelism > MAX_PARALLELISM
+        {
+            return Err(CipherError::InvalidParameters);
+        }
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_LENGTH),
+        )
+        .map_err(|_| CipherError::InvalidParameters)?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+/// Derives the shard key and returns the AEAD cipher for it
+fn cipher(password: &[u8], salt: &[u8], params: &KdfParams) -> Result<Aes256GcmSiv, CipherError> {
+    let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+    params
+        .argon2()?
+        .hash_password_into(password, salt, &mut *key)
+        .map_err(|_| CipherError::InvalidParameters)?;
+    Aes256GcmSiv::new_from_slice(&*key).map_err(|_| CipherError::EncryptionFailed)
+}
+
+/// Encrypts a shard with a key derived from the password with Argon2id.
+///
+/// The sealed shard is `magic | version | memory | iterations | parallelism | salt | nonce | ciphertext`.
+/// The whole header is authenticated, so the parameters can not be lowered without failing decryption.
+pub fn encrypt_shard(
+    data: &[u8],
+    password: &[u8],
+    params: &KdfParams,
+) -> Result<Vec<u8>, CipherError> {
+    let mut header = Vec::with_capacity(HEADER_LENGTH);
+    header.extend_from_slice(&CIPHER_MAGIC);
+    header.push(CIPHER_VERSION);
+    header.extend_from_slice(&params.memory_kib.to_be_bytes());
+    header.extend_from_slice(&params.iterations.to_be_bytes());
+    header.extend_from_slice(&params.parallelism.to_be_bytes());
+    let mut random = [0u8; SALT_LENGTH + NONCE_LENGTH];
+    getrandom::getrandom(&mut random).map_err(|_| CipherError::RandomnessError)?;
+    header.extend_from_slice(&random);
+
+    let (salt, nonce) = random.split_at(SALT_LENGTH);
+    let ciphertext = cipher(password, salt, params)?
+        .encrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: data,
+                aad: &header,
+            },
+        )
+        .map_err(|_| CipherError::EncryptionFailed)?;
+    header.extend_from_slice(&ciphertext);
+    Ok(header)
+}
+
+/// Decrypts a shard sealed by `encrypt_shard`.
+/// Shards sealed by simple_crypt before the Argon2id format are still accepted.
+pub fn decrypt_shard(sealed: &[u8], password: &[u8]) -> Result<Vec<u8>, CipherError> {
+    if !sealed.starts_with(&CIPHER_MAGIC) {
+        return decrypt(sealed, password).map_err(|_| CipherError::DecryptionFailed);
+    }
+    let params = KdfParams::of(sealed).ok_or(CipherError::InvalidCiphertext)?;
+    if sealed[4] != CIPHER_VERSION {
+        return Err(CipherError::UnsupportedVersion(sealed[4]));
+    }
+    let (header, ciphertext) = sealed.split_at(HEADER_LENGTH);
+    let salt = &header[17..17 + SALT_LENGTH];
+    let nonce = &header[17 + SALT_LENGTH..];
+    cipher(password, salt, &params)?
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| CipherError::DecryptionFailed)
+}
+
+#[cfg(feature = "wasm")]
 #[wasm_bindgen(js_name = decryptShard)]
 pub fn decrypt_shards(shards: Vec<u8>, password: String) -> Result<JsValue, JsValue> {
-    let decrypted = decrypt(&shards, &password.as_bytes())
-        .map_err(|e| JsValue::from_str(&format!("Encryption error: {:?}", e)))?;
+    let decrypted = Zeroizing::new(decrypt_shard(&shards, password.as_bytes())?);
     Ok(Uint8Array::from(decrypted.as_slice()).into())
 }
 
+/// Encrypts a shard with an Argon2id derived key.
+///
+/// # Arguments
+///
+/// * `shards` - The shard to encrypt
+/// * `password` - The pin or password to derive the key from
+/// * `params` - Optional `{memory_kib, iterations, parallelism}`, defaults to 19 MiB, 2 iterations and 1 lane
+///
+/// # Returns
+///
+/// The sealed shard, its header carries the parameters needed to decrypt it.
+#[cfg(feature = "wasm")]
 #[wasm_bindgen(js_name = encryptShard)]
-pub fn encrypt_shards(shards: Vec<u8>, password: String) -> Result<JsValue, JsValue> {
-    let encrypted = encrypt(&shards, &password.as_bytes())
-        .map_err(|e| JsValue::from_str(&format!("Encryption error: {:?}", e)))?;
+pub fn encrypt_shards(
+    shards: Vec<u8>,
+    password: String,
+    params: JsValue,
+) -> Result<JsValue, JsValue> {
+    let params = kdf_params_from(params)?;
+    let encrypted = encrypt_shard(&shards, password.as_bytes(), &params)?;
     Ok(Uint8Array::from(encrypted.as_slice()).into())
 }
+
+/// Reads optional key derivation parameters passed from javascript
+#[cfg(feature = "wasm")]
+pub fn kdf_params_from(params: JsValue) -> Result<KdfParams, EraError> {
+    if params.is_undefined() || params.is_null() {
+        return Ok(KdfParams::default());
+    }
+    from_js(params, "key derivation parameters")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encrypt;
+
+    // cheap parameters to keep the tests fast
+    const TEST_PARAMS: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn test_argon2id_round_trip() {
+        let sealed = encrypt_shard(b"project shard", b"222333", &TEST_PARAMS).unwrap();
+        assert_eq!(KdfParams::of(&sealed), Some(TEST_PARAMS));
+        assert_eq!(decrypt_shard(&sealed, b"222333").unwrap(), b"project shard");
+        assert_eq!(
+            decrypt_shard(&sealed, b"222334"),
+            Err(CipherError::DecryptionFailed)
+        );
+
+        // lowering the stored cost changes the authenticated header
+        let mut weakened = sealed.clone();
+        weakened[8] ^= 1;
+        assert_eq!(
+            decrypt_shard(&weakened, b"222333"),
+            Err(CipherError::DecryptionFailed)
+        );
+
+        let mut newer = sealed;
+        newer[4] = CIPHER_VERSION + 1;
+        assert_eq!(
+            decrypt_shard(&newer, b"222333"),
+            Err(CipherError::UnsupportedVersion(CIPHER_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn test_decrypts_legacy_shard() {
+        let legacy = encrypt(b"project shard", b"222333").unwrap();
+        assert_eq!(KdfParams::of(&legacy), None);
+        assert_eq!(decrypt_shard(&legacy, b"222333").unwrap(), b"project shard");
+    }
+
+    #[test]
+    fn test_rejects_excessive_parameters() {
+        let params = KdfParams {
+            memory_kib: MAX_MEMORY_KIB + 1,
+            ..TEST_PARAMS
+        };
+        assert_eq!(
+            encrypt_shard(b"shard", b"222333", &params),
+            Err(CipherError::InvalidParameters)
+        );
+
+        // a crafted header asking for 1 GiB is refused before any allocation
+        let mut crafted = encrypt_shard(b"shard", b"222333", &TEST_PARAMS).unwrap();
+        crafted[5..9].copy_from_slice(&(1u32 << 20).to_be_bytes());
+        assert_eq!(
+            decrypt_shard(&crafted, b"222333"),
+            Err(CipherError::InvalidParameters)
+        );
+        let params = KdfParams {
+            memory_kib: MAX_MEMORY_KIB,
+            ..TEST_PARAMS
+        };
+        assert!(params.argon2().is_ok());
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt::{self, Display};
+
+use crate::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq)]
+pub enum LayoutError {
+    /// The layout has no data shards or more shards than GF(2^8) supports
+    InvalidShardCount,
+    /// A slot points past the last shard of the layout
+    SlotOutOfRange(String),
+    /// Two slots share the same name or the same position
+    DuplicateSlot(String),
+    /// The wallet does not define a slot with this name
+    UnknownSlot(String),
+    /// A Reed-Solomon slot points at a data shard, a plaintext chunk of the entropy
+    DataShardSlot(String),
+    /// The slots hold fewer shards than the given number needed to rebuild the entropy
+    TooFewSlots(usize),
+}
+
+impl std::error::Error for LayoutError {}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::InvalidShardCount => write!(f, "Invalid shard count in wallet layout"),
+            LayoutError::SlotOutOfRange(name) => {
+                write!(f, "Slot '{}' is out of range for the wallet layout", name)
+            }
+            LayoutError::DuplicateSlot(name) => {
+                write!(f, "Slot '{}' is defined more than once", name)
+            }
+            LayoutError::UnknownSlot(name) => write!(f, "Unknown shard slot '{}'", name),
+            LayoutError::DataShardSlot(name) => write!(
+                f,
+                "Slot '{}' would hand out a Reed-Solomon data shard, which is plaintext entropy",
+                name
+            ),
+            LayoutError::TooFewSlots(needed) => write!(
+                f,
+                "The wallet layout needs at least {} slots to rebuild the entropy",
+                needed
+            ),
+        }
+    }
+}
+
+/// The algorithm used to split the wallet entropy into shards.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardScheme {
+    /// Reed-Solomon erasure coding. Tolerates lost shards but is not secret sharing,
+    /// a shard can leak information about the entropy.
+    #[default]
+    ReedSolomon,
+    /// Shamir secret sharing over GF(256). Fewer than `data_shards` shards reveal nothing.
+    Shamir,
+    /// Shamir secret sharing over the ristretto255 scalar field with Feldman commitments to
+    /// the polynomials, so every shard can be checked on its own against the commitments.
+    Feldman,
+}
+
+/// A named position in the aligned shard array of a wallet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShardSlot {
+    pub name: String,
+    pub index: usize,
+}
+
+/// Describes how the entropy of a wallet is split into shards.
+/// Any `data_shards` of the `data_shards + parity_shards` shards can rebuild the entropy.
+/// Only the positions listed in `slots` are ever handed out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WalletLayout {
+    #[serde(default)]
+    pub scheme: ShardScheme,
+    pub data_shards: usize,
+    pub parity_shards: usize,
+    pub slots: Vec<ShardSlot>,
+    /// Length of the entropy when it had to be padded, to a multiple of `data_shards` for
+    /// Reed-Solomon or to whole Feldman chunks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_length: Option<usize>,
+}
+
+impl WalletLayout {
+    pub const PROJECT: &'static str = "project";
+    pub const SYSTEM: &'static str = "system";
+    pub const RECOVERY: &'static str = "recovery";
+
+    pub fn new(data_shards: usize, parity_shards: usize, slots: Vec<ShardSlot>) -> Self {
+        Self {
+            scheme: ShardScheme::default(),
+            data_shards,
+            parity_shards,
+            slots,
+            secret_length: None,
+        }
+    }
+
+    /// Switches the layout to another splitting scheme
+    pub fn with_scheme(mut self, scheme: ShardScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    pub fn total_shards(&self) -> usize {
+        self.data_shards + self.parity_shards
+    }
+
+    /// Returns the position of the slot with the given name
+    pub fn position(&self, name: &str) -> Result<usize, LayoutError> {
+        self.slots
+            .iter()
+            .find(|slot| slot.name == name)
+            .map(|slot| slot.index)
+            .ok_or_else(|| LayoutError::UnknownSlot(name.into()))
+    }
+
+    /// Checks that the shard counts are supported and that every slot is unique and in range.
+    /// Reed-Solomon data shards are chunks of the entropy itself, so only parity shards may
+    /// be assigned to a slot. There must be at least `data_shards` slots, the wallet could
+    /// never be rebuilt from fewer.
+    pub fn validate(&self) -> Result<(), LayoutError> {
+        let max_shards = match self.scheme {
+            ShardScheme::ReedSolomon => 256,
+            // share x coordinates are the non-zero field elements
+            ShardScheme::Shamir | ShardScheme::Feldman => 255,
+        };
+        if self.data_shards == 0 || self.parity_shards == 0 || self.total_shards() > max_shards {
+            return Err(LayoutError::InvalidShardCount);
+        }
+        for (i, slot) in self.slots.iter().enumerate() {
+            if slot.index >= self.total_shards() {
+                return Err(LayoutError::SlotOutOfRange(slot.name.clone()));
+            }
+            if self.scheme == ShardScheme::ReedSolomon && slot.index < self.data_shards {
+                return Err(LayoutError::DataShardSlot(slot.name.clone()));
+            }
+            if self.slots[..i]
+                .iter()
+                .any(|other| other.name == slot.name || other.index == slot.index)
+            {
+                return Err(LayoutError::DuplicateSlot(slot.name.clone()));
+            }
+        }
+        if self.slots.len() < self.data_shards {
+            return Err(LayoutError::TooFewSlots(self.data_shards));
+        }
+        Ok(())
+    }
+}
+
+impl Default for WalletLayout {
+    /// The original ERA layout: 2 data shards and 3 parity shards named project, system and recovery
+    fn default() -> Self {
+        let slot = |name: &str, index| ShardSlot {
+            name: name.into(),
+            index,
+        };
+        Self::new(
+            2,
+            3,
+            vec![
+                slot(Self::PROJECT, 2),
+                slot(Self::SYSTEM, 3),
+                slot(Self::RECOVERY, 4),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_layout() {
+        let layout = WalletLayout::default();
+        assert_eq!(layout.validate(), Ok(()));
+        assert_eq!(layout.position(WalletLayout::SYSTEM), Ok(3));
+
+        let mut duplicate = layout.clone();
+        duplicate.slots.push(ShardSlot {
+            name: "device".into(),
+            index: 2,
+        });
+        assert_eq!(
+            duplicate.validate(),
+            Err(LayoutError::DuplicateSlot("device".into()))
+        );
+
+        let shamir = WalletLayout::new(5, 251, vec![]).with_scheme(ShardScheme::Shamir);
+        assert_eq!(shamir.validate(), Err(LayoutError::InvalidShardCount));
+
+        let mut plaintext = layout.clone();
+        plaintext.slots.push(ShardSlot {
+            name: "custodian".into(),
+            index: 1,
+        });
+        assert_eq!(
+            plaintext.validate(),
+            Err(LayoutError::DataShardSlot("custodian".into()))
+        );
+        // every shamir share is a share, whatever its index
+        assert_eq!(
+            plaintext.with_scheme(ShardScheme::Shamir).validate(),
+            Ok(())
+        );
+
+        let mut unrecoverable = layout.clone();
+        unrecoverable.slots.truncate(1);
+        assert_eq!(unrecoverable.validate(), Err(LayoutError::TooFewSlots(2)));
+
+        let mut out_of_range = layout;
+        out_of_range.slots[0].index = 5;
+        assert_eq!(
+            out_of_range.validate(),
+            Err(LayoutError::SlotOutOfRange(WalletLayout::PROJECT.into()))
+        );
+    }
+}
@@ -1,2 +1,3 @@
 pub mod cryptography;
+pub mod layout;
 pub mod wallet;
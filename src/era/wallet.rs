@@ -1,12 +1,31 @@
-use bip39::{Language, Mnemonic, Seed};
-use js_sys::{Error, EvalError};
+use std::collections::BTreeMap;
 
-use crate::chains::polkadot::PolkadotSigner;
-use sp_core::{sr25519, Pair};
-use crate::decrypt;
-use crate::{erasure_coding::ErasureError, Deserialize};
+use bip39::{Language, Mnemonic, MnemonicType, Seed};
+
+use crate::chains::bitcoin::BitcoinSigner;
+use crate::chains::cosmos::{self, CosmosSigner};
+use crate::chains::ethereum::EthereumSigner;
+use crate::chains::polkadot::{Derivation, PolkadotSigner, Scheme, DEFAULT_DERIVATION};
+use crate::chains::solana::{self, SolanaSigner};
+use crate::commitment::ShardCommitments;
+use crate::crypto::crypto::{derive_ed25519_key, derive_key};
+use crate::cryptography::{decrypt_shard, encrypt_shard, KdfParams};
+use crate::envelope::{EnvelopeError, ShardEnvelope, ENVELOPE_VERSION, WALLET_ID_LENGTH};
+use crate::erasure_coding::{encode_shards_internal, reconstruct_shards_internal};
+use crate::errors::{EraError, ErrorCode};
+use crate::feldman;
+use crate::key::KeyLength;
+use crate::layout::{LayoutError, ShardScheme, WalletLayout};
+use crate::oprf::PinUnlock;
+use crate::shamir::{combine_shares_internal, recover_shares_internal, split_secret_internal};
+#[cfg(feature = "wasm")]
+use crate::{cryptography::kdf_params_from, errors::from_js, to_value, wasm_bindgen, JsValue};
 use crate::{general_purpose, Engine};
-use crate::{wasm_bindgen, JsValue, ReedSolomon};
+use crate::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Shards aligned to their position in the layout, wiped when dropped
+type Shards = Zeroizing<Vec<Option<Vec<u8>>>>;
 
 // base_wallet -> Shares(vec<vec<u8>>) -> Key -> Signer
 
@@ -15,16 +34,85 @@ use crate::{wasm_bindgen, JsValue, ReedSolomon};
 // key.to_polkadot() -> Signer
 // signer.sign()
 
-#[wasm_bindgen]
-#[derive(Deserialize)]
-#[serde(deny_unknown_fields)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct BaseWallet {
-    project_shard: Option<Vec<u8>>,
-    system_shard: Option<Vec<u8>>,
-    recovery_shard: Option<Vec<u8>>,
+    // describes the shard scheme and where each named shard sits
+    layout: WalletLayout,
+    // shards aligned to their position in the layout
+    shards: Vec<Option<Vec<u8>>>,
+    // whether the shard at the same position is still encrypted
+    sealed: Vec<bool>,
+    // id shared by the enveloped shards of the wallet
+    wallet_id: Option<[u8; WALLET_ID_LENGTH]>,
+    // refresh generation shared by the enveloped shards of the wallet
+    generation: Option<u32>,
+    // blake3 hash of the entropy, used to check a key before resealing shards
+    seed_hash: Option<String>,
+    // published Feldman commitments, every shard is checked against them once it is readable
+    commitments: Option<ShardCommitments>,
 }
 
-#[wasm_bindgen]
+/// The sealed output of `BaseWallet::create`. Every shard is a base64 encoded `ShardEnvelope`,
+/// the project shard is encrypted with the pin and the recovery shard with the
+/// recovery password. Only this material should ever leave the client.
+#[derive(Debug, Serialize)]
+pub struct WalletShards {
+    pub project_shard: String,
+    pub system_shard: String,
+    pub recovery_shard: String,
+    /// Any other named slot of the layout, e.g. a device or custodian shard
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub shards: BTreeMap<String, String>,
+    pub layout: WalletLayout,
+    /// Hex encoded id stamped on every shard of the wallet
+    pub wallet_id: String,
+    /// Refresh generation stamped on every shard, shards of different generations never mix
+    pub generation: u32,
+    /// Feldman commitments to the polynomials of the shards, to publish alongside the wallet.
+    /// Only wallets of the `feldman` scheme have them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commitments: Option<ShardCommitments>,
+    /// blake3 hash of the entropy, as checked by `BaseWallet::verify_key`
+    pub seed_hash: String,
+}
+
+/// The output of `BaseWallet::change_pin`: the resealed project shard.
+/// It lies on the same polynomials, the published commitments still apply to it.
+#[derive(Debug, Serialize)]
+pub struct ResealedShard {
+    pub project_shard: String,
+}
+
+/// The output of `BaseWallet::recover`: a fresh project shard, an optional rotated
+/// recovery shard and the checks showing both rebuild the original seed.
+#[derive(Debug, Serialize)]
+pub struct RecoveredShards {
+    pub project_shard: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_shard: Option<String>,
+    pub seed_hash: String,
+    pub proofs: Vec<ConsistencyProof>,
+}
+
+/// Shows that one recovery path rebuilds the seed: the shards of `slots`, with the new shard
+/// among them, opened with their new secrets, rebuild the entropy whose blake3 hash is
+/// `seed_hash`.
+///
+/// It is checkable by anyone holding the shards of `slots`: the new envelope must hash to
+/// `envelope_hash`, and once opened their entropy must hash to `seed_hash`, e.g. with
+/// `BaseWallet::verify_key`. It proves nothing to a party that can not open the shards,
+/// it only pins down which envelope the check was made with.
+#[derive(Debug, Serialize)]
+pub struct ConsistencyProof {
+    pub slots: Vec<String>,
+    /// blake3 hash of the new envelope of the path.
+    /// `None` for wallets of bare shards, which have no envelope.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub envelope_hash: Option<String>,
+    pub seed_hash: String,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 /// Implementation of the `BaseWallet` struct with WebAssembly bindings.
 ///
 /// # Methods
@@ -35,67 +123,134 @@ pub struct BaseWallet {
 ///
 /// - `reconstruct_shards(&self) -> Result<JsValue, JsValue>`
 ///   Reconstructs data shards using Reed-Solomon erasure coding. This method:
-///   1. Initializes a Reed-Solomon instance with 2 data shards and 3 parity shards.
+///   1. Initializes a Reed-Solomon instance with the data and parity shard counts of the wallet layout.
 ///   2. Builds the shards from the wallet data.
 ///   3. Attempts to reconstruct missing shards, returning an error if reconstruction fails.
 ///   4. Verifies the integrity of the reconstructed shards.
 ///   5. Combines only the data shards into a single byte array and returns it as a JavaScript `Uint8Array`.
+///
 ///   Returns a JavaScript error if any step fails.
+///
+/// - `create(pin: String, recovery_password: String, length: KeyLength, layout: JsValue, kdf: JsValue) -> Result<JsValue, JsValue>`
+///   Generates a new wallet on the client and returns its sealed shards, layout and seed hash.
+///
+/// - `layout(&self) -> Result<JsValue, JsValue>`
+///   Returns the layout descriptor the wallet was built with.
+///
+/// - `unlock(&mut self, unlock: PinUnlock, response: Vec<u8>) -> Result<Signer, JsValue>`
+///   Builds the signer from a project shard protected by the server assisted pin OPRF.
+///
+/// - `change_pin(&self, old_pin: String, new_pin: String) -> Result<String, JsValue>`
+///   Reseals the project shard under a new pin without changing the seed.
+///
+/// - `recover(&self, recovery_password: String, new_pin: String, new_recovery_password: Option<String>) -> Result<JsValue, JsValue>`
+///   Issues a fresh project shard from the system and recovery shards.
+///
+/// - `refresh(&self, pin: String, recovery_password: String) -> Result<JsValue, JsValue>`
+///   Issues a brand new shard set for the same seed, invalidating the outstanding shards.
 impl BaseWallet {
     /// This function takes an object as an arguement and returns a base wallet.
     /// It decodes the base64 encoded shards and builds it into an object.
+    /// Enveloped shards must match the layout and belong to the same wallet.
+    /// The `project_shard`, `system_shard` and `recovery_shard` fields fill the slots of the same name,
+    /// any other named slot is passed in the `shards` object.
+    /// When no `layout` is given the default 2 data + 3 parity layout is used.
+    /// The optional `seed_hash` is required by operations that reseal shards, such as `change_pin`.
+    /// When the Feldman `commitments` of the wallet are given, every plaintext shard, like the
+    /// system shard, is checked against them before it is accepted, so a corrupted or forged shard
+    /// is rejected before any pin is spent on it. Encrypted shards are checked once decrypted.
+    #[cfg(feature = "wasm")]
     #[wasm_bindgen(constructor)]
     pub fn new(value: JsValue) -> Result<BaseWallet, JsValue> {
-        #[wasm_bindgen]
         #[derive(Deserialize)]
         #[serde(deny_unknown_fields)]
         struct Temp {
             project_shard: Option<String>,
             system_shard: Option<String>,
             recovery_shard: Option<String>,
+            #[serde(default)]
+            shards: BTreeMap<String, String>,
+            layout: Option<WalletLayout>,
+            seed_hash: Option<String>,
+            commitments: Option<ShardCommitments>,
         }
-        let temp: Temp = serde_wasm_bindgen::from_value(value).map_err(|e| {
-            JsValue::from_str(&format!(
-                "Failed to deserialize BaseWallet::expected base64 string, got: {}",
-                e
-            ))
-        })?;
+        let temp: Temp = from_js(value, "BaseWallet, expected base64 string shards")?;
 
-        let project_shard = temp
-            .project_shard
-            .map(|x| general_purpose::STANDARD.decode(x))
-            .transpose()
-            .map_err(|e| {
-                JsValue::from_str(&format!("Failed to deserialize Project Shard: {}", e))
-            })?;
-        let system_shard = temp
-            .system_shard
-            .map(|x| general_purpose::STANDARD.decode(x))
-            .transpose()
-            .map_err(|e| {
-                JsValue::from_str(&format!("Failed to deserialize System Shard: {}", e))
-            })?;
-        let recovery_shard = temp
-            .recovery_shard
-            .map(|x| general_purpose::STANDARD.decode(x))
-            .transpose()
-            .map_err(|e| {
-                JsValue::from_str(&format!("Failed to deserialize Recovery Shard: {}", e))
-            })?;
+        let mut wallet = Self::with_layout(temp.layout.unwrap_or_default())?;
+        wallet.seed_hash = temp.seed_hash;
+        wallet.commitments = temp.commitments;
+        let named = vec![
+            (WalletLayout::PROJECT.to_string(), temp.project_shard),
+            (WalletLayout::SYSTEM.to_string(), temp.system_shard),
+            (WalletLayout::RECOVERY.to_string(), temp.recovery_shard),
+        ];
+        let extra = temp
+            .shards
+            .into_iter()
+            .map(|(name, shard)| (name, Some(shard)));
+        for (name, shard) in named.into_iter().chain(extra) {
+            if let Some(shard) = shard {
+                let shard = general_purpose::STANDARD.decode(shard).map_err(|e| {
+                    EraError::new(
+                        ErrorCode::CorruptShard,
+                        format!("Failed to deserialize {} shard: {}", name, e),
+                    )
+                })?;
+                wallet.insert_shard(&name, shard)?;
+            }
+        }
+        Ok(wallet)
+    }
 
-        Ok(Self {
-            project_shard,
-            system_shard,
-            recovery_shard,
-        })
+    /// Creates a brand new wallet on the client.
+    /// The BIP-39 entropy is generated locally and split according to the layout,
+    /// only the named slots are returned so the raw entropy never leaves the client.
+    /// # Arguments
+    ///
+    /// * `pin` - The pin used to encrypt the project shard
+    /// * `recovery_password` - The password used to encrypt the recovery shard
+    /// * `length` - The length of the mnemonic backing the wallet
+    /// * `layout` - An optional layout descriptor, defaults to 2 data + 3 parity shards
+    /// * `kdf` - Optional Argon2id parameters `{memory_kib, iterations, parallelism}` for the pin and recovery password,
+    ///   defaults to 19 MiB, 2 iterations and 1 lane. They are stored in every encrypted shard.
+    ///
+    /// # Returns
+    ///
+    /// An object with the base64 encoded `project_shard`, `system_shard`, `recovery_shard`,
+    /// any other named `shards`, the `layout`, the `wallet_id`, the `generation`, the `seed_hash`
+    /// and, for the `feldman` scheme, the shard `commitments`.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn create(
+        pin: String,
+        recovery_password: String,
+        length: KeyLength,
+        layout: JsValue,
+        kdf: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let layout: WalletLayout = if layout.is_undefined() || layout.is_null() {
+            WalletLayout::default()
+        } else {
+            from_js(layout, "wallet layout")?
+        };
+        let kdf = kdf_params_from(kdf)?;
+        let shards = Self::create_wallet(&pin, &recovery_password, length, &layout, &kdf)?;
+        Ok(to_value(&shards)?)
     }
 
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn layout(&self) -> Result<JsValue, JsValue> {
+        Ok(to_value(&self.layout)?)
+    }
+
+    #[cfg(feature = "wasm")]
     #[wasm_bindgen]
     pub fn reconstruct_shards(&self) -> Result<Vec<u8>, JsValue> {
-        Ok(self.reconstruct_shards_internal()?.into())
+        Ok(self.reconstruct_shards_internal()?.to_vec())
     }
 
-    /// Builds a base wallet into a root signer key. 
+    /// Builds a base wallet into a root signer key.
     /// This should be the first action after the instanciation of the wallet
     /// This method assumes that the `BaseWallet` contains atleast the system_shard and one other shard.
     /// The system shard is in a unencrypted format but we assume that other shards are in an encrypted format.
@@ -109,163 +264,819 @@ impl BaseWallet {
     ///
     /// A Signer: array of the root key.
     /// If the data could not be decoded, an error is returned.
+    #[cfg(feature = "wasm")]
     #[wasm_bindgen]
     pub fn build(
         &mut self,
         password: String,
         project_shard: Option<bool>,
     ) -> Result<Signer, JsValue> {
-        Ok(self.build_signer(password, project_shard)?.into())
+        Ok(self.build_signer(password, project_shard)?)
     }
 
+    /// Builds a wallet whose project shard is protected by the pin OPRF.
+    /// The pin was blinded by `PinUnlock` and `response` is the evaluation returned by the server,
+    /// so the pin can only be checked with the help of the rate limited server.
+    /// A wallet is created for the OPRF by passing the key from `PinUnlock.finish` to `create` as the pin.
+    /// # Arguments
+    ///
+    /// * `unlock` - The pending unlock, it can not be reused
+    /// * `response` - The evaluated element returned by the server
+    ///
+    /// # Returns
+    ///
+    /// A Signer: array of the root key.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn unlock(&mut self, unlock: PinUnlock, response: Vec<u8>) -> Result<Signer, JsValue> {
+        Ok(self.unlock_signer(unlock, &response)?)
+    }
+
+    /// Changes the pin protecting the project shard. The seed is left untouched.
+    /// The project shard is decrypted with the old pin and the wallet is reconstructed
+    /// and checked against its `seed_hash` before the shard is resealed.
+    /// The wallet itself is not modified.
+    /// # Arguments
+    ///
+    /// * `old_pin` - The pin the project shard is currently encrypted with
+    /// * `new_pin` - The pin to encrypt the project shard with
+    ///
+    /// # Returns
+    ///
+    /// An object with the new base64 encoded `project_shard`, ready to upload.
+    /// The published commitments of the wallet still apply to it.
+    #[cfg(feature = "wasm")]
     #[wasm_bindgen]
-    pub fn verify_key(&self, hash: String) -> Result<bool, JsValue> {
+    pub fn change_pin(&self, old_pin: String, new_pin: String) -> Result<JsValue, JsValue> {
+        Ok(to_value(&self.change_pin_internal(&old_pin, &new_pin)?)?)
+    }
+
+    /// Recovers a wallet whose pin is lost.
+    /// The entropy is rebuilt from the system and recovery shards and checked against the `seed_hash`,
+    /// then a fresh project shard is issued under the new pin. The recovery password can be rotated at the same time.
+    /// The wallet itself is not modified.
+    /// # Arguments
+    ///
+    /// * `recovery_password` - The password the recovery shard is currently encrypted with
+    /// * `new_pin` - The pin to encrypt the new project shard with
+    /// * `new_recovery_password` - When given, the recovery shard is resealed with this password
+    ///
+    /// # Returns
+    ///
+    /// An object with the base64 encoded `project_shard`, the optional `recovery_shard`,
+    /// the `seed_hash` and the `proofs` that the new shards rebuild it.
+    /// The published commitments of the wallet still apply to the new shards.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn recover(
+        &self,
+        recovery_password: String,
+        new_pin: String,
+        new_recovery_password: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let recovered = self.recover_internal(
+            &recovery_password,
+            &new_pin,
+            new_recovery_password.as_deref(),
+        )?;
+        Ok(to_value(&recovered)?)
+    }
+
+    /// Proactively refreshes the shards of a Shamir or Feldman wallet.
+    /// The seed is rebuilt from the project shard and checked against the `seed_hash`,
+    /// then split again with fresh randomness. Every new shard carries the next generation,
+    /// so it can not be combined with a shard issued before the refresh.
+    /// The wallet itself is not modified.
+    /// # Arguments
+    ///
+    /// * `pin` - The pin the project shard is encrypted with, the new project shard uses it too
+    /// * `recovery_password` - The password to encrypt the new recovery shard with
+    ///
+    /// # Returns
+    ///
+    /// An object of the same shape as the one returned by `create`.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn refresh(&self, pin: String, recovery_password: String) -> Result<JsValue, JsValue> {
+        let shards = self.refresh_internal(&pin, &recovery_password)?;
+        Ok(to_value(&shards)?)
+    }
+
+    /// Checks the blake3 hash of the entropy rebuilt from the shards
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn verify_key(&self, hash: String) -> Result<bool, EraError> {
         let entropy = self.reconstruct_shards_internal()?;
         let entropy_hash = blake3::hash(&entropy).to_string();
         Ok(entropy_hash == hash)
     }
 
+    /// Zeroes every shard held by the wallet, decrypted ones included.
+    /// The wallet is also wiped when it is freed.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn wipe(&mut self) {
+        self.shards.iter_mut().for_each(Zeroize::zeroize);
+        self.sealed.iter_mut().for_each(|sealed| *sealed = false);
+    }
+}
+
+impl Drop for BaseWallet {
+    fn drop(&mut self) {
+        self.wipe();
+    }
 }
 
 impl BaseWallet {
-    /// Builds the base wallet and returns an aligned data and parity shards
-    pub fn validate(&self) -> Result<Vec<Option<Vec<u8>>>, Error> {
-        match &self {
-            BaseWallet {
-                project_shard: None,
-                system_shard: None,
-                ..
-            } => return Err(EvalError::new("Fields in BaseWallet are missing").into()),
-            BaseWallet {
-                system_shard: None,
-                recovery_shard: None,
-                ..
-            } => return Err(EvalError::new("Fields in BaseWallet are missing").into()),
-            BaseWallet {
-                project_shard: None,
-                recovery_shard: None,
-                ..
-            } => return Err(EvalError::new("Fields in BaseWallet are missing").into()),
-            _ => {}
+    /// Creates an empty wallet for the given layout
+    pub fn with_layout(layout: WalletLayout) -> Result<Self, LayoutError> {
+        layout.validate()?;
+        let shards = vec![None; layout.total_shards()];
+        let sealed = vec![false; layout.total_shards()];
+        Ok(Self {
+            layout,
+            shards,
+            sealed,
+            wallet_id: None,
+            generation: None,
+            seed_hash: None,
+            commitments: None,
+        })
+    }
+
+    /// Sets the commitments every shard inserted afterwards is checked against
+    pub fn set_commitments(&mut self, commitments: ShardCommitments) {
+        self.commitments = Some(commitments);
+    }
+
+    /// Sets the seed hash the wallet is checked against before resealing shards
+    pub fn set_seed_hash(&mut self, seed_hash: String) {
+        self.seed_hash = Some(seed_hash);
+    }
+
+    /// Places a decrypted shard in the slot with the given name
+    pub fn set_shard(&mut self, name: &str, shard: Vec<u8>) -> Result<(), LayoutError> {
+        let index = self.layout.position(name)?;
+        self.shards[index] = Some(shard);
+        self.sealed[index] = false;
+        Ok(())
+    }
+
+    /// Places an encoded shard in the slot with the given name.
+    /// A `ShardEnvelope` is checked against the layout, the slot position and the other shards
+    /// of the wallet before its payload is stored. Bare shards from before the envelope format are stored as is,
+    /// the project and recovery shards are then assumed to be encrypted.
+    /// Bare and enveloped shards never mix: a bare shard carries no wallet id or generation to check,
+    /// so it could be the stripped payload of a shard from before a refresh.
+    /// When the wallet has commitments, only enveloped shards of the committed wallet are accepted
+    /// and plaintext shards must lie on the committed polynomials.
+    pub fn insert_shard(&mut self, name: &str, shard: Vec<u8>) -> Result<(), EraError> {
+        if let Some(commitments) = &self.commitments {
+            match commitments.verify(&shard) {
+                // checked by `open_slot` once it is decrypted
+                Ok(()) | Err(EnvelopeError::SealedShard(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
         }
-        let shards = vec![
-            None,
-            None,
-            self.project_shard.clone(),
-            self.system_shard.clone(),
-            self.recovery_shard.clone(),
-        ];
-        Ok(shards)
+        let index = self.layout.position(name)?;
+        if !ShardEnvelope::is_envelope(&shard) {
+            if self.wallet_id.is_some() || self.generation.is_some() {
+                return Err(EnvelopeError::MixedFormats.into());
+            }
+            self.set_shard(name, shard)?;
+            self.sealed[index] = name == WalletLayout::PROJECT || name == WalletLayout::RECOVERY;
+            return Ok(());
+        }
+        let bare = self
+            .shards
+            .iter()
+            .enumerate()
+            .any(|(other, shard)| other != index && shard.is_some());
+        if self.wallet_id.is_none() && bare {
+            return Err(EnvelopeError::MixedFormats.into());
+        }
+        let envelope = ShardEnvelope::decode(&shard)?;
+        envelope.check(
+            self.layout.scheme,
+            self.layout.data_shards,
+            self.layout.parity_shards,
+            index,
+            self.wallet_id.as_ref(),
+            self.generation,
+        )?;
+        self.wallet_id = Some(envelope.wallet_id);
+        self.generation = Some(envelope.generation);
+        self.shards[index] = Some(envelope.payload);
+        self.sealed[index] = envelope.encrypted;
+        Ok(())
+    }
+
+    /// Generates fresh entropy, splits it and seals the shards. See `BaseWallet::create`.
+    pub fn create_wallet(
+        pin: &str,
+        recovery_password: &str,
+        length: KeyLength,
+        layout: &WalletLayout,
+        kdf: &KdfParams,
+    ) -> Result<WalletShards, EraError> {
+        layout.validate()?;
+        let mnemonic = Mnemonic::new(MnemonicType::from(length), Language::English);
+        let wallet_id = ShardEnvelope::new_wallet_id()?;
+        split_and_seal(
+            mnemonic.entropy(),
+            layout,
+            wallet_id,
+            0,
+            pin,
+            recovery_password,
+            kdf,
+        )
     }
 
-    ///  Reconstructs data shards using Reed-Solomon erasure coding.
-    pub fn reconstruct_shards_internal(&self) -> Result<Vec<u8>, Error> {
-        let reed_solomon = ReedSolomon::new(2, 3).unwrap();
-        let mut shards = self.validate()?;
-        reed_solomon
-            .reconstruct(&mut shards)
-            .map_err(|_| <ErasureError as Into<JsValue>>::into(ErasureError::FragmentationError))?;
-        let shard_refs: Vec<&[u8]> = shards.iter().map(|x| x.as_deref().unwrap()).collect();
-        reed_solomon
-            .verify(&shard_refs)
-            .map_err(|e| JsValue::from_str(&format!("Verification error: {:?}", e)))?;
+    /// Splits existing entropy, e.g. the entropy of an imported mnemonic, and seals the shards
+    /// like `create_wallet` does.
+    pub fn split_entropy(
+        entropy: &[u8],
+        pin: &str,
+        recovery_password: &str,
+        layout: &WalletLayout,
+        kdf: &KdfParams,
+    ) -> Result<WalletShards, EraError> {
+        layout.validate()?;
+        let wallet_id = ShardEnvelope::new_wallet_id()?;
+        split_and_seal(entropy, layout, wallet_id, 0, pin, recovery_password, kdf)
+    }
+
+    /// Checks that the wallet holds enough shards to be rebuilt
+    pub fn validate(&self) -> Result<(), EraError> {
+        let present = self.shards.iter().filter(|shard| shard.is_some()).count();
+        if present < self.layout.data_shards {
+            return Err(EraError::new(
+                ErrorCode::MissingShard,
+                "Fields in BaseWallet are missing",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the aligned shards without the ones that are still encrypted
+    fn opened_shards(&self) -> Result<Shards, EraError> {
+        self.validate()?;
+        let shards = self
+            .shards
+            .iter()
+            .zip(self.sealed.iter())
+            .map(|(shard, sealed)| if *sealed { None } else { shard.clone() })
+            .collect();
+        Ok(Zeroizing::new(shards))
+    }
+
+    ///  Reconstructs the entropy using the scheme of the wallet layout.
+    /// Shards that are still encrypted are left out.
+    pub fn reconstruct_shards_internal(&self) -> Result<Zeroizing<Vec<u8>>, EraError> {
+        self.reconstruct_from(self.opened_shards()?)
+    }
+
+    /// Recomputes every shard of the layout from aligned shards, missing ones included
+    fn all_shards(&self, mut shards: Shards) -> Result<Zeroizing<Vec<Vec<u8>>>, EraError> {
+        let present = shards.iter().filter(|shard| shard.is_some()).count();
+        if present < self.layout.data_shards {
+            return Err(EraError::new(
+                ErrorCode::MissingShard,
+                "Fields in BaseWallet are missing",
+            ));
+        }
+        match self.layout.scheme {
+            ShardScheme::Shamir => {
+                let shards = recover_shares_internal(&shards, self.layout.data_shards)?;
+                return Ok(Zeroizing::new(shards));
+            }
+            ShardScheme::Feldman => {
+                let shards = feldman::recover_shares_internal(&shards, self.layout.data_shards)?;
+                return Ok(Zeroizing::new(shards));
+            }
+            ShardScheme::ReedSolomon => {}
+        }
+        reconstruct_shards_internal(
+            &mut shards,
+            self.layout.data_shards,
+            self.layout.parity_shards,
+        )?;
+        // every shard is present after a successful reconstruction
+        Ok(Zeroizing::new(
+            shards.iter_mut().flat_map(Option::take).collect(),
+        ))
+    }
+
+    /// Reconstructs the entropy from shards aligned to the wallet layout
+    fn reconstruct_from(&self, shards: Shards) -> Result<Zeroizing<Vec<u8>>, EraError> {
+        if self.layout.scheme != ShardScheme::ReedSolomon {
+            let present = shards.iter().filter(|shard| shard.is_some()).count();
+            if present < self.layout.data_shards {
+                return Err(EraError::new(
+                    ErrorCode::MissingShard,
+                    "Fields in BaseWallet are missing",
+                ));
+            }
+            if self.layout.scheme == ShardScheme::Shamir {
+                let secret = combine_shares_internal(&shards, self.layout.data_shards)?;
+                return Ok(Zeroizing::new(secret));
+            }
+            // the chunks of the entropy keep the padding of the last one
+            let mut secret = feldman::combine_shares_internal(&shards, self.layout.data_shards)?;
+            if let Some(length) = self.layout.secret_length {
+                secret.truncate(length);
+            }
+            return Ok(secret);
+        }
+        let shards = self.all_shards(shards)?;
         // // Combine only the data shards
-        let mut full_data = Vec::new();
-        for shard in shards.iter().take(2) {
-            full_data.extend_from_slice(shard.as_ref().unwrap());
+        let length: usize = shards
+            .iter()
+            .take(self.layout.data_shards)
+            .map(Vec::len)
+            .sum();
+        let mut full_data = Zeroizing::new(Vec::with_capacity(length));
+        for shard in shards.iter().take(self.layout.data_shards) {
+            full_data.extend_from_slice(shard);
+        }
+        if let Some(length) = self.layout.secret_length {
+            full_data.truncate(length);
         }
         Ok(full_data)
     }
 
+    /// Decrypts the shard in the named slot and returns it with its position
+    fn open_slot(
+        &self,
+        name: &str,
+        password: &str,
+    ) -> Result<(usize, Zeroizing<Vec<u8>>), EraError> {
+        let index = self.layout.position(name)?;
+        let shard = self.shards[index].as_ref().ok_or_else(|| {
+            EraError::new(
+                ErrorCode::MissingShard,
+                format!("{} shard does not exist on base Wallet", name),
+            )
+        })?;
+        let shard = Zeroizing::new(decrypt_shard(shard, password.as_bytes())?);
+        self.check_share(index, &shard)?;
+        Ok((index, shard))
+    }
+
+    /// Checks a readable shard against the commitments of the wallet, when it has any
+    fn check_share(&self, index: usize, shard: &[u8]) -> Result<(), EraError> {
+        if let Some(commitments) = &self.commitments {
+            commitments.verify_share(index, self.layout.data_shards, shard)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the key derivation parameters of the sealed shard in the named slot.
+    /// Shards sealed before Argon2id get the default parameters when they are resealed.
+    fn kdf_params(&self, name: &str) -> KdfParams {
+        self.layout
+            .position(name)
+            .ok()
+            .and_then(|index| self.shards[index].as_deref())
+            .and_then(KdfParams::of)
+            .unwrap_or_default()
+    }
+
+    /// Decrypts the shard in the named slot in place
+    pub fn decrypt_slot(&mut self, name: &str, password: &str) -> Result<(), EraError> {
+        let (index, mut shard) = self.open_slot(name, password)?;
+        self.shards[index] = Some(std::mem::take(&mut *shard));
+        self.sealed[index] = false;
+        Ok(())
+    }
+
+    /// Reconstructs the wallet with the shard at `index` replaced by its decrypted form
+    /// and checks the entropy against the seed hash of the wallet.
+    fn check_opened(&self, index: usize, shard: &[u8]) -> Result<Shards, EraError> {
+        let mut shards = self.opened_shards()?;
+        shards[index] = Some(shard.to_vec());
+        self.check_seed(shards.clone())?;
+        Ok(shards)
+    }
+
+    /// Reconstructs the entropy from aligned shards and checks it against the seed hash.
+    /// Returns the seed hash on success.
+    fn check_seed(&self, shards: Shards) -> Result<String, EraError> {
+        let seed_hash = self.seed_hash.as_ref().ok_or_else(|| {
+            EraError::new(
+                ErrorCode::InvalidInput,
+                "Seed hash does not exist on base Wallet",
+            )
+        })?;
+        let entropy = self.reconstruct_from(shards)?;
+        if blake3::hash(&entropy).to_string() != *seed_hash {
+            return Err(EraError::new(
+                ErrorCode::SeedMismatch,
+                "Reconstructed key does not match the seed hash",
+            ));
+        }
+        Ok(seed_hash.clone())
+    }
+
+    /// Wraps a shard for this wallet, shards of wallets without an envelope stay bare
+    fn seal(&self, index: usize, encrypted: bool, payload: Vec<u8>) -> Vec<u8> {
+        match self.wallet_id {
+            Some(wallet_id) => seal_shard(
+                &self.layout,
+                wallet_id,
+                self.generation.unwrap_or(0),
+                index,
+                encrypted,
+                payload,
+            ),
+            None => payload,
+        }
+    }
+
+    /// Reseals the project shard under a new pin. See `BaseWallet::change_pin`.
+    pub fn change_pin_internal(
+        &self,
+        old_pin: &str,
+        new_pin: &str,
+    ) -> Result<ResealedShard, EraError> {
+        let (index, shard) = self.open_slot(WalletLayout::PROJECT, old_pin)?;
+        self.check_opened(index, &shard)?;
+        let kdf = self.kdf_params(WalletLayout::PROJECT);
+        let sealed = self.seal(
+            index,
+            true,
+            encrypt_shard(&shard, new_pin.as_bytes(), &kdf)?,
+        );
+        Ok(ResealedShard {
+            project_shard: general_purpose::STANDARD.encode(sealed),
+        })
+    }
+
+    /// Issues a new shard set of the next generation. See `BaseWallet::refresh`.
+    pub fn refresh_internal(
+        &self,
+        pin: &str,
+        recovery_password: &str,
+    ) -> Result<WalletShards, EraError> {
+        if self.layout.scheme == ShardScheme::ReedSolomon {
+            return Err(EraError::new(
+                ErrorCode::InvalidLayout,
+                "Shard refresh requires the shamir or feldman scheme",
+            ));
+        }
+        let (index, shard) = self.open_slot(WalletLayout::PROJECT, pin)?;
+        let shards = self.check_opened(index, &shard)?;
+        let entropy = self.reconstruct_from(shards)?;
+        let wallet_id = match self.wallet_id {
+            Some(wallet_id) => wallet_id,
+            None => ShardEnvelope::new_wallet_id()?,
+        };
+        let generation = self
+            .generation
+            .unwrap_or(0)
+            .checked_add(1)
+            .ok_or_else(|| EraError::new(ErrorCode::Internal, "Shard generation overflow"))?;
+        split_and_seal(
+            &entropy,
+            &self.layout,
+            wallet_id,
+            generation,
+            pin,
+            recovery_password,
+            &self.kdf_params(WalletLayout::PROJECT),
+        )
+    }
+
+    /// Issues a fresh project shard from the recovery path. See `BaseWallet::recover`.
+    pub fn recover_internal(
+        &self,
+        recovery_password: &str,
+        new_pin: &str,
+        new_recovery_password: Option<&str>,
+    ) -> Result<RecoveredShards, EraError> {
+        let (recovery_index, recovery) =
+            self.open_slot(WalletLayout::RECOVERY, recovery_password)?;
+        let project_index = self.layout.position(WalletLayout::PROJECT)?;
+        // the project shard is the one being replaced, never rebuild from it, even to check
+        let mut opened = self.opened_shards()?;
+        opened[recovery_index] = Some(recovery.to_vec());
+        opened[project_index] = None;
+        let seed_hash = self.check_seed(opened.clone())?;
+        let all = self.all_shards(opened.clone())?;
+        self.check_share(project_index, &all[project_index])?;
+
+        // the recovery shard carries the parameters the wallet was created with
+        let kdf = self.kdf_params(WalletLayout::RECOVERY);
+        let project = encrypt_shard(&all[project_index], new_pin.as_bytes(), &kdf)?;
+        let rotated = new_recovery_password
+            .map(|password| encrypt_shard(&recovery, password.as_bytes(), &kdf))
+            .transpose()?;
+
+        // open the new shards with their new secrets and rebuild the seed from each path
+        let mut proofs = Vec::new();
+        let mut paths = vec![(project_index, recovery_index, &project, new_pin)];
+        if let (Some(rotated), Some(password)) = (&rotated, new_recovery_password) {
+            paths.push((recovery_index, project_index, rotated, password));
+        }
+        for (index, excluded, sealed, password) in paths {
+            let shard = decrypt_shard(sealed, password.as_bytes())?;
+            let mut shards = opened.clone();
+            shards[excluded] = None;
+            shards[index] = Some(shard);
+            let slots = self
+                .layout
+                .slots
+                .iter()
+                .filter(|slot| shards[slot.index].is_some())
+                .map(|slot| slot.name.clone())
+                .collect();
+            let seed_hash = self.check_seed(shards)?;
+            let envelope_hash = self
+                .wallet_id
+                .map(|_| blake3::hash(&self.seal(index, true, sealed.clone())).to_string());
+            proofs.push(ConsistencyProof {
+                slots,
+                envelope_hash,
+                seed_hash,
+            });
+        }
+
+        let project = self.seal(project_index, true, project);
+        let rotated = rotated.map(|shard| self.seal(recovery_index, true, shard));
+        Ok(RecoveredShards {
+            project_shard: general_purpose::STANDARD.encode(&project),
+            recovery_shard: rotated.map(|shard| general_purpose::STANDARD.encode(shard)),
+            seed_hash,
+            proofs,
+        })
+    }
+
     // first decrypt key
     // reconstruct shard
     // create signer from shard
 
+    /// Finishes a pin unlock and builds the signer from the project shard. See `BaseWallet::unlock`.
+    pub fn unlock_signer(
+        &mut self,
+        unlock: PinUnlock,
+        response: &[u8],
+    ) -> Result<Signer, EraError> {
+        let key = unlock.finish_internal(response)?;
+        let password = Zeroizing::new(hex::encode(key.as_slice()));
+        self.open_signer(WalletLayout::PROJECT, &password)
+    }
+
     /// Assumes that the personal shard is still encrypted unless given otherwise by passing `project_shard` to false.
     pub fn build_signer(
         &mut self,
         password: String,
         project_shard: Option<bool>,
-    ) -> Result<Signer, Error> {
+    ) -> Result<Signer, EraError> {
+        let password = Zeroizing::new(password);
+        // check if project_shard arg is false; this means signer is being build with a recovery shard
+        let slot = match project_shard {
+            Some(false) => WalletLayout::RECOVERY,
+            _ => WalletLayout::PROJECT,
+        };
+        self.open_signer(slot, &password)
+    }
+
+    /// Decrypts the named slot with the password and rebuilds the signer from the shards
+    fn open_signer(&mut self, slot: &str, password: &str) -> Result<Signer, EraError> {
         // build as a form of validation
         self.validate()?;
-        // check if project_shard arg is false; this means signer is being build with a recovery shard
-        if let Some(project_shard) = project_shard {
-            if !project_shard {
-                if let Some(shard) = self.recovery_shard.take() {
-                    let shard = decrypt(&shard, password.as_bytes())
-                        .map_err(|e| JsValue::from_str(&format!("Decryption error: {:?}", e)))?;
-                    self.recovery_shard = Some(shard);
-                } else {
-                    return Err(JsValue::from_str(&format!(
-                        "Recovery shard does not exist on base Wallet"
-                    ))
-                    .into());
-                }
-            }
-        } else {
-            if let Some(shard) = self.project_shard.take() {
-                let shard = decrypt(&shard, password.as_bytes())
-                    .map_err(|e| JsValue::from_str(&format!("Decryption error: {:?}", e)))?;
-                self.project_shard = Some(shard);
-            } else {
-                return Err(JsValue::from_str(&format!(
-                    "Project shard does not exist on base Wallet"
-                ))
-                .into());
-            }
-        }
+        self.decrypt_slot(slot, password)?;
         let entropy = self.reconstruct_shards_internal()?;
         let mnemonic: Mnemonic = Mnemonic::from_entropy(entropy.as_slice(), Language::English)
-            .map_err(|e| JsValue::from_str(&format!("Could not generate seed: {:?}", e)))?;
-        let phrase = mnemonic.phrase().into();
-        let seed = Seed::new(&mnemonic, "").as_bytes().to_vec();
+            .map_err(|e| {
+                EraError::new(
+                    ErrorCode::InvalidMnemonic,
+                    format!("Could not generate seed: {}", e),
+                )
+            })?;
+        let phrase = Zeroizing::new(mnemonic.phrase().into());
+        let seed = Zeroizing::new(Seed::new(&mnemonic, "").as_bytes().to_vec());
         Ok(Signer { seed, phrase })
     }
 }
 
-#[wasm_bindgen]
+/// Splits the entropy according to the layout and seals every named slot.
+/// The project shard is encrypted with the pin and the recovery shard with the recovery password,
+/// both with keys derived by Argon2id with the given parameters.
+fn split_and_seal(
+    entropy: &[u8],
+    layout: &WalletLayout,
+    wallet_id: [u8; WALLET_ID_LENGTH],
+    generation: u32,
+    pin: &str,
+    recovery_password: &str,
+    kdf: &KdfParams,
+) -> Result<WalletShards, EraError> {
+    let mut layout = layout.clone();
+    layout.secret_length = None;
+    let mut commitments = None;
+    let shards = match layout.scheme {
+        ShardScheme::ReedSolomon => {
+            // pad the entropy when it does not split evenly, the layout records the real length
+            let padded = entropy.len().next_multiple_of(layout.data_shards);
+            // sized up front so padding never reallocates and leaves a copy behind
+            let mut data = Zeroizing::new(Vec::with_capacity(padded));
+            data.extend_from_slice(entropy);
+            if padded != entropy.len() {
+                layout.secret_length = Some(entropy.len());
+                data.resize(padded, 0);
+            }
+            encode_shards_internal(&data, layout.data_shards, layout.parity_shards)?
+        }
+        ShardScheme::Shamir => {
+            split_secret_internal(entropy, layout.data_shards, layout.total_shards())?
+        }
+        ShardScheme::Feldman => {
+            // the last chunk is zero padded, the layout records the real length
+            if !entropy.len().is_multiple_of(feldman::CHUNK_LENGTH) {
+                layout.secret_length = Some(entropy.len());
+            }
+            let mut split =
+                feldman::split_secret_internal(entropy, layout.data_shards, layout.total_shards())?;
+            commitments = Some(ShardCommitments::new(
+                hex::encode(wallet_id),
+                generation,
+                &split.commitments,
+            ));
+            std::mem::take(&mut split.shares)
+        }
+    };
+    let shards = Zeroizing::new(shards);
+
+    let mut sealed = BTreeMap::new();
+    for slot in &layout.slots {
+        let shard = &shards[slot.index];
+        let password = match slot.name.as_str() {
+            WalletLayout::PROJECT => Some(pin),
+            WalletLayout::RECOVERY => Some(recovery_password),
+            _ => None,
+        };
+        let payload = match password {
+            Some(password) => encrypt_shard(shard, password.as_bytes(), kdf)?,
+            None => shard.clone(),
+        };
+        let envelope = seal_shard(
+            &layout,
+            wallet_id,
+            generation,
+            slot.index,
+            password.is_some(),
+            payload,
+        );
+        sealed.insert(
+            slot.name.clone(),
+            general_purpose::STANDARD.encode(envelope),
+        );
+    }
+    let mut take = |name: &str| {
+        sealed
+            .remove(name)
+            .ok_or_else(|| EraError::from(LayoutError::UnknownSlot(name.into())))
+    };
+    Ok(WalletShards {
+        project_shard: take(WalletLayout::PROJECT)?,
+        system_shard: take(WalletLayout::SYSTEM)?,
+        recovery_shard: take(WalletLayout::RECOVERY)?,
+        shards: sealed,
+        layout,
+        wallet_id: hex::encode(wallet_id),
+        generation,
+        commitments,
+        seed_hash: blake3::hash(entropy).to_string(),
+    })
+}
+
+/// Wraps a shard payload in an envelope for the given layout and wallet
+fn seal_shard(
+    layout: &WalletLayout,
+    wallet_id: [u8; WALLET_ID_LENGTH],
+    generation: u32,
+    index: usize,
+    encrypted: bool,
+    payload: Vec<u8>,
+) -> Vec<u8> {
+    ShardEnvelope {
+        version: ENVELOPE_VERSION,
+        scheme: layout.scheme,
+        data_shards: layout.data_shards as u16,
+        parity_shards: layout.parity_shards as u16,
+        index: index as u16,
+        generation,
+        wallet_id,
+        encrypted,
+        payload,
+    }
+    .encode()
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Signer {
     // HD seed of the signer
-    seed: Vec<u8>,
+    seed: Zeroizing<Vec<u8>>,
     // Mnemonic of the signer
-    phrase: String
+    phrase: Zeroizing<String>,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Signer {
-    #[wasm_bindgen(constructor)]
-    pub fn new(seed: Vec<u8>, phrase: String) -> Result<Signer, JsValue> {
-        Ok(Signer { seed, phrase })
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(seed: Vec<u8>, phrase: String) -> Signer {
+        Signer {
+            seed: Zeroizing::new(seed),
+            phrase: Zeroizing::new(phrase),
+        }
     }
-  
-    #[wasm_bindgen]
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn as_mnemonic(&self) -> String {
-        self.phrase.clone()
+        self.phrase.to_string()
     }
-    #[wasm_bindgen]
-    pub fn to_polkadot_signer(&self) -> Result<PolkadotSigner, JsValue> {
-        let derivation = "//polkadot//0"; // Polkadot-style hard derivation
-        let full_uri = format!("{}{}", self.phrase, derivation);
-        let pair = sr25519::Pair::from_string(&full_uri, None) 
-            .map_err(|e| JsValue::from_str(&format!("Could not generate seed: {:?}", e)))?;
-        let signer = PolkadotSigner::new(pair.to_raw_vec(), derivation.into());
+
+    /// Zeroes the seed and mnemonic of the signer. The signer is also wiped when it is freed.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn wipe(&mut self) {
+        self.seed.zeroize();
+        self.phrase.zeroize();
+    }
+    /// The sr25519 signer at a substrate derivation of the mnemonic: `//` hard and `/` soft
+    /// junctions and an optional `///password`, like polkadot.js. Without a derivation it is
+    /// `//polkadot//0`, the account earlier versions always derived.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn to_polkadot_signer(&self, uri: Option<String>) -> Result<PolkadotSigner, EraError> {
+        self.to_substrate_signer("sr25519", uri)
+    }
+
+    /// The substrate signer at a derivation with the `sr25519`, `ed25519` or `ecdsa` scheme,
+    /// `//polkadot//0` by default. Ecdsa only has hard junctions.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn to_substrate_signer(
+        &self,
+        scheme: &str,
+        uri: Option<String>,
+    ) -> Result<PolkadotSigner, EraError> {
+        let scheme: Scheme = scheme.parse()?;
+        let derivation = Derivation::parse(uri.as_deref().unwrap_or(DEFAULT_DERIVATION))?;
+        let secret = scheme.secret_from_uri(&derivation.uri(&self.phrase))?;
+        let signer = PolkadotSigner::with_scheme(scheme, secret.to_vec(), derivation.path);
         Ok(signer)
     }
 
+    /// The secp256k1 ethereum signer at a BIP-32 path, `m/44'/60'/0'/0/i` for account `i`
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn to_ethereum_signer(&self, path: &str) -> Result<EthereumSigner, EraError> {
+        let (path, xpriv) = derive_key(&self.seed, path)?;
+        let key = xpriv.private_key().to_bytes().to_vec();
+        Ok(EthereumSigner::new(key, path.to_string()))
+    }
+
+    /// The bitcoin signer of the wallet on `mainnet`, `testnet`, `signet` or `regtest`. It
+    /// derives its keys along BIP-44/49/84/86 and signs the PSBT inputs it owns.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn to_bitcoin_signer(&self, network: &str) -> Result<BitcoinSigner, EraError> {
+        Ok(BitcoinSigner::new(self.seed.to_vec(), network.parse()?))
+    }
+
+    /// The ed25519 solana signer of account `index`, derived with SLIP-0010 at
+    /// `m/44'/501'/index'/0'` like Phantom and Solflare
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn to_solana_signer(&self, index: u32) -> Result<SolanaSigner, EraError> {
+        let (path, key) = derive_ed25519_key(&self.seed, &solana::account_path(index))?;
+        Ok(SolanaSigner::new(key.to_vec(), path))
+    }
 
+    /// The secp256k1 cosmos SDK signer of account `index` at `m/44'/coin_type'/0'/0/index`,
+    /// with bech32 addresses under `prefix`, e.g. `cosmos`, `osmo` or `juno`. The coin type
+    /// defaults to 118, chains with their own pass it.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn to_cosmos_signer(
+        &self,
+        prefix: &str,
+        index: u32,
+        coin_type: Option<u32>,
+    ) -> Result<CosmosSigner, EraError> {
+        let coin_type = coin_type.unwrap_or(cosmos::COSMOS_COIN_TYPE);
+        let (path, xpriv) = derive_key(&self.seed, &cosmos::account_path(coin_type, index))?;
+        let key = xpriv.private_key().to_bytes().to_vec();
+        Ok(CosmosSigner::new(key, prefix.into(), path.to_string())?)
+    }
 }
 
 impl Signer {
-    pub fn to_bytes(&self) -> Vec<u8> {
-        self.seed.clone()
+    /// Borrows the HD seed of the signer
+    pub fn seed(&self) -> &[u8] {
+        &self.seed
     }
 }
 
-#[wasm_bindgen()]
-pub fn new_with_object(value: JsValue) {
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn new_with_object(value: JsValue) -> Result<BaseWallet, JsValue> {
     let obj = js_sys::Object::from(value);
 
     let get_string = |key: &str| -> Result<Option<Vec<u8>>, JsValue> {
@@ -273,19 +1084,516 @@ pub fn new_with_object(value: JsValue) {
         if val.is_undefined() || val.is_null() {
             Ok(None)
         } else {
-            let s = val
-                .as_string()
-                .ok_or_else(|| JsValue::from_str(&format!("Field '{key}' must be a string")))?;
-            general_purpose::STANDARD
-                .decode(&s)
-                .map(Some)
-                .map_err(|e| JsValue::from_str(&format!("Failed to decode '{key}': {e}")))
+            let s = val.as_string().ok_or_else(|| {
+                EraError::new(
+                    ErrorCode::InvalidInput,
+                    format!("Field '{key}' must be a string"),
+                )
+            })?;
+            general_purpose::STANDARD.decode(&s).map(Some).map_err(|e| {
+                EraError::new(
+                    ErrorCode::CorruptShard,
+                    format!("Failed to decode '{key}': {e}"),
+                )
+                .into()
+            })
         }
     };
 
-    BaseWallet {
-        project_shard: get_string("project_shard").unwrap(),
-        system_shard: get_string("system_shard").unwrap(),
-        recovery_shard: get_string("recovery_shard").unwrap(),
+    let mut wallet = BaseWallet::with_layout(WalletLayout::default())?;
+    for name in &[
+        WalletLayout::PROJECT,
+        WalletLayout::SYSTEM,
+        WalletLayout::RECOVERY,
+    ] {
+        if let Some(shard) = get_string(&format!("{}_shard", name))? {
+            wallet.insert_shard(name, shard)?;
+        }
+    }
+    Ok(wallet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::ShardSlot;
+
+    // cheap key derivation to keep the tests fast
+    const TEST_KDF: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
     };
+
+    /// A 12 word wallet sealed with pin `222333` and recovery password `password1234567`
+    fn create(layout: &WalletLayout) -> WalletShards {
+        BaseWallet::create_wallet(
+            "222333",
+            "password1234567",
+            KeyLength::Words12,
+            layout,
+            &TEST_KDF,
+        )
+        .unwrap()
+    }
+
+    fn wallet(layout: &WalletLayout, shards: &[(&str, &str)]) -> BaseWallet {
+        let mut wallet = BaseWallet::with_layout(layout.clone()).unwrap();
+        for (name, shard) in shards {
+            let shard = general_purpose::STANDARD.decode(shard).unwrap();
+            wallet.insert_shard(name, shard).unwrap();
+        }
+        wallet
+    }
+
+    #[test]
+    fn test_create_wallet_round_trip() {
+        let layout = WalletLayout::default();
+        let created = create(&layout);
+        assert!(created.shards.is_empty());
+
+        let mut base = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::PROJECT, &created.project_shard),
+                (WalletLayout::SYSTEM, &created.system_shard),
+            ],
+        );
+        let signer = base.build_signer("222333".into(), None).unwrap();
+        assert_eq!(signer.as_mnemonic().split(' ').count(), 12);
+        assert_eq!(
+            blake3::hash(&base.reconstruct_shards_internal().unwrap()).to_string(),
+            created.seed_hash
+        );
+
+        let mut recovery = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::SYSTEM, &created.system_shard),
+                (WalletLayout::RECOVERY, &created.recovery_shard),
+            ],
+        );
+        let recovered = recovery
+            .build_signer("password1234567".into(), Some(false))
+            .unwrap();
+        assert_eq!(recovered.as_mnemonic(), signer.as_mnemonic());
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let layout = WalletLayout::default();
+        let created = create(&layout);
+        let mut base = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::PROJECT, &created.project_shard),
+                (WalletLayout::SYSTEM, &created.system_shard),
+            ],
+        );
+        let error = base.build_signer("222334".into(), None).err().unwrap();
+        assert_eq!(error.code(), ErrorCode::WrongPin);
+
+        let mut base = wallet(
+            &created.layout,
+            &[(WalletLayout::PROJECT, &created.project_shard)],
+        );
+        let error = base.build_signer("222333".into(), None).err().unwrap();
+        assert_eq!(error.code(), ErrorCode::MissingShard);
+    }
+
+    #[test]
+    fn test_wipe() {
+        let layout = WalletLayout::default();
+        let created = create(&layout);
+        let mut base = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::PROJECT, &created.project_shard),
+                (WalletLayout::SYSTEM, &created.system_shard),
+            ],
+        );
+        let mut signer = base.build_signer("222333".into(), None).unwrap();
+
+        base.wipe();
+        assert!(base.shards.iter().all(Option::is_none));
+
+        signer.wipe();
+        assert!(signer.seed().is_empty());
+        assert!(signer.as_mnemonic().is_empty());
+    }
+
+    #[test]
+    fn test_custom_layout_three_of_five() {
+        let names = ["project", "system", "recovery", "device", "custodian"];
+        let slots = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| ShardSlot {
+                name: name.to_string(),
+                index: 3 + i,
+            })
+            .collect();
+        let layout = WalletLayout::new(3, 5, slots);
+        let created = create(&layout);
+        // 16 bytes of entropy do not split into 3 shards evenly
+        assert_eq!(created.layout.secret_length, Some(16));
+
+        let base = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::SYSTEM, &created.system_shard),
+                ("device", &created.shards["device"]),
+                ("custodian", &created.shards["custodian"]),
+            ],
+        );
+        let entropy = base.reconstruct_shards_internal().unwrap();
+        assert_eq!(entropy.len(), 16);
+        assert_eq!(blake3::hash(&entropy).to_string(), created.seed_hash);
+    }
+
+    #[test]
+    fn test_shamir_layout() {
+        let layout = WalletLayout::default().with_scheme(ShardScheme::Shamir);
+        let created = BaseWallet::create_wallet(
+            "222333",
+            "password1234567",
+            KeyLength::Words24,
+            &layout,
+            &TEST_KDF,
+        )
+        .unwrap();
+        assert_eq!(created.layout.scheme, ShardScheme::Shamir);
+
+        let mut base = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::SYSTEM, &created.system_shard),
+                (WalletLayout::RECOVERY, &created.recovery_shard),
+            ],
+        );
+        let signer = base
+            .build_signer("password1234567".into(), Some(false))
+            .unwrap();
+        assert_eq!(signer.as_mnemonic().split(' ').count(), 24);
+        assert!(base.verify_key(created.seed_hash).unwrap());
+    }
+
+    #[test]
+    fn test_shards_are_enveloped() {
+        let layout = WalletLayout::default();
+        let created = create(&layout);
+        let system = general_purpose::STANDARD
+            .decode(&created.system_shard)
+            .unwrap();
+        let envelope = ShardEnvelope::decode(&system).unwrap();
+        assert_eq!(envelope.index, 3);
+        assert!(!envelope.encrypted);
+        assert_eq!(envelope.wallet_id_hex(), created.wallet_id);
+
+        let project = general_purpose::STANDARD
+            .decode(&created.project_shard)
+            .unwrap();
+        let envelope = ShardEnvelope::decode(&project).unwrap();
+        assert_eq!(envelope.index, 2);
+        assert!(envelope.encrypted);
+    }
+
+    #[test]
+    fn test_change_pin() {
+        let layout = WalletLayout::default().with_scheme(ShardScheme::Feldman);
+        let created = create(&layout);
+        let commitments = created.commitments.clone().unwrap();
+        let mut base = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::PROJECT, &created.project_shard),
+                (WalletLayout::SYSTEM, &created.system_shard),
+            ],
+        );
+        base.set_seed_hash(created.seed_hash.clone());
+        base.set_commitments(commitments.clone());
+        let resealed = base.change_pin_internal("222333", "654321").unwrap();
+        let project_shard = resealed.project_shard;
+        // the resealed shard keeps the key derivation parameters of the wallet
+        let envelope =
+            ShardEnvelope::decode(&general_purpose::STANDARD.decode(&project_shard).unwrap())
+                .unwrap();
+        assert_eq!(KdfParams::of(&envelope.payload), Some(TEST_KDF));
+
+        // the resealed shard lies on the same polynomials, the commitments still apply
+        let mut changed = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::PROJECT, &project_shard),
+                (WalletLayout::SYSTEM, &created.system_shard),
+            ],
+        );
+        changed.set_commitments(commitments);
+        let signer = changed.build_signer("654321".into(), None).unwrap();
+        assert!(changed.verify_key(created.seed_hash.clone()).unwrap());
+        let original = base.build_signer("222333".into(), None).unwrap();
+        assert_eq!(signer.as_mnemonic(), original.as_mnemonic());
+    }
+
+    #[test]
+    fn test_recover_with_rotation() {
+        let layout = WalletLayout::default();
+        let created = create(&layout);
+        let mut base = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::SYSTEM, &created.system_shard),
+                (WalletLayout::RECOVERY, &created.recovery_shard),
+            ],
+        );
+        base.set_seed_hash(created.seed_hash.clone());
+        let recovered = base
+            .recover_internal("password1234567", "111111", Some("new-password"))
+            .unwrap();
+        assert_eq!(recovered.seed_hash, created.seed_hash);
+        assert_eq!(recovered.proofs.len(), 2);
+        assert_eq!(recovered.proofs[0].slots, vec!["project", "system"]);
+        assert_eq!(recovered.proofs[1].slots, vec!["system", "recovery"]);
+        // each proof pins down the new envelope of its path
+        let project = general_purpose::STANDARD
+            .decode(&recovered.project_shard)
+            .unwrap();
+        let rotated = general_purpose::STANDARD
+            .decode(recovered.recovery_shard.as_ref().unwrap())
+            .unwrap();
+        let proofs = &recovered.proofs;
+        assert_eq!(
+            proofs[0].envelope_hash,
+            Some(blake3::hash(&project).to_string())
+        );
+        assert_eq!(
+            proofs[1].envelope_hash,
+            Some(blake3::hash(&rotated).to_string())
+        );
+
+        let mut restored = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::PROJECT, &recovered.project_shard),
+                (WalletLayout::SYSTEM, &created.system_shard),
+                (
+                    WalletLayout::RECOVERY,
+                    recovered.recovery_shard.as_ref().unwrap(),
+                ),
+            ],
+        );
+        restored.build_signer("111111".into(), None).unwrap();
+        assert!(restored.verify_key(created.seed_hash).unwrap());
+    }
+
+    #[test]
+    fn test_recover_ignores_corrupt_project_shard() {
+        let layout = WalletLayout::default();
+        let created = create(&layout);
+        let mut base = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::PROJECT, &created.project_shard),
+                (WalletLayout::SYSTEM, &created.system_shard),
+                (WalletLayout::RECOVERY, &created.recovery_shard),
+            ],
+        );
+        base.set_seed_hash(created.seed_hash.clone());
+        base.decrypt_slot(WalletLayout::PROJECT, "222333").unwrap();
+        let index = layout.position(WalletLayout::PROJECT).unwrap();
+        base.shards[index].as_mut().unwrap()[0] ^= 1;
+        // the corrupt shard breaks any reconstruction that includes it
+        assert!(base.check_seed(base.opened_shards().unwrap()).is_err());
+
+        let recovered = base
+            .recover_internal("password1234567", "111111", None)
+            .unwrap();
+        assert_eq!(recovered.seed_hash, created.seed_hash);
+    }
+
+    #[test]
+    fn test_refresh_bumps_generation() {
+        let layout = WalletLayout::default().with_scheme(ShardScheme::Shamir);
+        let created = create(&layout);
+        assert_eq!(created.generation, 0);
+        let mut base = wallet(
+            &created.layout,
+            &[
+                (WalletLayout::PROJECT, &created.project_shard),
+                (WalletLayout::SYSTEM, &created.system_shard),
+            ],
+        );
+        base.set_seed_hash(created.seed_hash.clone());
+        let refreshed = base.refresh_internal("222333", "password1234567").unwrap();
+        assert_eq!(refreshed.generation, 1);
+        assert_eq!(refreshed.wallet_id, created.wallet_id);
+        assert_eq!(refreshed.seed_hash, created.seed_hash);
+        assert_ne!(refreshed.system_shard, created.system_shard);
+
+        let mut next = wallet(
+            &refreshed.layout,
+            &[
+                (WalletLayout::SYSTEM, &refreshed.system_shard),
+                (WalletLayout::RECOVERY, &refreshed.recovery_shard),
+            ],
+        );
+        next.build_signer("password1234567".into(), Some(false))
+            .unwrap();
+        assert!(next.verify_key(created.seed_hash).unwrap());
+
+        // a shard of the previous generation is refused
+        let mut mixed = wallet(
+            &refreshed.layout,
+            &[(WalletLayout::SYSTEM, &refreshed.system_shard)],
+        );
+        let stale = general_purpose::STANDARD
+            .decode(&created.recovery_shard)
+            .unwrap();
+        let error = mixed
+            .insert_shard(WalletLayout::RECOVERY, stale.clone())
+            .err()
+            .unwrap();
+        assert_eq!(
+            error,
+            EnvelopeError::GenerationMismatch {
+                expected: 1,
+                found: 0
+            }
+            .into()
+        );
+
+        // and so is its payload stripped of the envelope
+        let payload = ShardEnvelope::decode(&stale).unwrap().payload;
+        let error = mixed
+            .insert_shard(WalletLayout::RECOVERY, payload.clone())
+            .err()
+            .unwrap();
+        assert_eq!(error, EnvelopeError::MixedFormats.into());
+
+        // an enveloped shard does not join bare shards either
+        let mut bare = BaseWallet::with_layout(created.layout.clone()).unwrap();
+        bare.insert_shard(WalletLayout::RECOVERY, payload).unwrap();
+        let system = general_purpose::STANDARD
+            .decode(&refreshed.system_shard)
+            .unwrap();
+        let error = bare
+            .insert_shard(WalletLayout::SYSTEM, system)
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), ErrorCode::ShardMismatch);
+    }
+
+    #[test]
+    fn test_feldman_commitments() {
+        let layout = WalletLayout::default().with_scheme(ShardScheme::Feldman);
+        let created = create(&layout);
+        assert_eq!(created.layout.secret_length, Some(16));
+        let commitments = created.commitments.clone().unwrap();
+        // 16 bytes of entropy fit one chunk, on a polynomial of 2 coefficients
+        assert_eq!(commitments.coefficients.len(), 1);
+        assert_eq!(commitments.coefficients[0].len(), 2);
+        assert!(create(&WalletLayout::default()).commitments.is_none());
+
+        let system = general_purpose::STANDARD
+            .decode(&created.system_shard)
+            .unwrap();
+        assert_eq!(commitments.verify(&system), Ok(()));
+        let project = general_purpose::STANDARD
+            .decode(&created.project_shard)
+            .unwrap();
+        assert_eq!(
+            commitments.verify(&project),
+            Err(EnvelopeError::SealedShard(2))
+        );
+
+        let mut base = BaseWallet::with_layout(created.layout.clone()).unwrap();
+        base.set_commitments(commitments.clone());
+        base.insert_shard(WalletLayout::PROJECT, project.clone())
+            .unwrap();
+        base.insert_shard(WalletLayout::SYSTEM, system.clone())
+            .unwrap();
+        base.build_signer("222333".into(), None).unwrap();
+        assert!(base.verify_key(created.seed_hash.clone()).unwrap());
+
+        // a dealer handing out a system shard of another sharing of the same seed, in a valid
+        // envelope of the wallet, is caught before any pin is spent
+        let entropy = base.reconstruct_shards_internal().unwrap();
+        let dealt = feldman::split_secret_internal(&entropy, 2, 5).unwrap();
+        let mut envelope = ShardEnvelope::decode(&system).unwrap();
+        envelope.payload = dealt.shares[0].clone();
+        let mut dealer = BaseWallet::with_layout(created.layout.clone()).unwrap();
+        dealer.set_commitments(commitments.clone());
+        let error = dealer
+            .insert_shard(WalletLayout::SYSTEM, envelope.encode())
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), ErrorCode::CorruptShard);
+
+        // a system shard from another wallet fails before anything is decrypted
+        let other = create(&layout);
+        let foreign = general_purpose::STANDARD
+            .decode(&other.system_shard)
+            .unwrap();
+        assert_eq!(
+            commitments.verify(&foreign),
+            Err(EnvelopeError::WalletMismatch)
+        );
+
+        // an encrypted shard of another sharing is caught once it is decrypted
+        let mut envelope = ShardEnvelope::decode(&project).unwrap();
+        envelope.payload = encrypt_shard(&dealt.shares[2], b"222333", &TEST_KDF).unwrap();
+        let mut forged = wallet(
+            &created.layout,
+            &[(WalletLayout::SYSTEM, &created.system_shard)],
+        );
+        forged.set_commitments(commitments);
+        forged
+            .insert_shard(WalletLayout::PROJECT, envelope.encode())
+            .unwrap();
+        let error = forged.build_signer("222333".into(), None).err().unwrap();
+        assert_eq!(error.code(), ErrorCode::CorruptShard);
+    }
+
+    #[test]
+    fn test_unlock_with_pin_oprf() {
+        use crate::oprf::OprfServer;
+
+        let mut server = OprfServer::new(None);
+        server.register_internal("alice").unwrap();
+        let unlock = PinUnlock::start("alice", b"222333").unwrap();
+        let response = server
+            .evaluate_internal("alice", &unlock.request())
+            .unwrap();
+        let key = hex::encode(unlock.finish_internal(&response).unwrap().as_slice());
+
+        let layout = WalletLayout::default();
+        let created = BaseWallet::create_wallet(
+            &key,
+            "password1234567",
+            KeyLength::Words12,
+            &layout,
+            &TEST_KDF,
+        )
+        .unwrap();
+        let shards = [
+            (WalletLayout::PROJECT, created.project_shard.as_str()),
+            (WalletLayout::SYSTEM, created.system_shard.as_str()),
+        ];
+
+        // the plain pin does not open the project shard
+        let project = general_purpose::STANDARD
+            .decode(&created.project_shard)
+            .unwrap();
+        let envelope = ShardEnvelope::decode(&project).unwrap();
+        assert!(decrypt_shard(&envelope.payload, b"222333").is_err());
+
+        let mut base = wallet(&created.layout, &shards);
+        let unlock = PinUnlock::start("alice", b"222333").unwrap();
+        let response = server
+            .evaluate_internal("alice", &unlock.request())
+            .unwrap();
+        let signer = base.unlock_signer(unlock, &response).unwrap();
+        assert!(base.verify_key(created.seed_hash.clone()).unwrap());
+        assert_eq!(signer.as_mnemonic().split(' ').count(), 12);
+    }
 }
@@ -0,0 +1,317 @@
+use std::fmt::{self, Display};
+
+use crate::chains::errors::{BitcoinError, CosmosError, EthereumError, PolkadotError, SolanaError};
+use crate::cryptography::CipherError;
+use crate::envelope::EnvelopeError;
+use crate::erasure_coding::ErasureError;
+use crate::layout::LayoutError;
+use crate::oprf::OprfError;
+use crate::shamir::ShamirError;
+#[cfg(feature = "wasm")]
+use crate::{wasm_bindgen, JsValue};
+
+/// Stable error codes, surfaced to javascript as the `code` of an `EraError`.
+/// The string form of a code never changes, new codes may be added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The pin or password does not open the shard
+    WrongPin,
+    /// Not enough shards to rebuild the wallet, or a required shard is absent
+    MissingShard,
+    /// A shard is malformed, altered or inconsistent with the others
+    CorruptShard,
+    /// A shard belongs to another wallet, scheme, slot or generation
+    ShardMismatch,
+    /// The rebuilt seed does not match the seed hash of the wallet
+    SeedMismatch,
+    /// The shard or ciphertext was written by a newer version of the format
+    UnsupportedVersion,
+    /// The layout descriptor or threshold parameters are invalid
+    InvalidLayout,
+    /// The derivation path can not be parsed or derived
+    InvalidPath,
+    /// The mnemonic phrase is invalid
+    InvalidMnemonic,
+    /// A key or seed has the wrong length or format
+    InvalidKey,
+    /// An argument could not be parsed
+    InvalidInput,
+    /// The server refused further pin attempts
+    RateLimited,
+    /// The platform random number generator failed
+    RandomnessError,
+    /// An operation failed for a reason that is not the caller's fault
+    Internal,
+}
+
+impl ErrorCode {
+    /// Every code, in declaration order
+    pub const ALL: [ErrorCode; 14] = [
+        ErrorCode::WrongPin,
+        ErrorCode::MissingShard,
+        ErrorCode::CorruptShard,
+        ErrorCode::ShardMismatch,
+        ErrorCode::SeedMismatch,
+        ErrorCode::UnsupportedVersion,
+        ErrorCode::InvalidLayout,
+        ErrorCode::InvalidPath,
+        ErrorCode::InvalidMnemonic,
+        ErrorCode::InvalidKey,
+        ErrorCode::InvalidInput,
+        ErrorCode::RateLimited,
+        ErrorCode::RandomnessError,
+        ErrorCode::Internal,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::WrongPin => "WRONG_PIN",
+            ErrorCode::MissingShard => "MISSING_SHARD",
+            ErrorCode::CorruptShard => "CORRUPT_SHARD",
+            ErrorCode::ShardMismatch => "SHARD_MISMATCH",
+            ErrorCode::SeedMismatch => "SEED_MISMATCH",
+            ErrorCode::UnsupportedVersion => "UNSUPPORTED_VERSION",
+            ErrorCode::InvalidLayout => "INVALID_LAYOUT",
+            ErrorCode::InvalidPath => "INVALID_PATH",
+            ErrorCode::InvalidMnemonic => "INVALID_MNEMONIC",
+            ErrorCode::InvalidKey => "INVALID_KEY",
+            ErrorCode::InvalidInput => "INVALID_INPUT",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::RandomnessError => "RANDOMNESS_ERROR",
+            ErrorCode::Internal => "INTERNAL",
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The error returned by every fallible operation of the crate.
+/// Module errors such as `EnvelopeError` convert into it with their stable code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EraError {
+    code: ErrorCode,
+    message: String,
+}
+
+impl EraError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::error::Error for EraError {}
+
+impl Display for EraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(inline_js = r#"
+export class EraError extends Error {
+    constructor(code, message) {
+        super(message);
+        this.name = "EraError";
+        this.code = code;
+    }
+}
+"#)]
+extern "C" {
+    #[wasm_bindgen(extends = js_sys::Error, js_name = EraError)]
+    type JsEraError;
+
+    #[wasm_bindgen(constructor, js_class = EraError)]
+    fn new(code: &str, message: &str) -> JsEraError;
+}
+
+#[cfg(feature = "wasm")]
+impl From<EraError> for JsValue {
+    /// Surfaces the error as an `EraError`, a javascript `Error` with a `code` property
+    fn from(error: EraError) -> Self {
+        JsEraError::new(error.code.as_str(), &error.message).into()
+    }
+}
+
+/// Maps a module error to its code, keeping its message
+macro_rules! era_error_from {
+    ($error:ty, |$e:ident| $code:expr) => {
+        impl From<$error> for EraError {
+            fn from($e: $error) -> Self {
+                let code = $code;
+                EraError::new(code, $e.to_string())
+            }
+        }
+
+        #[cfg(feature = "wasm")]
+        impl From<$error> for JsValue {
+            fn from(error: $error) -> Self {
+                EraError::from(error).into()
+            }
+        }
+    };
+}
+
+era_error_from!(LayoutError, |e| match e {
+    LayoutError::UnknownSlot(_)
+    | LayoutError::InvalidShardCount
+    | LayoutError::SlotOutOfRange(_)
+    | LayoutError::DuplicateSlot(_)
+    | LayoutError::DataShardSlot(_)
+    | LayoutError::TooFewSlots(_) => ErrorCode::InvalidLayout,
+});
+
+era_error_from!(ShamirError, |e| match e {
+    ShamirError::InvalidThreshold => ErrorCode::InvalidLayout,
+    ShamirError::InsufficientShares => ErrorCode::MissingShard,
+    ShamirError::InconsistentShares => ErrorCode::CorruptShard,
+    ShamirError::RandomnessError => ErrorCode::RandomnessError,
+});
+
+era_error_from!(ErasureError, |e| match e {
+    ErasureError::InvalidShardCount => ErrorCode::InvalidLayout,
+    ErasureError::MisalignedData => ErrorCode::InvalidInput,
+    ErasureError::TooFewShards => ErrorCode::MissingShard,
+    ErasureError::VerificationFailed => ErrorCode::CorruptShard,
+});
+
+era_error_from!(EnvelopeError, |e| match e {
+    EnvelopeError::InvalidEncoding
+    | EnvelopeError::ChecksumMismatch
+    | EnvelopeError::CommitmentMismatch(_) => ErrorCode::CorruptShard,
+    EnvelopeError::UnsupportedVersion(_) | EnvelopeError::UnknownScheme(_) => {
+        ErrorCode::UnsupportedVersion
+    }
+    EnvelopeError::WalletMismatch
+    | EnvelopeError::SchemeMismatch
+    | EnvelopeError::ThresholdMismatch
+    | EnvelopeError::IndexMismatch { .. }
+    | EnvelopeError::GenerationMismatch { .. }
+    | EnvelopeError::MixedFormats => ErrorCode::ShardMismatch,
+    EnvelopeError::InvalidCommitments | EnvelopeError::SealedShard(_) => ErrorCode::InvalidInput,
+});
+
+era_error_from!(CipherError, |e| match e {
+    CipherError::DecryptionFailed => ErrorCode::WrongPin,
+    CipherError::InvalidCiphertext => ErrorCode::CorruptShard,
+    CipherError::UnsupportedVersion(_) => ErrorCode::UnsupportedVersion,
+    CipherError::InvalidParameters => ErrorCode::InvalidInput,
+    CipherError::EncryptionFailed => ErrorCode::Internal,
+    CipherError::RandomnessError => ErrorCode::RandomnessError,
+});
+
+era_error_from!(OprfError, |e| match e {
+    OprfError::InvalidElement | OprfError::UnknownKey(_) | OprfError::KeyExists(_) => {
+        ErrorCode::InvalidInput
+    }
+    OprfError::Locked(_) => ErrorCode::RateLimited,
+    OprfError::RandomnessError => ErrorCode::RandomnessError,
+});
+
+era_error_from!(PolkadotError, |e| match e {
+    PolkadotError::InvalidKey => ErrorCode::InvalidKey,
+    PolkadotError::InvalidSecretUri => ErrorCode::InvalidPath,
+    PolkadotError::UnknownScheme(_)
+    | PolkadotError::UnknownNetwork(_)
+    | PolkadotError::InvalidAddress(_)
+    | PolkadotError::InvalidExtrinsic(_) => ErrorCode::InvalidInput,
+});
+
+era_error_from!(EthereumError, |e| match e {
+    EthereumError::InvalidKey => ErrorCode::InvalidKey,
+    EthereumError::InvalidHash
+    | EthereumError::InvalidSignature
+    | EthereumError::InvalidTransaction(_)
+    | EthereumError::InvalidTypedData(_) => ErrorCode::InvalidInput,
+});
+
+era_error_from!(BitcoinError, |e| match e {
+    BitcoinError::InvalidKey => ErrorCode::InvalidKey,
+    BitcoinError::UnknownNetwork(_)
+    | BitcoinError::UnknownAddressType(_)
+    | BitcoinError::InvalidPsbt(_)
+    | BitcoinError::UnsupportedSighash(_) => ErrorCode::InvalidInput,
+});
+
+era_error_from!(SolanaError, |e| match e {
+    SolanaError::InvalidKey => ErrorCode::InvalidKey,
+    SolanaError::InvalidMessage(_) | SolanaError::NotASigner => ErrorCode::InvalidInput,
+});
+
+era_error_from!(CosmosError, |e| match e {
+    CosmosError::InvalidKey => ErrorCode::InvalidKey,
+    CosmosError::InvalidPrefix(_) | CosmosError::InvalidSignDoc(_) => ErrorCode::InvalidInput,
+});
+
+impl From<getrandom::Error> for EraError {
+    fn from(error: getrandom::Error) -> Self {
+        EraError::new(
+            ErrorCode::RandomnessError,
+            format!("Could not generate randomness: {}", error),
+        )
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl From<serde_wasm_bindgen::Error> for EraError {
+    fn from(error: serde_wasm_bindgen::Error) -> Self {
+        EraError::new(ErrorCode::InvalidInput, error.to_string())
+    }
+}
+
+/// Reads an optional serde argument passed from javascript
+#[cfg(feature = "wasm")]
+pub fn from_js<T: serde::de::DeserializeOwned>(value: JsValue, what: &str) -> Result<T, EraError> {
+    serde_wasm_bindgen::from_value(value)
+        .map_err(|e| EraError::new(ErrorCode::InvalidInput, format!("Invalid {}: {}", what, e)))
+}
+
+/// Lists every error code, so javascript can check a `code` without hardcoding strings
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = errorCodes)]
+pub fn error_codes() -> Vec<JsValue> {
+    ErrorCode::ALL
+        .iter()
+        .map(|code| JsValue::from_str(code.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_errors_keep_their_code() {
+        let error = EraError::from(CipherError::DecryptionFailed);
+        assert_eq!(error.code(), ErrorCode::WrongPin);
+        assert_eq!(error.code().as_str(), "WRONG_PIN");
+
+        let error = EraError::from(EnvelopeError::GenerationMismatch {
+            expected: 1,
+            found: 0,
+        });
+        assert_eq!(error.code(), ErrorCode::ShardMismatch);
+        assert_eq!(
+            error.to_string(),
+            "SHARD_MISMATCH: Shard generation mismatch: expected 1, found 0"
+        );
+
+        let error = EraError::from(ShamirError::InsufficientShares);
+        assert_eq!(error.code(), ErrorCode::MissingShard);
+    }
+}
@@ -0,0 +1,889 @@
+//! C ABI over the native API, for the iOS and Android apps. The declarations live in `include/era.h`.
+//!
+//! Every fallible function returns `ERA_OK` (0) or the position of its `ErrorCode` in
+//! `ErrorCode::ALL` plus one, and keeps the message for `era_last_error`. Objects are opaque
+//! handles released with their `_free` function, strings with `era_string_free` and byte buffers
+//! with `era_bytes_free`. A panic never crosses the boundary, it is reported as `ERA_INTERNAL`.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use zeroize::Zeroize;
+
+use crate::chains::bitcoin::{AddressType, BitcoinSigner};
+use crate::chains::cosmos::{CosmosSigner, SignDoc};
+use crate::chains::eip712::TypedData;
+use crate::chains::ethereum::EthereumSigner;
+use crate::chains::evm::Transaction;
+use crate::chains::extrinsic::Extrinsic;
+use crate::chains::polkadot::PolkadotSigner;
+use crate::chains::solana::SolanaSigner;
+use crate::chains::ss58;
+use crate::crypto::crypto::Ecdsa;
+use crate::errors::{EraError, ErrorCode};
+use crate::key::Account;
+use crate::layout::WalletLayout;
+use crate::wallet::{BaseWallet, Signer};
+use crate::{general_purpose, Engine};
+
+pub const ERA_OK: i32 = 0;
+
+/// A byte buffer owned by the library
+#[repr(C)]
+pub struct EraBytes {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// The status returned across the C ABI for an error code
+pub fn status(code: ErrorCode) -> i32 {
+    ErrorCode::ALL
+        .iter()
+        .position(|other| *other == code)
+        .map_or(-1, |position| position as i32 + 1)
+}
+
+/// Runs `f`, turning its error or panic into a status and keeping the message.
+/// A success clears the message of an earlier error.
+fn call(f: impl FnOnce() -> Result<(), EraError>) -> i32 {
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        Err(EraError::new(
+            ErrorCode::Internal,
+            "Unexpected panic in era-js",
+        ))
+    });
+    match result {
+        Ok(()) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = None);
+            ERA_OK
+        }
+        Err(error) => {
+            let status = status(error.code());
+            let message = CString::new(error.to_string()).ok();
+            LAST_ERROR.with(|last| *last.borrow_mut() = message);
+            status
+        }
+    }
+}
+
+fn null_pointer(what: &str) -> EraError {
+    EraError::new(ErrorCode::InvalidInput, format!("{} is null", what))
+}
+
+/// Borrows a C string argument
+unsafe fn text<'a>(value: *const c_char, what: &str) -> Result<&'a str, EraError> {
+    if value.is_null() {
+        return Err(null_pointer(what));
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|_| EraError::new(ErrorCode::InvalidInput, format!("{} is not UTF-8", what)))
+}
+
+/// Borrows an object handle
+unsafe fn handle<'a, T>(value: *const T, what: &str) -> Result<&'a T, EraError> {
+    value.as_ref().ok_or_else(|| null_pointer(what))
+}
+
+unsafe fn handle_mut<'a, T>(value: *mut T, what: &str) -> Result<&'a mut T, EraError> {
+    value.as_mut().ok_or_else(|| null_pointer(what))
+}
+
+/// Writes an output argument
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), EraError> {
+    if out.is_null() {
+        return Err(null_pointer("output"));
+    }
+    out.write(value);
+    Ok(())
+}
+
+unsafe fn slice<'a>(value: *const u8, len: usize, what: &str) -> Result<&'a [u8], EraError> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if value.is_null() {
+        return Err(null_pointer(what));
+    }
+    Ok(std::slice::from_raw_parts(value, len))
+}
+
+fn c_string(value: String) -> Result<*mut c_char, EraError> {
+    CString::new(value)
+        .map(CString::into_raw)
+        .map_err(|_| EraError::new(ErrorCode::Internal, "String contains a nul byte"))
+}
+
+fn parse<T: DeserializeOwned>(value: &str, what: &str) -> Result<T, EraError> {
+    serde_json::from_str(value)
+        .map_err(|e| EraError::new(ErrorCode::InvalidInput, format!("Invalid {}: {}", what, e)))
+}
+
+fn json(value: &impl Serialize) -> Result<*mut c_char, EraError> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| EraError::new(ErrorCode::Internal, e.to_string()))?;
+    c_string(json)
+}
+
+fn bytes(value: Vec<u8>) -> EraBytes {
+    let mut value = value.into_boxed_slice();
+    let bytes = EraBytes {
+        data: value.as_mut_ptr(),
+        len: value.len(),
+    };
+    std::mem::forget(value);
+    bytes
+}
+
+/// The message of the last error on this thread, or null. Free it with `era_string_free`.
+#[no_mangle]
+pub extern "C" fn era_last_error() -> *mut c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null_mut(), |message| message.clone().into_raw())
+    })
+}
+
+/// # Safety
+/// `value` must come from this library and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn era_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        let mut value = CString::from_raw(value).into_bytes();
+        value.zeroize();
+    }
+}
+
+/// # Safety
+/// `value` must come from this library and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn era_bytes_free(value: EraBytes) {
+    if !value.data.is_null() {
+        let mut value = Box::from_raw(ptr::slice_from_raw_parts_mut(value.data, value.len));
+        value.zeroize();
+    }
+}
+
+/// Creates an empty wallet. `layout_json` may be null for the default layout.
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_wallet_new(
+    layout_json: *const c_char,
+    out: *mut *mut BaseWallet,
+) -> i32 {
+    call(|| {
+        let layout = if layout_json.is_null() {
+            WalletLayout::default()
+        } else {
+            parse(text(layout_json, "layout")?, "wallet layout")?
+        };
+        let wallet = BaseWallet::with_layout(layout)?;
+        write(out, Box::into_raw(Box::new(wallet)))
+    })
+}
+
+/// Places a base64 encoded shard in the named slot
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_wallet_insert_shard(
+    wallet: *mut BaseWallet,
+    name: *const c_char,
+    shard_base64: *const c_char,
+) -> i32 {
+    call(|| {
+        let wallet = handle_mut(wallet, "wallet")?;
+        let shard = general_purpose::STANDARD
+            .decode(text(shard_base64, "shard")?)
+            .map_err(|e| {
+                EraError::new(
+                    ErrorCode::CorruptShard,
+                    format!("Invalid base64 shard: {}", e),
+                )
+            })?;
+        wallet.insert_shard(text(name, "name")?, shard)
+    })
+}
+
+/// Rebuilds the entropy from the decrypted shards of the wallet
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_wallet_reconstruct(
+    wallet: *const BaseWallet,
+    out: *mut EraBytes,
+) -> i32 {
+    call(|| {
+        let entropy = handle(wallet, "wallet")?.reconstruct_shards_internal()?;
+        write(out, bytes(entropy.to_vec()))
+    })
+}
+
+/// Decrypts the project shard with the pin, or the recovery shard when `recovery` is set,
+/// and builds the signer
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_wallet_build_signer(
+    wallet: *mut BaseWallet,
+    password: *const c_char,
+    recovery: bool,
+    out: *mut *mut Signer,
+) -> i32 {
+    call(|| {
+        let wallet = handle_mut(wallet, "wallet")?;
+        let signer = wallet.build_signer(text(password, "password")?.into(), Some(!recovery))?;
+        write(out, Box::into_raw(Box::new(signer)))
+    })
+}
+
+/// Checks the entropy rebuilt from the shards against a seed hash
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_wallet_verify_key(
+    wallet: *const BaseWallet,
+    seed_hash: *const c_char,
+    out: *mut bool,
+) -> i32 {
+    call(|| {
+        let valid = handle(wallet, "wallet")?.verify_key(text(seed_hash, "seed hash")?.into())?;
+        write(out, valid)
+    })
+}
+
+/// # Safety
+/// `wallet` must come from `era_wallet_new` and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn era_wallet_free(wallet: *mut BaseWallet) {
+    if !wallet.is_null() {
+        drop(Box::from_raw(wallet));
+    }
+}
+
+/// The mnemonic of the signer
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_signer_mnemonic(signer: *const Signer, out: *mut *mut c_char) -> i32 {
+    call(|| write(out, c_string(handle(signer, "signer")?.as_mnemonic())?))
+}
+
+/// The sr25519 signer of the wallet at a secret URI derivation like `//polkadot//0`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_signer_to_polkadot(
+    signer: *const Signer,
+    uri: *const c_char,
+    out: *mut *mut PolkadotSigner,
+) -> i32 {
+    call(|| {
+        let uri = text(uri, "uri")?.to_string();
+        let polkadot = handle(signer, "signer")?.to_polkadot_signer(Some(uri))?;
+        write(out, Box::into_raw(Box::new(polkadot)))
+    })
+}
+
+/// The substrate signer of the wallet at a secret URI derivation with the `sr25519`,
+/// `ed25519` or `ecdsa` scheme
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_signer_to_substrate(
+    signer: *const Signer,
+    scheme: *const c_char,
+    uri: *const c_char,
+    out: *mut *mut PolkadotSigner,
+) -> i32 {
+    call(|| {
+        let uri = text(uri, "uri")?.to_string();
+        let substrate =
+            handle(signer, "signer")?.to_substrate_signer(text(scheme, "scheme")?, Some(uri))?;
+        write(out, Box::into_raw(Box::new(substrate)))
+    })
+}
+
+/// The ethereum signer at a BIP-32 path, `m/44'/60'/0'/0/i` for account `i`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_signer_to_ethereum(
+    signer: *const Signer,
+    path: *const c_char,
+    out: *mut *mut EthereumSigner,
+) -> i32 {
+    call(|| {
+        let ethereum = handle(signer, "signer")?.to_ethereum_signer(text(path, "path")?)?;
+        write(out, Box::into_raw(Box::new(ethereum)))
+    })
+}
+
+/// The bitcoin signer on `mainnet`, `testnet`, `signet` or `regtest`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_signer_to_bitcoin(
+    signer: *const Signer,
+    network: *const c_char,
+    out: *mut *mut BitcoinSigner,
+) -> i32 {
+    call(|| {
+        let bitcoin = handle(signer, "signer")?.to_bitcoin_signer(text(network, "network")?)?;
+        write(out, Box::into_raw(Box::new(bitcoin)))
+    })
+}
+
+/// The ed25519 solana signer of account `index`, at `m/44'/501'/index'/0'`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_signer_to_solana(
+    signer: *const Signer,
+    index: u32,
+    out: *mut *mut SolanaSigner,
+) -> i32 {
+    call(|| {
+        let solana = handle(signer, "signer")?.to_solana_signer(index)?;
+        write(out, Box::into_raw(Box::new(solana)))
+    })
+}
+
+/// The cosmos SDK signer of account `index` at `m/44'/coin_type'/0'/0/index`, with bech32
+/// addresses under `prefix`. A `coin_type` of 0 selects the default, 118.
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_signer_to_cosmos(
+    signer: *const Signer,
+    prefix: *const c_char,
+    index: u32,
+    coin_type: u32,
+    out: *mut *mut CosmosSigner,
+) -> i32 {
+    call(|| {
+        let prefix = text(prefix, "prefix")?;
+        // 0 is the bitcoin coin type, never a cosmos one, so C callers pass it for the default
+        let coin_type = if coin_type == 0 {
+            None
+        } else {
+            Some(coin_type)
+        };
+        let cosmos = handle(signer, "signer")?.to_cosmos_signer(prefix, index, coin_type)?;
+        write(out, Box::into_raw(Box::new(cosmos)))
+    })
+}
+
+/// # Safety
+/// `signer` must come from this library and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn era_signer_free(signer: *mut Signer) {
+    if !signer.is_null() {
+        drop(Box::from_raw(signer));
+    }
+}
+
+/// Creates an account from an English mnemonic
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_account_from_mnemonic(
+    mnemonic: *const c_char,
+    out: *mut *mut Account,
+) -> i32 {
+    call(|| {
+        let account: Account = text(mnemonic, "mnemonic")?.parse()?;
+        write(out, Box::into_raw(Box::new(account)))
+    })
+}
+
+/// Derives the BIP-32 key at `path`, returned as a JSON `KeyObject`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_account_derive(
+    account: *const Account,
+    path: *const c_char,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let key = handle(account, "account")?.generate_extended_key(text(path, "path")?)?;
+        write(out_json, json(&key)?)
+    })
+}
+
+/// # Safety
+/// `account` must come from this library and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn era_account_free(account: *mut Account) {
+    if !account.is_null() {
+        drop(Box::from_raw(account));
+    }
+}
+
+/// The keys and address of the polkadot signer, returned as a JSON `KeyObject`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_polkadot_key(
+    signer: *const PolkadotSigner,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| write(out_json, json(&handle(signer, "signer")?.get_key()?)?))
+}
+
+/// Signs a message, the signature is 64 bytes, or 65 for ecdsa
+///
+/// # Safety
+/// Pointers must be valid for their C type, `message` for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn era_polkadot_sign(
+    signer: *const PolkadotSigner,
+    message: *const u8,
+    len: usize,
+    out: *mut EraBytes,
+) -> i32 {
+    call(|| {
+        let message = slice(message, len, "message")?;
+        let signature = handle(signer, "signer")?.sign(message)?;
+        write(out, bytes(signature))
+    })
+}
+
+/// Signs a message, the signature is prefixed with its `MultiSignature` variant
+///
+/// # Safety
+/// Pointers must be valid for their C type, `message` for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn era_polkadot_multi_signature(
+    signer: *const PolkadotSigner,
+    message: *const u8,
+    len: usize,
+    out: *mut EraBytes,
+) -> i32 {
+    call(|| {
+        let message = slice(message, len, "message")?;
+        let signature = handle(signer, "signer")?.multi_signature(message)?;
+        write(out, bytes(signature))
+    })
+}
+
+/// Builds and signs an extrinsic given as JSON, returns `{ extrinsic, hash }`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_polkadot_sign_extrinsic(
+    signer: *const PolkadotSigner,
+    extrinsic_json: *const c_char,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let extrinsic: Extrinsic = parse(text(extrinsic_json, "extrinsic")?, "extrinsic")?;
+        let signed = handle(signer, "signer")?.build_extrinsic(&extrinsic)?;
+        write(out_json, json(&signed)?)
+    })
+}
+
+/// Selects the network of the addresses, by SS58 registry name or prefix number
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_polkadot_set_network(
+    signer: *mut PolkadotSigner,
+    network: *const c_char,
+) -> i32 {
+    call(|| handle_mut(signer, "signer")?.set_network(text(network, "network")?))
+}
+
+/// Re-encodes an SS58 address for the network given by registry name or prefix number
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_ss58_reencode(
+    address: *const c_char,
+    network: *const c_char,
+    out: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let address = ss58::reencode(text(address, "address")?, text(network, "network")?)?;
+        write(out, c_string(address)?)
+    })
+}
+
+/// Checks the checksum and prefix of an SS58 address, and its network unless `network` is
+/// null. Returns JSON `{ prefix, network, account_id }`.
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_ss58_validate(
+    address: *const c_char,
+    network: *const c_char,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let network = if network.is_null() {
+            None
+        } else {
+            Some(text(network, "network")?)
+        };
+        let info = ss58::validate(text(address, "address")?, network)?;
+        write(out_json, json(&info)?)
+    })
+}
+
+/// # Safety
+/// `signer` must come from this library and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn era_polkadot_free(signer: *mut PolkadotSigner) {
+    if !signer.is_null() {
+        drop(Box::from_raw(signer));
+    }
+}
+
+/// The keys and EIP-55 address of the ethereum signer, returned as a JSON `KeyObject`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_ethereum_key(
+    signer: *const EthereumSigner,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| write(out_json, json(&handle(signer, "signer")?.get_key()?)?))
+}
+
+/// Signs a message with the EIP-191 prefix, the signature is the `r || s || v` hex
+///
+/// # Safety
+/// Pointers must be valid for their C type, `message` for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn era_ethereum_personal_sign(
+    signer: *const EthereumSigner,
+    message: *const u8,
+    len: usize,
+    out: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let message = slice(message, len, "message")?;
+        write(
+            out,
+            c_string(handle(signer, "signer")?.personal_sign(message)?)?,
+        )
+    })
+}
+
+/// Signs a 32 byte hash, the signature is the `r || s || v` hex
+///
+/// # Safety
+/// Pointers must be valid for their C type, `hash` for 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn era_ethereum_sign_hash(
+    signer: *const EthereumSigner,
+    hash: *const u8,
+    out: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let hash = slice(hash, 32, "hash")?;
+        write(out, c_string(handle(signer, "signer")?.sign_hash(hash)?)?)
+    })
+}
+
+/// Signs a JSON transaction, returned as JSON `{ raw, hash }`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_ethereum_sign_transaction(
+    signer: *const EthereumSigner,
+    transaction_json: *const c_char,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let transaction: Transaction =
+            parse(text(transaction_json, "transaction")?, "transaction")?;
+        let signed = handle(signer, "signer")?.sign_tx(&transaction)?;
+        write(out_json, json(&signed)?)
+    })
+}
+
+/// Signs EIP-712 typed data, the JSON of `eth_signTypedData_v4`. The signature is the
+/// `r || s || v` hex.
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_ethereum_sign_typed_data(
+    signer: *const EthereumSigner,
+    typed_data_json: *const c_char,
+    out: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let typed_data = TypedData::from_json(text(typed_data_json, "typed data")?)?;
+        let signature = handle(signer, "signer")?.sign_typed(&typed_data)?;
+        write(out, c_string(signature.to_hex())?)
+    })
+}
+
+/// Hashes EIP-712 typed data without signing it, returned as JSON
+/// `{ domain_separator, struct_hash, hash }`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_ethereum_hash_typed_data(
+    typed_data_json: *const c_char,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let typed_data = TypedData::from_json(text(typed_data_json, "typed data")?)?;
+        write(out_json, json(&typed_data.hash()?)?)
+    })
+}
+
+/// # Safety
+/// `signer` must come from this library and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn era_ethereum_free(signer: *mut EthereumSigner) {
+    if !signer.is_null() {
+        drop(Box::from_raw(signer));
+    }
+}
+
+/// The keys and address of receive address `index` of an address type, returned as a JSON
+/// `KeyObject`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_bitcoin_key(
+    signer: *const BitcoinSigner,
+    address_type: *const c_char,
+    index: u32,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let signer = handle(signer, "signer")?;
+        let address_type: AddressType = text(address_type, "address type")?.parse()?;
+        let key = signer.get_key(address_type, &signer.default_path(address_type, index))?;
+        write(out_json, json(&key)?)
+    })
+}
+
+/// Signs the inputs of a base64 PSBT that belong to the wallet, returned as base64
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_bitcoin_sign_psbt(
+    signer: *const BitcoinSigner,
+    psbt_base64: *const c_char,
+    out: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let signed = handle(signer, "signer")?.sign_psbt(text(psbt_base64, "psbt")?)?;
+        write(out, c_string(signed)?)
+    })
+}
+
+/// # Safety
+/// `signer` must come from this library and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn era_bitcoin_free(signer: *mut BitcoinSigner) {
+    if !signer.is_null() {
+        drop(Box::from_raw(signer));
+    }
+}
+
+/// The keys and base58 address of the solana signer, returned as a JSON `KeyObject`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_solana_key(
+    signer: *const SolanaSigner,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| write(out_json, json(&handle(signer, "signer")?.get_key()?)?))
+}
+
+/// Signs a serialized legacy or v0 message, the signature is 64 bytes
+///
+/// # Safety
+/// Pointers must be valid for their C type, `message` for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn era_solana_sign_message(
+    signer: *const SolanaSigner,
+    message: *const u8,
+    len: usize,
+    out: *mut EraBytes,
+) -> i32 {
+    call(|| {
+        let message = slice(message, len, "message")?;
+        let signature = handle(signer, "signer")?.sign_message(message)?;
+        write(out, bytes(signature))
+    })
+}
+
+/// Signs a serialized transaction, returned with the signature in the slot of the signer
+///
+/// # Safety
+/// Pointers must be valid for their C type, `transaction` for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn era_solana_sign_transaction(
+    signer: *const SolanaSigner,
+    transaction: *const u8,
+    len: usize,
+    out: *mut EraBytes,
+) -> i32 {
+    call(|| {
+        let transaction = slice(transaction, len, "transaction")?;
+        let signed = handle(signer, "signer")?.sign_transaction(transaction)?;
+        write(out, bytes(signed))
+    })
+}
+
+/// Signs a UTF-8 message in the solana off-chain message format, the signature is 64 bytes
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_solana_sign_offchain_message(
+    signer: *const SolanaSigner,
+    message: *const c_char,
+    out: *mut EraBytes,
+) -> i32 {
+    call(|| {
+        let message = text(message, "message")?;
+        let signature = handle(signer, "signer")?.sign_offchain_message(message)?;
+        write(out, bytes(signature))
+    })
+}
+
+/// # Safety
+/// `signer` must come from this library and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn era_solana_free(signer: *mut SolanaSigner) {
+    if !signer.is_null() {
+        drop(Box::from_raw(signer));
+    }
+}
+
+/// The keys and bech32 address of the cosmos signer, returned as a JSON `KeyObject`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_cosmos_key(
+    signer: *const CosmosSigner,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| write(out_json, json(&handle(signer, "signer")?.get_key()?)?))
+}
+
+/// Signs a SIGN_MODE_DIRECT `SignDoc`, returned as the JSON `StdSignature`
+///
+/// # Safety
+/// Pointers must be valid for their C type, `body_bytes` and `auth_info_bytes` for their
+/// lengths.
+#[no_mangle]
+pub unsafe extern "C" fn era_cosmos_sign_direct(
+    signer: *const CosmosSigner,
+    body_bytes: *const u8,
+    body_len: usize,
+    auth_info_bytes: *const u8,
+    auth_info_len: usize,
+    chain_id: *const c_char,
+    account_number: u64,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let doc = SignDoc {
+            body_bytes: slice(body_bytes, body_len, "body bytes")?.to_vec(),
+            auth_info_bytes: slice(auth_info_bytes, auth_info_len, "auth info bytes")?.to_vec(),
+            chain_id: text(chain_id, "chain id")?.into(),
+            account_number,
+        };
+        write(out_json, json(&handle(signer, "signer")?.sign_doc(&doc)?)?)
+    })
+}
+
+/// Signs an amino JSON `StdSignDoc`, returned as the JSON `StdSignature`
+///
+/// # Safety
+/// Pointers must be valid for their C type.
+#[no_mangle]
+pub unsafe extern "C" fn era_cosmos_sign_amino(
+    signer: *const CosmosSigner,
+    doc_json: *const c_char,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        let doc: serde_json::Value = parse(text(doc_json, "sign doc")?, "sign doc")?;
+        write(
+            out_json,
+            json(&handle(signer, "signer")?.sign_amino_doc(&doc)?)?,
+        )
+    })
+}
+
+/// # Safety
+/// `signer` must come from this library and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn era_cosmos_free(signer: *mut CosmosSigner) {
+    if !signer.is_null() {
+        drop(Box::from_raw(signer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_codes_are_stable() {
+        assert_eq!(status(ErrorCode::WrongPin), 1);
+        assert_eq!(status(ErrorCode::InvalidPath), 8);
+        assert_eq!(status(ErrorCode::Internal), 14);
+
+        let mut account = ptr::null_mut();
+        let status = unsafe { era_account_from_mnemonic(ptr::null(), &mut account) };
+        assert_eq!(status, super::status(ErrorCode::InvalidInput));
+        assert!(account.is_null());
+        let message = era_last_error();
+        assert_eq!(
+            unsafe { CStr::from_ptr(message) }.to_str().unwrap(),
+            "INVALID_INPUT: mnemonic is null"
+        );
+        unsafe { era_string_free(message) };
+    }
+}
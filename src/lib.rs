@@ -1,6 +1,14 @@
-mod chains;
-mod crypto;
-mod era;
+//! Cryptographic core of the ERA wallet: seed generation, shard splitting, sealing and recovery.
+//!
+//! The Rust API works on plain structs and returns `EraError`. The javascript bindings are a thin
+//! layer on top of it, compiled with the default `wasm` feature. A native consumer, e.g. the backend
+//! holding the system shards, depends on the crate with `default-features = false`.
+pub mod chains;
+pub mod crypto;
+pub mod era;
+pub mod errors;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ffi;
 
 use crypto::*;
 use era::*;
@@ -12,10 +20,26 @@ pub use base64::{engine::general_purpose, Engine};
 use bip32::*;
 use bip39::{Language, Mnemonic, MnemonicType, Seed};
 use hex::*;
+#[cfg(feature = "wasm")]
 use js_sys::Uint8Array;
 use reed_solomon_erasure::galois_8::ReedSolomon;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
 use serde_wasm_bindgen::to_value;
 pub use simple_crypt::{decrypt, encrypt};
 pub use sp_core::{crypto::Ss58Codec, sr25519, Pair};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
+
+pub use chains::bitcoin::BitcoinSigner;
+pub use chains::cosmos::CosmosSigner;
+pub use chains::ethereum::EthereumSigner;
+pub use chains::polkadot::PolkadotSigner;
+pub use chains::solana::SolanaSigner;
+pub use crypto::crypto::Ecdsa;
+pub use crypto::key::{Account, KeyLanguage, KeyLength};
+pub use crypto::oprf::{OprfServer, PinUnlock};
+pub use era::cryptography::KdfParams;
+pub use era::layout::{ShardScheme, ShardSlot, WalletLayout};
+pub use era::wallet::{BaseWallet, RecoveredShards, ResealedShard, Signer, WalletShards};
+pub use errors::{EraError, ErrorCode};
@@ -0,0 +1,195 @@
+/*
+ * Runs the C ABI against the vectors the wasm package and the Rust tests produce for the
+ * same wallet. Built and run by tests/ffi.rs, or by hand:
+ *
+ *   cargo build && cc tests/c/harness.c -Iinclude -Ltarget/debug -lera_js -o harness
+ *   LD_LIBRARY_PATH=target/debug ./harness
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "era.h"
+
+#define MNEMONIC "right pave sketch blanket across oppose route shell favorite domain comfort super"
+#define PROJECT_SHARD "RVJBUwIAAQACAAMAAgAAAADzgqcjdagQZg6euHnCRV8eAAAARUVSQUsBAAAAQAAAAAEAAAABd/kZ5GqgfYDtYmyOEXD8wifdtqk6+diUG1uETlXQZvqxqnoslu7zBuKcPr2/RbGVOiDI+l2GXrniTVLf"
+#define SYSTEM_SHARD "RVJBUwIAAAACAAMAAwAAAADzgqcjdagQZg6euHnCRV8eAAAACB7fG6wTeZ9HL+aTwlY+ghc="
+#define SEED_HASH "70ce7b911d1f3e058a77671790fd5bef44f49fbca69a27799d7d54d42160adc9"
+#define POLKADOT_ADDRESS "\"address\":\"1pXuu6r5GxhpJxx8Mk2YvS6zB5sVR8dDs7JzeJMrXDX8QUX\""
+#define ETHEREUM_KEY "\"private_key\":\"0x487af1decc8d53b1af50298a380467d64a652ecf840390de402f5f3ae83a8e57\""
+
+static int failures = 0;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        char *error = era_last_error();
+        fprintf(stderr, "FAIL: %s (%s)\n", what, error ? error : "no error");
+        era_string_free(error);
+        failures++;
+    }
+}
+
+static EraWallet *wallet(void) {
+    EraWallet *wallet = NULL;
+    check(era_wallet_new(NULL, &wallet) == ERA_OK, "era_wallet_new");
+    check(era_wallet_insert_shard(wallet, "project", PROJECT_SHARD) == ERA_OK, "insert project shard");
+    check(era_wallet_insert_shard(wallet, "system", SYSTEM_SHARD) == ERA_OK, "insert system shard");
+    return wallet;
+}
+
+int main(void) {
+    EraWallet *base = wallet();
+    EraSigner *signer = NULL;
+    check(era_wallet_build_signer(base, "222333", false, &signer) == ERA_OK, "era_wallet_build_signer");
+
+    bool valid = false;
+    check(era_wallet_verify_key(base, SEED_HASH, &valid) == ERA_OK && valid, "era_wallet_verify_key");
+
+    EraBytes entropy = {0};
+    check(era_wallet_reconstruct(base, &entropy) == ERA_OK && entropy.len == 16, "era_wallet_reconstruct");
+    era_bytes_free(entropy);
+
+    char *mnemonic = NULL;
+    check(era_signer_mnemonic(signer, &mnemonic) == ERA_OK && strcmp(mnemonic, MNEMONIC) == 0,
+          "era_signer_mnemonic");
+    era_string_free(mnemonic);
+
+    EraPolkadotSigner *polkadot = NULL;
+    char *key = NULL;
+    check(era_signer_to_polkadot(signer, "//polkadot//0", &polkadot) == ERA_OK, "era_signer_to_polkadot");
+    check(era_polkadot_key(polkadot, &key) == ERA_OK && strstr(key, POLKADOT_ADDRESS) != NULL,
+          "era_polkadot_key");
+    era_string_free(key);
+
+    EraBytes signature = {0};
+    const uint8_t message[] = "era-js";
+    check(era_polkadot_sign(polkadot, message, sizeof(message) - 1, &signature) == ERA_OK &&
+              signature.len == 64,
+          "era_polkadot_sign");
+    era_bytes_free(signature);
+    check(era_polkadot_sign_extrinsic(polkadot,
+                                      "{\"call\":\"0x0000186572612d6a73\",\"nonce\":0,\"spec_version\":1003000,"
+                                      "\"transaction_version\":26,\"genesis_hash\":"
+                                      "\"0x91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3\"}",
+                                      &key) == ERA_OK &&
+              strstr(key, "\"extrinsic\":\"0x") != NULL,
+          "era_polkadot_sign_extrinsic");
+    era_string_free(key);
+    check(era_polkadot_set_network(polkadot, "kusama") == ERA_OK && era_polkadot_key(polkadot, &key) == ERA_OK &&
+              strstr(key, "\"ss58_prefix\":2") != NULL,
+          "era_polkadot_set_network");
+    era_string_free(key);
+    era_polkadot_free(polkadot);
+
+    check(era_ss58_reencode("1pXuu6r5GxhpJxx8Mk2YvS6zB5sVR8dDs7JzeJMrXDX8QUX", "42", &key) == ERA_OK &&
+              key[0] == '5',
+          "era_ss58_reencode");
+    char *info = NULL;
+    check(era_ss58_validate(key, "substrate", &info) == ERA_OK && strstr(info, "\"prefix\":42") != NULL,
+          "era_ss58_validate");
+    era_string_free(info);
+    check(era_ss58_validate(key, "polkadot", &info) == ERA_INVALID_INPUT, "era_ss58_validate checks the network");
+    era_string_free(key);
+
+    check(era_signer_to_substrate(signer, "ecdsa", "//polkadot//0", &polkadot) == ERA_OK, "era_signer_to_substrate");
+    check(era_polkadot_multi_signature(polkadot, message, sizeof(message) - 1, &signature) == ERA_OK &&
+              signature.len == 66 && signature.data[0] == 2,
+          "era_polkadot_multi_signature");
+    era_bytes_free(signature);
+    era_polkadot_free(polkadot);
+    check(era_signer_to_substrate(signer, "bls", "//polkadot//0", &polkadot) == ERA_INVALID_INPUT,
+          "era_signer_to_substrate rejects unknown schemes");
+
+    EraEthereumSigner *ethereum = NULL;
+    check(era_signer_to_ethereum(signer, "m/44'/60'/0'/0/0", &ethereum) == ERA_OK,
+          "era_signer_to_ethereum");
+    check(era_ethereum_key(ethereum, &key) == ERA_OK && strstr(key, ETHEREUM_KEY) != NULL,
+          "era_ethereum_key");
+    era_string_free(key);
+    char *hex = NULL;
+    check(era_ethereum_personal_sign(ethereum, message, sizeof(message) - 1, &hex) == ERA_OK &&
+              strlen(hex) == 132,
+          "era_ethereum_personal_sign");
+    era_string_free(hex);
+    check(era_ethereum_sign_transaction(ethereum,
+                                        "{\"type\":\"eip1559\",\"chain_id\":1,\"nonce\":0,"
+                                        "\"max_fee_per_gas\":2,\"max_priority_fee_per_gas\":1,"
+                                        "\"gas_limit\":21000,\"to\":\"0x3535353535353535353535353535353535353535\"}",
+                                        &key) == ERA_OK &&
+              strstr(key, "\"raw\":\"0x02") != NULL,
+          "era_ethereum_sign_transaction");
+    era_string_free(key);
+    era_ethereum_free(ethereum);
+
+    EraBitcoinSigner *bitcoin = NULL;
+    check(era_signer_to_bitcoin(signer, "mainnet", &bitcoin) == ERA_OK, "era_signer_to_bitcoin");
+    check(era_bitcoin_key(bitcoin, "p2tr", 0, &key) == ERA_OK && strstr(key, "\"address\":\"bc1p") != NULL &&
+              strstr(key, "\"path\":\"m/86'/0'/0'/0/0\"") != NULL,
+          "era_bitcoin_key");
+    era_string_free(key);
+    check(era_bitcoin_sign_psbt(bitcoin, "cHNidP8=", &key) == ERA_INVALID_INPUT,
+          "era_bitcoin_sign_psbt rejects bad PSBTs");
+    era_bitcoin_free(bitcoin);
+
+    EraSolanaSigner *solana = NULL;
+    check(era_signer_to_solana(signer, 0, &solana) == ERA_OK, "era_signer_to_solana");
+    check(era_solana_key(solana, &key) == ERA_OK && strstr(key, "\"path\":\"m/44'/501'/0'/0'\"") != NULL,
+          "era_solana_key");
+    era_string_free(key);
+    check(era_solana_sign_offchain_message(solana, "era-js", &signature) == ERA_OK && signature.len == 64,
+          "era_solana_sign_offchain_message");
+    era_bytes_free(signature);
+    check(era_solana_sign_message(solana, message, sizeof(message) - 1, &signature) == ERA_INVALID_INPUT,
+          "era_solana_sign_message rejects foreign messages");
+    era_solana_free(solana);
+
+    EraCosmosSigner *cosmos = NULL;
+    check(era_signer_to_cosmos(signer, "osmo", 0, 118, &cosmos) == ERA_OK, "era_signer_to_cosmos");
+    check(era_cosmos_key(cosmos, &key) == ERA_OK && strstr(key, "\"address\":\"osmo1") != NULL,
+          "era_cosmos_key");
+    EraCosmosSigner *cosmos_default = NULL;
+    char *key_default = NULL;
+    check(era_signer_to_cosmos(signer, "osmo", 0, 0, &cosmos_default) == ERA_OK &&
+              era_cosmos_key(cosmos_default, &key_default) == ERA_OK && strcmp(key, key_default) == 0,
+          "era_signer_to_cosmos defaults coin type 0 to 118");
+    era_string_free(key_default);
+    era_cosmos_free(cosmos_default);
+    era_string_free(key);
+    check(era_cosmos_sign_direct(cosmos, message, sizeof(message) - 1, message, sizeof(message) - 1,
+                                 "osmosis-1", 7, &key) == ERA_OK &&
+              strstr(key, "tendermint/PubKeySecp256k1") != NULL,
+          "era_cosmos_sign_direct");
+    era_string_free(key);
+    check(era_cosmos_sign_amino(cosmos,
+                                "{\"chain_id\":\"osmosis-1\",\"account_number\":\"7\",\"sequence\":\"0\","
+                                "\"fee\":{\"gas\":\"200000\",\"amount\":[]},\"msgs\":[],\"memo\":\"\"}",
+                                &key) == ERA_OK,
+          "era_cosmos_sign_amino");
+    era_string_free(key);
+    era_cosmos_free(cosmos);
+    era_signer_free(signer);
+    era_wallet_free(base);
+
+    EraAccount *account = NULL;
+    check(era_account_from_mnemonic(MNEMONIC, &account) == ERA_OK, "era_account_from_mnemonic");
+    check(era_account_derive(account, "m/44'/60'/0'/0/0", &key) == ERA_OK && strstr(key, ETHEREUM_KEY) != NULL,
+          "era_account_derive");
+    era_string_free(key);
+    check(era_account_derive(account, "m/x", &key) == ERA_INVALID_PATH, "era_account_derive rejects bad paths");
+    era_account_free(account);
+
+    base = wallet();
+    check(era_wallet_build_signer(base, "222334", false, &signer) == ERA_WRONG_PIN, "wrong pin");
+    char *error = era_last_error();
+    check(error != NULL && strncmp(error, "WRONG_PIN: ", 11) == 0, "era_last_error");
+    era_string_free(error);
+    check(era_wallet_build_signer(base, "222333", false, &signer) == ERA_OK && era_last_error() == NULL,
+          "era_last_error is cleared by a success");
+    era_signer_free(signer);
+    era_wallet_free(base);
+
+    if (failures == 0) {
+        printf("ok\n");
+    }
+    return failures == 0 ? EXIT_SUCCESS : EXIT_FAILURE;
+}
@@ -0,0 +1,102 @@
+//! The `era` command line tool, run as a subprocess.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use era_js::{encrypt, general_purpose, Engine};
+use serde_json::Value;
+
+const MNEMONIC: &str =
+    "right pave sketch blanket across oppose route shell favorite domain comfort super";
+const KDF: &str = r#"{"memory_kib":64,"iterations":1,"parallelism":1}"#;
+
+fn era(args: &[&str], stdin: &str) -> (bool, Value) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_era"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.success(),
+        serde_json::from_slice(&output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_split_combine_and_derive() {
+    let stdin = format!("{}\n222333\npassword1234567\n", MNEMONIC);
+    let (ok, shards) = era(&["split", "--kdf", KDF], &stdin);
+    assert!(ok);
+    let project = format!("project={}", shards["project_shard"].as_str().unwrap());
+    let system = format!("system={}", shards["system_shard"].as_str().unwrap());
+    let seed_hash = shards["seed_hash"].as_str().unwrap();
+
+    let args = ["--shard", &project, "--shard", &system, "--open", "project"];
+    let (ok, combined) = era(&[&["combine"], &args[..]].concat(), "222333\n");
+    assert!(ok);
+    assert_eq!(combined["seed_hash"], seed_hash);
+
+    let (ok, verified) = era(
+        &[&["verify", "--seed-hash", seed_hash], &args[..]].concat(),
+        "222333\n",
+    );
+    assert!(ok);
+    assert_eq!(verified["valid"], true);
+
+    let (ok, error) = era(&[&["combine"], &args[..]].concat(), "222334\n");
+    assert!(!ok);
+    assert_eq!(error["error"]["code"], "WRONG_PIN");
+
+    let (ok, key) = era(&["derive", "polkadot"], MNEMONIC);
+    assert!(ok);
+    assert_eq!(key["path"], "//polkadot//0");
+    let (ok, key) = era(&["derive", "bip32", "--path", "m/44'/60'/0'/0/0"], MNEMONIC);
+    assert!(ok);
+    assert_eq!(key["path"], "m/44'/60'/0'/0/0");
+}
+
+#[test]
+fn test_encrypt_and_decrypt() {
+    let shard = general_purpose::STANDARD.encode(b"era shard");
+    let (ok, sealed) = era(
+        &["encrypt", "--kdf", KDF],
+        &format!("password1234567\n{}\n", shard),
+    );
+    assert!(ok);
+    let sealed = sealed["shard"].as_str().unwrap();
+
+    let (ok, opened) = era(&["decrypt", "--shard", sealed], "password1234567\n");
+    assert!(ok);
+    assert_eq!(opened["shard"], shard);
+    assert!(opened.get("envelope").is_none());
+
+    let (ok, error) = era(&["decrypt", "--shard", sealed], "password1234568\n");
+    assert!(!ok);
+    assert_eq!(error["error"]["code"], "WRONG_PIN");
+
+    // shards sealed before Argon2id
+    let legacy = general_purpose::STANDARD.encode(encrypt(b"era shard", b"222333").unwrap());
+    let (ok, opened) = era(&["decrypt", "--shard", &legacy], "222333\n");
+    assert!(ok);
+    assert_eq!(opened["shard"], shard);
+
+    // an enveloped shard reports its header
+    let stdin = format!("{}\n222333\npassword1234567\n", MNEMONIC);
+    let (_, shards) = era(&["split", "--kdf", KDF], &stdin);
+    let project = shards["project_shard"].as_str().unwrap();
+    let (ok, opened) = era(&["decrypt", "--shard", project], "222333\n");
+    assert!(ok);
+    assert_eq!(opened["envelope"]["wallet_id"], shards["wallet_id"]);
+
+    let (ok, error) = era(&["encrypt", "--kdf", KDF], "password1234567\n");
+    assert!(!ok);
+    assert_eq!(error["error"]["code"], "INVALID_INPUT");
+}
@@ -0,0 +1,62 @@
+//! The C ABI: the header stays in sync with `src/ffi.rs` and the C harness passes.
+
+use std::path::Path;
+use std::process::Command;
+
+use era_js::ErrorCode;
+
+#[test]
+fn test_header_matches_ffi() {
+    let header = std::fs::read_to_string("include/era.h").unwrap();
+    let source = std::fs::read_to_string("src/ffi.rs").unwrap();
+
+    let functions: Vec<&str> = source
+        .split("extern \"C\" fn ")
+        .skip(1)
+        .map(|rest| rest.split('(').next().unwrap())
+        .collect();
+    assert!(!functions.is_empty());
+    for function in functions {
+        assert!(
+            header.contains(&format!(" {}(", function)),
+            "{} is missing from include/era.h",
+            function
+        );
+    }
+    for (position, code) in ErrorCode::ALL.iter().enumerate() {
+        let define = format!("#define ERA_{} {}\n", code.as_str(), position + 1);
+        assert!(header.contains(&define), "missing `{}`", define.trim());
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_c_harness() {
+    // cargo builds the cdylib next to the test binary, in target/debug/deps
+    let exe = std::env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap();
+    assert!(
+        lib_dir.join("libera_js.so").exists(),
+        "libera_js.so was not built next to {}",
+        exe.display()
+    );
+    let harness = Path::new(env!("CARGO_TARGET_TMPDIR")).join("era_harness");
+    let compiled = Command::new("cc")
+        .args(["tests/c/harness.c", "-Iinclude", "-lera_js", "-o"])
+        .arg(&harness)
+        .arg(format!("-L{}", lib_dir.display()))
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .status();
+    let compiled = compiled.expect("the C harness needs a C compiler, `cc`");
+    assert!(compiled.success(), "the C harness does not compile");
+    // load the library next to this test, not one found on the loader path
+    let output = Command::new(&harness)
+        .env_remove("LD_LIBRARY_PATH")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,188 @@
+//! The Rust API, as used by a native consumer without the javascript bindings.
+
+use era_js::{
+    general_purpose, sr25519, Account, BaseWallet, Ecdsa, Engine, ErrorCode, KdfParams, KeyLength,
+    Pair, Signer, WalletLayout,
+};
+
+// cheap key derivation to keep the test fast
+const TEST_KDF: KdfParams = KdfParams {
+    memory_kib: 64,
+    iterations: 1,
+    parallelism: 1,
+};
+
+fn wallet(shards: &[(&str, &str)]) -> BaseWallet {
+    let mut wallet = BaseWallet::with_layout(WalletLayout::default()).unwrap();
+    for (name, shard) in shards {
+        let shard = general_purpose::STANDARD.decode(shard).unwrap();
+        wallet.insert_shard(name, shard).unwrap();
+    }
+    wallet
+}
+
+#[test]
+fn test_wallet_without_javascript() {
+    let created = BaseWallet::create_wallet(
+        "222333",
+        "password1234567",
+        KeyLength::Words12,
+        &WalletLayout::default(),
+        &TEST_KDF,
+    )
+    .unwrap();
+
+    let mut base = wallet(&[
+        (WalletLayout::PROJECT, &created.project_shard),
+        (WalletLayout::SYSTEM, &created.system_shard),
+    ]);
+    let signer = base.build_signer("222333".into(), None).unwrap();
+    assert!(base.verify_key(created.seed_hash.clone()).unwrap());
+
+    let account: Account = signer.as_mnemonic().parse().unwrap();
+    let key = account.generate_extended_key("m/44'/60'/0'/0/0").unwrap();
+    assert!(key.private_key.starts_with("0x"));
+    let polkadot = signer
+        .to_polkadot_signer(Some("//polkadot//0".into()))
+        .unwrap();
+    assert!(polkadot.get_key().is_ok());
+    let ed25519 = signer
+        .to_substrate_signer("ed25519", Some("//polkadot//0".into()))
+        .unwrap();
+    assert_eq!(ed25519.multi_signature(b"era-js").unwrap()[0], 0);
+    assert_ne!(
+        ed25519.get_key().unwrap().address,
+        polkadot.get_key().unwrap().address
+    );
+
+    let mut base = wallet(&[(WalletLayout::SYSTEM, &created.system_shard)]);
+    let error = base.build_signer("222333".into(), None).err().unwrap();
+    assert_eq!(error.code(), ErrorCode::MissingShard);
+}
+
+#[test]
+fn test_ethereum_accounts() {
+    let phrase = "test test test test test test test test test test test junk";
+    let account: Account = phrase.parse().unwrap();
+    let signer = Signer::new(account.to_bytes(), phrase.into());
+
+    let first = signer.to_ethereum_signer("m/44'/60'/0'/0/0").unwrap();
+    assert_eq!(
+        first.address().unwrap(),
+        "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+    );
+    let second = signer.to_ethereum_signer("m/44'/60'/0'/0/1").unwrap();
+    assert_eq!(
+        second.address().unwrap(),
+        "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+    );
+    assert_eq!(second.get_key().unwrap().path, "m/44'/60'/0'/0/1");
+
+    let error = signer.to_ethereum_signer("m/x").err().unwrap();
+    assert_eq!(error.code(), ErrorCode::InvalidPath);
+}
+
+#[test]
+fn test_bitcoin_addresses() {
+    let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    let account: Account = phrase.parse().unwrap();
+    let signer = Signer::new(account.to_bytes(), phrase.into());
+
+    let bitcoin = signer.to_bitcoin_signer("mainnet").unwrap();
+    assert_eq!(
+        bitcoin.address("p2wpkh", 1).unwrap(),
+        "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g"
+    );
+    let error = signer.to_bitcoin_signer("litecoin").err().unwrap();
+    assert_eq!(error.code(), ErrorCode::InvalidInput);
+}
+
+#[test]
+fn test_substrate_secret_uris() {
+    let phrase = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+    let account: Account = phrase.parse().unwrap();
+    let signer = Signer::new(account.to_bytes(), phrase.into());
+
+    for (uri, path) in [
+        ("//Alice", "//Alice"),
+        ("//polkadot/007", "//polkadot/7"),
+        ("//0/staking///hunter2", "//0/staking"),
+    ]
+    .iter()
+    {
+        let key = signer
+            .to_polkadot_signer(Some(uri.to_string()))
+            .unwrap()
+            .get_key()
+            .unwrap();
+        let (pair, _) =
+            sr25519::Pair::from_string_with_seed(&format!("{}{}", phrase, uri), None).unwrap();
+        assert_eq!(key.public_key, format!("0x{}", hex::encode(pair.public())));
+        assert_eq!(key.path, *path);
+    }
+
+    // the well-known development account
+    let alice = signer
+        .to_polkadot_signer(Some("//Alice".into()))
+        .unwrap()
+        .get_key()
+        .unwrap();
+    assert_eq!(
+        alice.public_key,
+        "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d"
+    );
+    assert!(signer
+        .to_substrate_signer("ecdsa", Some("//Alice/soft".into()))
+        .is_err());
+    let error = signer
+        .to_polkadot_signer(Some("polkadot//0".into()))
+        .err()
+        .unwrap();
+    assert_eq!(error.code(), ErrorCode::InvalidPath);
+
+    // no derivation is the account of earlier versions, an empty one is not the root account
+    let default = signer.to_polkadot_signer(None).unwrap().get_key().unwrap();
+    let polkadot = signer
+        .to_polkadot_signer(Some("//polkadot//0".into()))
+        .unwrap()
+        .get_key()
+        .unwrap();
+    assert_eq!(default.address, polkadot.address);
+    assert_eq!(default.path, "//polkadot//0");
+    let error = signer.to_polkadot_signer(Some("".into())).err().unwrap();
+    assert_eq!(error.code(), ErrorCode::InvalidPath);
+}
+
+#[test]
+fn test_solana_accounts() {
+    let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    let account: Account = phrase.parse().unwrap();
+    let signer = Signer::new(account.to_bytes(), phrase.into());
+
+    let solana = signer.to_solana_signer(0).unwrap();
+    assert_eq!(
+        solana.address().unwrap(),
+        "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+    );
+    assert_eq!(solana.get_key().unwrap().path, "m/44'/501'/0'/0'");
+}
+
+#[test]
+fn test_cosmos_accounts() {
+    let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    let account: Account = phrase.parse().unwrap();
+    let signer = Signer::new(account.to_bytes(), phrase.into());
+
+    let cosmos = signer.to_cosmos_signer("cosmos", 0, None).unwrap();
+    assert_eq!(
+        cosmos.address().unwrap(),
+        "cosmos19rl4cm2hmr8afy4kldpxz3fka4jguq0auqdal4"
+    );
+    let osmosis = signer.to_cosmos_signer("osmo", 0, None).unwrap();
+    assert!(osmosis.address().unwrap().starts_with("osmo1"));
+    let secret = signer.to_cosmos_signer("secret", 0, Some(529)).unwrap();
+    assert_eq!(secret.get_key().unwrap().path, "m/44'/529'/0'/0/0");
+
+    let error = signer.to_cosmos_signer("Cosmos", 0, None).err().unwrap();
+    assert_eq!(error.code(), ErrorCode::InvalidInput);
+}
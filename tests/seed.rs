@@ -1,4 +1,4 @@
-#![allow(unused_variables)]
+#![allow(unused_variables, clippy::eq_op)]
 fn main() {
     use wasm_bindgen_test::*;
 
@@ -11,3 +11,36 @@ wasm_bindgen_test_configure!(run_in_browser);
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+#[wasm_bindgen_test]
+fn sign_transaction_with_wide_bigint() {
+    use era_js::EthereumSigner;
+    use js_sys::{BigInt, Reflect, JSON};
+    use wasm_bindgen::JsValue;
+
+    let key = vec![0x46; 32];
+    let signer = EthereumSigner::new(key, "".into());
+    let request = |value: JsValue| {
+        let transaction = JSON::parse(
+            r#"{"type":"eip1559","chainId":1,"nonce":0,"maxPriorityFeePerGas":"1000000000",
+                "maxFeePerGas":"30000000000","gas":21000,
+                "to":"0x3535353535353535353535353535353535353535"}"#,
+        )
+        .unwrap();
+        Reflect::set(&transaction, &"value".into(), &value).unwrap();
+        let signed = signer.sign_transaction(transaction).unwrap();
+        Reflect::get(&signed, &"raw".into())
+            .unwrap()
+            .as_string()
+            .unwrap()
+    };
+
+    // parseEther("100"), past the u64::MAX that serde-wasm-bindgen reads by itself
+    let ether = BigInt::new(&"100000000000000000000".into()).unwrap();
+    assert_eq!(
+        request(ether.into()),
+        request("100000000000000000000".into())
+    );
+    let small = BigInt::from(21000u64);
+    assert_eq!(request(small.into()), request("21000".into()));
+}